@@ -1,5 +1,7 @@
 use std::{any::Any, fmt::Debug};
 
+pub use neurotick_derive::Activation;
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -19,6 +21,13 @@ impl<T: 'static + Activation> AsAny for T {
     }
 }
 
+/**
+ * The function of an activation, used by #[derive(Activation)] to implement Activation::apply
+ */
+pub trait ActivationFn {
+    fn activate(&self, array: &NDMatrix) -> NDMatrix;
+}
+
 pub trait ActivationSerialize {
     fn as_serialized(&self) -> ActivationSerialised;
 }
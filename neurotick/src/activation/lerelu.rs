@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{matrix::nmatrix::NDMatrix, utils::json_wrap::JsonWrap};
+use crate::{
+    matrix::nmatrix::NDMatrix, serial::registry::ActivationRegistration,
+    utils::json_wrap::JsonWrap,
+};
 
 use super::abs::{Activation, ActivationSerialised, ActivationVirtual};
 
@@ -46,3 +49,10 @@ impl ActivationVirtual for LeakyReLu {
         return Self::NAME;
     }
 }
+
+inventory::submit! {
+    ActivationRegistration {
+        name: LeakyReLu::NAME,
+        create: <LeakyReLu as ActivationVirtual>::from_json,
+    }
+}
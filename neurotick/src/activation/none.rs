@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{matrix::nmatrix::NDMatrix, utils::json_wrap::JsonWrap};
+use crate::{
+    matrix::nmatrix::NDMatrix, serial::registry::ActivationRegistration,
+    utils::json_wrap::JsonWrap,
+};
 
 use super::abs::{Activation, ActivationSerialised, ActivationVirtual};
 
@@ -41,3 +44,10 @@ impl ActivationVirtual for NoneAct {
         return Self::NAME;
     }
 }
+
+inventory::submit! {
+    ActivationRegistration {
+        name: NoneAct::NAME,
+        create: <NoneAct as ActivationVirtual>::from_json,
+    }
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{matrix::nmatrix::NDMatrix, utils::json_wrap::JsonWrap};
+use crate::{
+    matrix::nmatrix::NDMatrix, serial::registry::ActivationRegistration,
+    utils::json_wrap::JsonWrap,
+};
 
 use super::abs::{Activation, ActivationSerialised, ActivationVirtual};
 
@@ -44,3 +47,10 @@ impl ActivationVirtual for ReLu {
         return Self::NAME;
     }
 }
+
+inventory::submit! {
+    ActivationRegistration {
+        name: ReLu::NAME,
+        create: <ReLu as ActivationVirtual>::from_json,
+    }
+}
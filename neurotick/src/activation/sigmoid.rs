@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     matrix::nmatrix::NDMatrix,
+    serial::registry::ActivationRegistration,
     utils::{json_wrap::JsonWrap, math::fast_math::FMath},
 };
 
@@ -45,3 +46,10 @@ impl ActivationVirtual for Sigmoid {
         return Self::NAME;
     }
 }
+
+inventory::submit! {
+    ActivationRegistration {
+        name: Sigmoid::NAME,
+        create: <Sigmoid as ActivationVirtual>::from_json,
+    }
+}
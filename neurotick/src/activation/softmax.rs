@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{matrix::nmatrix::NDMatrix, utils::{json_wrap::JsonWrap, math::matrix_math::MatrixMath}};
+use crate::{matrix::nmatrix::NDMatrix, serial::registry::ActivationRegistration, utils::{json_wrap::JsonWrap, math::matrix_math::MatrixMath}};
 
 use super::abs::{Activation, ActivationVirtual, ActivationSerialised};
 
@@ -41,4 +41,11 @@ impl ActivationVirtual for SoftMax {
     fn type_name() -> &'static str {
         return Self::NAME
     }
-}
\ No newline at end of file
+}
+
+inventory::submit! {
+    ActivationRegistration {
+        name: SoftMax::NAME,
+        create: <SoftMax as ActivationVirtual>::from_json,
+    }
+}
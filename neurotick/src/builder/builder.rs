@@ -3,14 +3,18 @@ use std::fmt::Debug;
 use indexmap::IndexMap;
 
 use crate::{
-    layer::abs::{LayerPropagateEnum, LayerRef},
+    layer::abs::{LayerPropagateEnum, LayerRef, Mode},
     map,
     matrix::meta::node::LayerType,
     model::model::Model,
 };
 
-use super::graph_elements::{
-    BuilderNode, DeadEndStruct, ModelPropagationNode, MultipleParentStruct, SingleParentStruct,
+use super::{
+    graph_elements::{
+        BuilderNode, DeadEndStruct, ModelPropagationNode, MultipleParentStruct,
+        SingleParentStruct,
+    },
+    graph_export::GraphExport,
 };
 
 /**
@@ -84,10 +88,49 @@ impl ModelBuilder {
             output_layer_to_data_name: outputs,
             sequential_prop: serialized,
             builder_ref: builder_ref,
+            mode: Mode::default(),
         };
     }
 }
 
+impl ModelBuilder {
+    /**
+     * Graph exporter with layer shapes, which are only available before building
+     */
+    pub fn graph_export(&self) -> GraphExport {
+        let graph: IndexMap<String, BuilderNode> = self
+            .graph
+            .iter()
+            .map(|n| (n.1.layer_name(), n.1.clone()))
+            .collect();
+        let inputs: IndexMap<String, String> = self
+            .inputs
+            .iter()
+            .map(|i| (self.graph.get(i.0).unwrap().layer_name(), i.1.clone()))
+            .collect();
+        let outputs: IndexMap<String, String> = self
+            .outputs
+            .iter()
+            .map(|o| (self.graph.get(o.0).unwrap().layer_name(), o.1.clone()))
+            .collect();
+        let shapes = self
+            .graph
+            .iter()
+            .map(|n| (n.1.layer_name(), n.0.get_shape()))
+            .collect();
+
+        return GraphExport::new(graph, inputs, outputs).with_shapes(shapes);
+    }
+
+    pub fn to_dot(&self) -> String {
+        return self.graph_export().to_dot();
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        return self.graph_export().to_mermaid();
+    }
+}
+
 impl ModelBuilder {
     /**
      * Helper constant for skipping definition if key in a single branched input/output model
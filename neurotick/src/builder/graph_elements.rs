@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
 use crate::{
-    layer::abs::{LayerMultiInput, LayerSingleInput},
+    layer::abs::{LayerMultiInput, LayerPropagateEnum, LayerSingleInput, Mode},
     utils::json_wrap::JsonWrap,
 };
 
@@ -74,6 +74,54 @@ pub enum ModelPropagationNode {
 }
 
 impl ModelPropagationNode {
+    /**
+     * Pairs a created layer with the parents of its builder node
+     */
+    pub fn from_builder(node: &BuilderNode, layer: LayerPropagateEnum) -> ModelPropagationNode {
+        return match node {
+            BuilderNode::DeadEnd(_) => {
+                let cast = if let LayerPropagateEnum::SingleInput(single) = layer {
+                    single
+                } else {
+                    panic!("Not a dead end builder node")
+                };
+                ModelPropagationNode::DeadEnd(cast)
+            }
+            BuilderNode::SingleParent(c) => {
+                let cast = if let LayerPropagateEnum::SingleInput(single) = layer {
+                    single
+                } else {
+                    panic!("Not a single input builder node")
+                };
+                ModelPropagationNode::SingleInput(c.parent_name.clone(), cast)
+            }
+            BuilderNode::MultipleParent(c) => {
+                let cast = if let LayerPropagateEnum::MultipleInput(single) = layer {
+                    single
+                } else {
+                    panic!("Not a multi input builder node")
+                };
+                ModelPropagationNode::MultipleInput(c.parent_names.clone(), cast)
+            }
+        };
+    }
+
+    pub fn set_mode(&mut self, mode: Mode) {
+        match self {
+            ModelPropagationNode::DeadEnd(r) => r.set_mode(mode),
+            ModelPropagationNode::SingleInput(_, r) => r.set_mode(mode),
+            ModelPropagationNode::MultipleInput(_, r) => r.set_mode(mode),
+        };
+    }
+
+    pub fn non_trainable_fields(&self) -> &'static [&'static str] {
+        return match self {
+            ModelPropagationNode::DeadEnd(r) => r.non_trainable_fields(),
+            ModelPropagationNode::SingleInput(_, r) => r.non_trainable_fields(),
+            ModelPropagationNode::MultipleInput(_, r) => r.non_trainable_fields(),
+        };
+    }
+
     pub fn to_json(&self) -> JsonWrap {
         return match self {
             ModelPropagationNode::DeadEnd(r) => r.to_json(),
@@ -0,0 +1,196 @@
+use indexmap::IndexMap;
+
+use crate::matrix::meta::shape::Shape;
+
+use super::graph_elements::BuilderNode;
+
+/**
+ * Renders a builder node graph as text diagrams (Graphviz DOT, Mermaid).
+ * Layers are keyed by their layer name, io maps are layer name -> data name
+ */
+pub struct GraphExport {
+    graph: IndexMap<String, BuilderNode>,
+    inputs: IndexMap<String, String>,
+    outputs: IndexMap<String, String>,
+    /**
+     * Optional (features, size) per layer name, only known before instancing
+     */
+    shapes: IndexMap<String, (Shape, Shape)>,
+}
+
+impl GraphExport {
+    pub fn new(
+        graph: IndexMap<String, BuilderNode>,
+        inputs: IndexMap<String, String>,
+        outputs: IndexMap<String, String>,
+    ) -> GraphExport {
+        return GraphExport {
+            graph,
+            inputs,
+            outputs,
+            shapes: IndexMap::new(),
+        };
+    }
+
+    pub fn with_shapes(mut self, shapes: IndexMap<String, (Shape, Shape)>) -> GraphExport {
+        self.shapes = shapes;
+        return self;
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut lines: Vec<String> = vec![];
+        lines.push("digraph model {".to_string());
+        lines.push("    rankdir=TB;".to_string());
+
+        self.graph.iter().for_each(|(name, node)| {
+            let label = self
+                .node_label(name, node)
+                .iter()
+                .map(|part| Self::escape_dot(part))
+                .collect::<Vec<_>>()
+                .join("\\n");
+            lines.push(format!(
+                "    \"{}\" [label=\"{}\", shape=box];",
+                Self::escape_dot(name),
+                label
+            ));
+        });
+
+        self.inputs.iter().for_each(|(layer, data)| {
+            let id = format!("input:{}", data);
+            lines.push(format!(
+                "    \"{}\" [label=\"{}\", shape=ellipse];",
+                Self::escape_dot(&id),
+                Self::escape_dot(data)
+            ));
+            lines.push(format!(
+                "    \"{}\" -> \"{}\";",
+                Self::escape_dot(&id),
+                Self::escape_dot(layer)
+            ));
+        });
+
+        self.graph.iter().for_each(|(name, node)| {
+            Self::parents(node).iter().for_each(|parent| {
+                lines.push(format!(
+                    "    \"{}\" -> \"{}\";",
+                    Self::escape_dot(parent),
+                    Self::escape_dot(name)
+                ));
+            });
+        });
+
+        self.outputs.iter().for_each(|(layer, data)| {
+            let id = format!("output:{}", data);
+            lines.push(format!(
+                "    \"{}\" [label=\"{}\", shape=ellipse];",
+                Self::escape_dot(&id),
+                Self::escape_dot(data)
+            ));
+            lines.push(format!(
+                "    \"{}\" -> \"{}\";",
+                Self::escape_dot(layer),
+                Self::escape_dot(&id)
+            ));
+        });
+
+        lines.push("}".to_string());
+        return lines.join("\n");
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        // mermaid ids are restricted, layers are referenced by their graph index
+        let ids: IndexMap<&String, String> = self
+            .graph
+            .keys()
+            .enumerate()
+            .map(|(index, name)| (name, format!("n{}", index)))
+            .collect();
+
+        let mut lines: Vec<String> = vec![];
+        lines.push("flowchart TD".to_string());
+
+        self.graph.iter().for_each(|(name, node)| {
+            let label = self
+                .node_label(name, node)
+                .iter()
+                .map(|part| Self::escape_mermaid(part))
+                .collect::<Vec<_>>()
+                .join("<br/>");
+            lines.push(format!("    {}[\"{}\"]", ids.get(name).unwrap(), label));
+        });
+
+        self.inputs
+            .iter()
+            .enumerate()
+            .for_each(|(index, (layer, data))| {
+                lines.push(format!(
+                    "    i{}([\"{}\"]) --> {}",
+                    index,
+                    Self::escape_mermaid(data),
+                    ids.get(layer).unwrap()
+                ));
+            });
+
+        self.graph.iter().for_each(|(name, node)| {
+            Self::parents(node).iter().for_each(|parent| {
+                lines.push(format!(
+                    "    {} --> {}",
+                    ids.get(parent).unwrap(),
+                    ids.get(name).unwrap()
+                ));
+            });
+        });
+
+        self.outputs
+            .iter()
+            .enumerate()
+            .for_each(|(index, (layer, data))| {
+                lines.push(format!(
+                    "    {} --> o{}([\"{}\"])",
+                    ids.get(layer).unwrap(),
+                    index,
+                    Self::escape_mermaid(data)
+                ));
+            });
+
+        return lines.join("\n");
+    }
+
+    fn node_label(&self, name: &String, node: &BuilderNode) -> Vec<String> {
+        let mut parts = vec![name.clone(), node.type_name()];
+        if let Some((features, size)) = self.shapes.get(name) {
+            parts.push(format!(
+                "{} x {}",
+                Self::shape_label(features),
+                Self::shape_label(size)
+            ));
+        }
+        return parts;
+    }
+
+    fn shape_label(shape: &Shape) -> String {
+        return match shape {
+            Shape::Const(c) => c.to_string(),
+            Shape::Repeat => "Repeat".to_string(),
+            Shape::Variable => "Variable".to_string(),
+            Shape::Grid(height, width) => format!("{}x{}", height, width),
+        };
+    }
+
+    fn parents(node: &BuilderNode) -> Vec<String> {
+        return match node {
+            BuilderNode::DeadEnd(_) => vec![],
+            BuilderNode::SingleParent(s) => vec![s.parent_name.clone()],
+            BuilderNode::MultipleParent(s) => s.parent_names.clone(),
+        };
+    }
+
+    fn escape_dot(text: &str) -> String {
+        return text.replace('\\', "\\\\").replace('"', "\\\"");
+    }
+
+    fn escape_mermaid(text: &str) -> String {
+        return text.replace('"', "#quot;");
+    }
+}
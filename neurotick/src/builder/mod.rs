@@ -1,2 +1,4 @@
 pub mod builder;
 pub mod graph_elements;
+pub mod graph_export;
+mod tests;
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod test {
+    use crate::{
+        builder::builder::ModelBuilder,
+        layer::{concat::Concat, dense::Dense, input::Input},
+        map,
+        matrix::meta::shape::Shape,
+    };
+
+    fn build_branched() -> ModelBuilder {
+        let input_1 = Input::new(Shape::Const(3), Shape::Repeat);
+        let input_2 = Input::new(Shape::Const(5), Shape::Repeat);
+        let d1 = Dense::new(4, || &input_1);
+        let concat = Concat::new(|| vec![&d1, &input_2]);
+        let d2 = Dense::new(2, || &concat);
+
+        let inputs = map! {
+            input_1 => "obs".to_owned(),
+            input_2 => "extra".to_owned(),
+        };
+        return ModelBuilder::from_single_o(inputs, d2);
+    }
+
+    #[test]
+    fn export_dot_test() {
+        let mb = build_branched();
+        let dot = mb.to_dot();
+        assert!(dot.starts_with("digraph model {"));
+        assert!(dot.contains("\"Input_0\" [label=\"Input_0\\nInput\\n3 x Repeat\", shape=box];"));
+        assert!(dot.contains("\"Dense_1\" [label=\"Dense_1\\nDense\\n4 x Repeat\", shape=box];"));
+        assert!(dot.contains("\"input:obs\" -> \"Input_0\";"));
+        assert!(dot.contains("\"Dense_1\" -> \"Concat_3\";"));
+        assert!(dot.contains("\"Input_2\" -> \"Concat_3\";"));
+        assert!(dot.contains("\"Dense_4\" -> \"output:DEF_IO\";"));
+        assert!(dot.ends_with("}"));
+    }
+
+    #[test]
+    fn export_mermaid_test() {
+        let mb = build_branched();
+        let mermaid = mb.to_mermaid();
+        assert!(mermaid.starts_with("flowchart TD"));
+        assert!(mermaid.contains("n3[\"Concat_3<br/>Concat<br/>9 x Variable\"]"));
+        assert!(mermaid.contains("i1([\"extra\"]) --> n2"));
+        assert!(mermaid.contains("n1 --> n3"));
+        assert!(mermaid.contains("n4 --> o0([\"DEF_IO\"])"));
+    }
+
+    #[test]
+    fn export_model_without_shapes_test() {
+        let model = build_branched().build();
+        let dot = model.to_dot();
+        let mermaid = model.to_mermaid();
+
+        assert!(dot.contains("\"Dense_4\" [label=\"Dense_4\\nDense\", shape=box];"));
+        assert!(mermaid.contains("n4[\"Dense_4<br/>Dense\"]"));
+    }
+}
@@ -0,0 +1 @@
+mod graph_export_tests;
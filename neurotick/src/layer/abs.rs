@@ -2,6 +2,8 @@ use std::hash::Hash;
 use std::hash::Hasher;
 use std::rc::Rc;
 
+use ndarray::Axis;
+
 use crate::matrix::{
     meta::{node::LayerType, shape::Shape},
     nmatrix::NDMatrix,
@@ -9,6 +11,8 @@ use crate::matrix::{
 use crate::serial::model_reader::ModelReader;
 use crate::utils::json_wrap::JsonWrap;
 
+pub use neurotick_derive::LayerImpl;
+
 /**
  * Struct for building out a graph of layers. Pre-instancing
  * Serialization is done on layer instances
@@ -50,6 +54,17 @@ pub trait Layer {
 
 // Implementation for layers, forward propagation
 
+/**
+ * Whether a model is being trained or used, set on the whole model by Model::set_mode
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /** Deterministic propagation, noise layers are the identity */
+    #[default]
+    Inference,
+    Training,
+}
+
 /**
  * Init
  */
@@ -57,6 +72,19 @@ pub trait LayerBase {
     fn init(&mut self);
     fn to_json(&self) -> JsonWrap;
 
+    /**
+     * Called for every layer when the model changes mode, new layers start in inference
+     */
+    fn set_mode(&mut self, _mode: Mode) {}
+
+    /**
+     * Matrix fields of to_json that propagation updates instead of training, ex.: running
+     * statistics
+     */
+    fn non_trainable_fields(&self) -> &'static [&'static str] {
+        return &[];
+    }
+
     fn create_from_ser(json: &JsonWrap, model_reader: &ModelReader) -> LayerPropagateEnum
     where
         Self: Sized;
@@ -64,6 +92,35 @@ pub trait LayerBase {
 
 pub trait LayerSingleInput: LayerBase {
     fn propagate(&self, input: &NDMatrix) -> NDMatrix;
+
+    /**
+     * One tick of Model::step where every input row is its own environment. Layers with a
+     * state replace theirs, it is empty on the first tick. By default every row propagates
+     * on its own so layers that mix rows, ex.: Flatten or Conv1D, don't mix environments,
+     * layers that keep rows apart can propagate the whole input instead.
+     */
+    fn step(&self, input: &NDMatrix, _state: &mut Vec<NDMatrix>) -> NDMatrix {
+        if input.height == 0 {
+            return self.propagate(input);
+        }
+        let rows: Vec<NDMatrix> = input
+            .values
+            .axis_chunks_iter(Axis(0), 1)
+            .map(|row| {
+                let output = self.propagate(&NDMatrix::with(input.width, 1, row.to_owned()));
+                if output.height != 1 {
+                    panic!(
+                        "Step output of {} rows for an environment, expected one",
+                        output.height
+                    );
+                }
+                return output;
+            })
+            .collect();
+        let views: Vec<_> = rows.iter().map(|m| m.values.view()).collect();
+        let values = ndarray::concatenate(Axis(0), &views).unwrap();
+        return NDMatrix::with(values.ncols(), values.nrows(), values);
+    }
 }
 
 pub trait LayerMultiInput: LayerBase {
@@ -0,0 +1,303 @@
+use ndarray::{s, Array2, Axis};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    suppliers::suppliers::{GlorothUniformSupplier, Supplier, Suppliers, ZeroSupplier},
+    utils::{json_wrap::JsonWrap, math::fast_math::FMath},
+};
+
+use super::abs::{Layer, LayerBase, LayerPropagateEnum, LayerRef, LayerSingleInput};
+
+/**
+ * Which keys a row of the attention may look at
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttentionMask {
+    /** Every row attends to all rows */
+    None,
+    /** A row attends to itself and the rows above it only */
+    Causal,
+    /**
+     * Rows with every feature equal to the value are padding and never attended to, ex.: empty
+     * entity slots of a fixed size set
+     */
+    Padding(f32),
+}
+
+/**
+ * Multi-head scaled dot product self-attention over the size axis, each row is one entity
+ * or step with its features. The size may be Shape::Variable, it is preserved.
+ * Outputs the parent features unless with_output_features is set.
+ */
+pub struct MultiHeadAttention {
+    heads: usize,
+    key_dim: usize,
+    output_features: Option<usize>,
+    mask: AttentionMask,
+    parent: LayerRef,
+    weight_init: Suppliers,
+    bias_init: Suppliers,
+}
+
+impl MultiHeadAttention {
+    pub const NAME: &str = "MultiHeadAttention";
+
+    pub fn new<'a, F>(heads: usize, key_dim: usize, uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let attention = Self::builder(heads, key_dim, uplink);
+        return LayerRef::pin(attention);
+    }
+
+    pub fn builder<'a, F>(heads: usize, key_dim: usize, uplink: F) -> MultiHeadAttention
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return MultiHeadAttention {
+            heads,
+            key_dim,
+            output_features: None,
+            mask: AttentionMask::None,
+            parent: uplink().clone(),
+            weight_init: GlorothUniformSupplier::new().into_enum(),
+            bias_init: ZeroSupplier::new().into_enum(),
+        };
+    }
+
+    pub fn with_output_features(mut self, features: usize) -> MultiHeadAttention {
+        self.output_features = Some(features);
+        return self;
+    }
+
+    pub fn with_mask(mut self, mask: AttentionMask) -> MultiHeadAttention {
+        self.mask = mask;
+        return self;
+    }
+
+    pub fn with_weight_init(mut self, supplier: impl Supplier) -> MultiHeadAttention {
+        self.weight_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn with_bias_init(mut self, supplier: impl Supplier) -> MultiHeadAttention {
+        self.bias_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn build(self) -> LayerRef {
+        return LayerRef::pin(self);
+    }
+
+    fn features(&self) -> usize {
+        return self
+            .output_features
+            .unwrap_or_else(|| self.parent.get_shape().0.unwrap_to_conts());
+    }
+}
+
+impl Layer for MultiHeadAttention {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        return (
+            Shape::Const(self.features()),
+            self.parent.get_shape().1.clone(),
+        );
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let attention = Attention::create(
+            &id,
+            &self.parent,
+            self.heads,
+            self.key_dim,
+            self.features(),
+            self.mask,
+            &self.weight_init,
+            &self.bias_init,
+        );
+        let instance = MultiHeadAttentionImpl { id, attention };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+/**
+ * Projections and heads of a self-attention, shared with the TransformerEncoder.
+ * Every projection is features by heads * key_dim, the heads take consecutive columns.
+ */
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct Attention {
+    heads: usize,
+    mask: AttentionMask,
+    query: NDMatrix,
+    query_bias: NDMatrix,
+    key: NDMatrix,
+    key_bias: NDMatrix,
+    value: NDMatrix,
+    value_bias: NDMatrix,
+    /**
+     * heads * key_dim by output features
+     */
+    output: NDMatrix,
+    output_bias: NDMatrix,
+}
+
+impl Attention {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn create(
+        id: &str,
+        parent: &LayerRef,
+        heads: usize,
+        key_dim: usize,
+        output_features: usize,
+        mask: AttentionMask,
+        weight_init: &Suppliers,
+        bias_init: &Suppliers,
+    ) -> Attention {
+        let parent_feats = parent.get_shape().0.unwrap_to_conts();
+        if parent_feats == 0 {
+            panic!("Zero features in parent is not allowed, by: {}", id);
+        }
+        if heads == 0 || key_dim == 0 || output_features == 0 {
+            panic!(
+                "Heads, key dim and output features must be positive, by: {}",
+                id
+            );
+        }
+        let projected = heads * key_dim;
+        return Attention {
+            heads,
+            mask,
+            query: weight_init.supply_matrix(projected, parent_feats),
+            query_bias: bias_init.supply_matrix(projected, 1),
+            key: weight_init.supply_matrix(projected, parent_feats),
+            key_bias: bias_init.supply_matrix(projected, 1),
+            value: weight_init.supply_matrix(projected, parent_feats),
+            value_bias: bias_init.supply_matrix(projected, 1),
+            output: weight_init.supply_matrix(output_features, projected),
+            output_bias: bias_init.supply_matrix(output_features, 1),
+        };
+    }
+
+    fn project(input: &NDMatrix, weight: &NDMatrix, bias: &NDMatrix) -> Array2<f32> {
+        return input.values.dot(&weight.values) + &bias.values;
+    }
+
+    fn allowed(&self, input: &NDMatrix, query: usize, key: usize) -> bool {
+        return match self.mask {
+            AttentionMask::None => true,
+            AttentionMask::Causal => key <= query,
+            AttentionMask::Padding(value) => input.values.row(key).iter().any(|v| *v != value),
+        };
+    }
+
+    /**
+     * Softmax(Q K^T / sqrt(key_dim)) V per head, the heads concatenated and projected to the
+     * output features. A row without any key to attend to gets zeros before the projection.
+     */
+    pub(crate) fn attend(&self, input: &NDMatrix) -> NDMatrix {
+        let rows = input.height;
+        let key_dim = self.query.width / self.heads;
+        let scale = 1.0 / (key_dim as f32).sqrt();
+
+        let query = Self::project(input, &self.query, &self.query_bias);
+        let key = Self::project(input, &self.key, &self.key_bias);
+        let value = Self::project(input, &self.value, &self.value_bias);
+
+        let mut heads = Array2::<f32>::zeros((rows, self.query.width));
+        for head in 0..self.heads {
+            let columns = s![.., head * key_dim..(head + 1) * key_dim];
+            let mut scores = query.slice(columns).dot(&key.slice(columns).t()) * scale;
+            for (row, mut row_scores) in scores.axis_iter_mut(Axis(0)).enumerate() {
+                for (col, score) in row_scores.iter_mut().enumerate() {
+                    if !self.allowed(input, row, col) {
+                        *score = f32::NEG_INFINITY;
+                    }
+                }
+                let max = row_scores.fold(f32::NEG_INFINITY, |a, b| a.max(*b));
+                if max == f32::NEG_INFINITY {
+                    row_scores.fill(0.0);
+                    continue;
+                }
+                row_scores.mapv_inplace(|score| {
+                    if score == f32::NEG_INFINITY {
+                        return 0.0;
+                    }
+                    return FMath::fast_exponent(score - max);
+                });
+                let sum = row_scores.sum();
+                row_scores.mapv_inplace(|score| score / sum);
+            }
+            heads
+                .slice_mut(columns)
+                .assign(&scores.dot(&value.slice(columns)));
+        }
+
+        let concatenated = NDMatrix::with(self.query.width, rows, heads);
+        let projected = NDMatrix::mat_mul(&concatenated, &self.output);
+        return NDMatrix::add(&projected, &self.output_bias);
+    }
+}
+
+pub struct MultiHeadAttentionImpl {
+    id: String,
+    attention: Attention,
+}
+
+impl LayerBase for MultiHeadAttentionImpl {
+    fn init(&mut self) {}
+
+    fn create_from_ser(json: &JsonWrap, _model_reader: &ModelReader) -> LayerPropagateEnum {
+        let deserialized: MultiHeadAttentionSerialization = json.to().unwrap();
+        let impl_ref = MultiHeadAttentionImpl {
+            id: deserialized.id,
+            attention: deserialized.attention,
+        };
+        return LayerPropagateEnum::SingleInput(Box::new(impl_ref));
+    }
+
+    fn to_json(&self) -> JsonWrap {
+        let serial = MultiHeadAttentionSerialization {
+            id: self.id.clone(),
+            attention: self.attention.clone(),
+        };
+        return JsonWrap::from(serial).unwrap();
+    }
+}
+
+impl LayerSingleInput for MultiHeadAttentionImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        return self.attention.attend(input);
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: MultiHeadAttention::NAME,
+        create: MultiHeadAttentionImpl::create_from_ser,
+    }
+}
+
+/**
+ * Serialization
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+struct MultiHeadAttentionSerialization {
+    id: String,
+    #[serde(flatten)]
+    attention: Attention,
+}
@@ -0,0 +1,224 @@
+use std::cell::RefCell;
+
+use ndarray::{Array2, Axis};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    utils::json_wrap::JsonWrap,
+};
+
+use super::abs::{Layer, LayerBase, LayerPropagateEnum, LayerRef, LayerSingleInput, Mode};
+
+/**
+ * Normalizes each feature over the rows of the batch, then scales by gamma and shifts by
+ * beta. In Mode::Training it uses the statistics of the batch and moves the running mean and
+ * variance towards them, in inference the running ones so rows stay independent.
+ */
+pub struct BatchNorm {
+    epsilon: f32,
+    momentum: f32,
+    parent: LayerRef,
+}
+
+impl BatchNorm {
+    pub const NAME: &str = "BatchNorm";
+
+    pub fn new<'a, F>(uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let norm = Self::builder(uplink);
+        return LayerRef::pin(norm);
+    }
+
+    pub fn builder<'a, F>(uplink: F) -> BatchNorm
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return BatchNorm {
+            epsilon: 1e-3,
+            momentum: 0.99,
+            parent: uplink().clone(),
+        };
+    }
+
+    /**
+     * Added to the variance, 1e-3 by default
+     */
+    pub fn with_epsilon(mut self, epsilon: f32) -> BatchNorm {
+        self.epsilon = epsilon;
+        return self;
+    }
+
+    /**
+     * Share of the running statistics kept on each training batch, 0.99 by default
+     */
+    pub fn with_momentum(mut self, momentum: f32) -> BatchNorm {
+        self.momentum = momentum;
+        return self;
+    }
+
+    pub fn build(self) -> LayerRef {
+        return LayerRef::pin(self);
+    }
+}
+
+impl Layer for BatchNorm {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        return self.parent.get_shape();
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let parent_feats = self.parent.get_shape().0.unwrap_to_conts();
+        if parent_feats == 0 {
+            panic!("Zero features in parent is not allowed, by: {}", id);
+        }
+        if !(0.0..=1.0).contains(&self.momentum) {
+            panic!("Momentum must be within 0 and 1, by: {}", id);
+        }
+        let instance = BatchNormImpl {
+            id,
+            epsilon: self.epsilon,
+            momentum: self.momentum,
+            training: false,
+            gamma: NDMatrix::constant(parent_feats, 1, 1.0),
+            beta: NDMatrix::new(parent_feats, 1),
+            running_mean: RefCell::new(NDMatrix::new(parent_feats, 1)),
+            running_variance: RefCell::new(NDMatrix::constant(parent_feats, 1, 1.0)),
+        };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+pub struct BatchNormImpl {
+    id: String,
+    epsilon: f32,
+    momentum: f32,
+    training: bool,
+    gamma: NDMatrix,
+    beta: NDMatrix,
+    /**
+     * Updated by propagation in training mode
+     */
+    running_mean: RefCell<NDMatrix>,
+    running_variance: RefCell<NDMatrix>,
+}
+
+impl BatchNormImpl {
+    /**
+     * Mean and biased variance of each feature over the rows, as 1 by features
+     */
+    fn batch_statistics(input: &NDMatrix) -> (Array2<f32>, Array2<f32>) {
+        let mean = input
+            .values
+            .mean_axis(Axis(0))
+            .unwrap()
+            .insert_axis(Axis(0));
+        let variance = (&input.values - &mean)
+            .mapv(|v| v * v)
+            .mean_axis(Axis(0))
+            .unwrap()
+            .insert_axis(Axis(0));
+        return (mean, variance);
+    }
+
+    fn normalize(&self, input: &NDMatrix, mean: &Array2<f32>, variance: &Array2<f32>) -> NDMatrix {
+        let deviation = variance.mapv(|v| (v + self.epsilon).sqrt());
+        let values = (&input.values - mean) / deviation * &self.gamma.values + &self.beta.values;
+        return NDMatrix::with(input.width, input.height, values);
+    }
+}
+
+impl LayerBase for BatchNormImpl {
+    fn init(&mut self) {}
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.training = mode == Mode::Training;
+    }
+
+    fn non_trainable_fields(&self) -> &'static [&'static str] {
+        return &["running_mean", "running_variance"];
+    }
+
+    fn create_from_ser(json: &JsonWrap, _model_reader: &ModelReader) -> LayerPropagateEnum {
+        let deserialized: BatchNormSerialization = json.to().unwrap();
+        let impl_ref = BatchNormImpl {
+            id: deserialized.id,
+            epsilon: deserialized.epsilon,
+            momentum: deserialized.momentum,
+            training: false,
+            gamma: deserialized.gamma,
+            beta: deserialized.beta,
+            running_mean: RefCell::new(deserialized.running_mean),
+            running_variance: RefCell::new(deserialized.running_variance),
+        };
+        return LayerPropagateEnum::SingleInput(Box::new(impl_ref));
+    }
+
+    fn to_json(&self) -> JsonWrap {
+        let serial = BatchNormSerialization {
+            id: self.id.clone(),
+            epsilon: self.epsilon,
+            momentum: self.momentum,
+            gamma: self.gamma.clone(),
+            beta: self.beta.clone(),
+            running_mean: self.running_mean.borrow().clone(),
+            running_variance: self.running_variance.borrow().clone(),
+        };
+        return JsonWrap::from(serial).unwrap();
+    }
+}
+
+impl LayerSingleInput for BatchNormImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        if !self.training || input.height == 0 {
+            let mean = self.running_mean.borrow();
+            let variance = self.running_variance.borrow();
+            return self.normalize(input, &mean.values, &variance.values);
+        }
+
+        let (mean, variance) = Self::batch_statistics(input);
+        let momentum = self.momentum;
+        let mut running_mean = self.running_mean.borrow_mut();
+        running_mean.values = &running_mean.values * momentum + &mean * (1.0 - momentum);
+        let mut running_variance = self.running_variance.borrow_mut();
+        running_variance.values =
+            &running_variance.values * momentum + &variance * (1.0 - momentum);
+        return self.normalize(input, &mean, &variance);
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: BatchNorm::NAME,
+        create: BatchNormImpl::create_from_ser,
+    }
+}
+
+/**
+ * Serialization
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BatchNormSerialization {
+    id: String,
+    epsilon: f32,
+    momentum: f32,
+    gamma: NDMatrix,
+    beta: NDMatrix,
+    running_mean: NDMatrix,
+    running_variance: NDMatrix,
+}
@@ -5,8 +5,8 @@ use crate::{
         meta::{node::LayerType, shape::Shape},
         nmatrix::NDMatrix,
     },
-    serial::model_reader::ModelReader,
-    utils::{extensions::Distinct, json_wrap::JsonWrap},
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    utils::json_wrap::JsonWrap,
 };
 
 use super::abs::{Layer, LayerBase, LayerMultiInput, LayerPropagateEnum, LayerRef};
@@ -30,19 +30,7 @@ impl Concat {
             .map(|l| l.get_shape().0.unwrap_to_conts())
             .sum();
 
-        let all_const = uplink_vec.iter().all(|l| l.get_shape().1.is_const());
-        let size = if all_const {
-            let set = uplink_vec
-                .iter()
-                .distinct_vec(|l| l.get_shape().1.unwrap_to_conts());
-            if set.len() != 1 {
-                panic!("Different sizes")
-            }
-            Shape::Const(set[0])
-        } else {
-            Shape::Variable
-        };
-
+        let size = Self::concat_size(&uplink_vec);
         let concat = Concat {
             parents: uplink_vec.iter().map(|u| (*u).clone()).collect(),
             features: Shape::Const(features),
@@ -50,6 +38,23 @@ impl Concat {
         };
         return LayerRef::pin(concat);
     }
+
+    /**
+     * Grids need the same grid in every parent, constant sizes the same size, any other size
+     * leaves it to the propagation
+     */
+    fn concat_size(parents: &[&LayerRef]) -> Shape {
+        let sizes: Vec<Shape> = parents.iter().map(|l| l.get_shape().1).collect();
+        let grids = sizes.iter().any(|s| matches!(s, Shape::Grid(..)));
+        if grids || sizes.iter().all(|s| s.is_const()) {
+            // channels of the same grid, or rows of the same count
+            if sizes.iter().any(|s| *s != sizes[0]) {
+                panic!("Different sizes in {}: {:?}", Self::NAME, sizes);
+            }
+            return sizes[0].clone();
+        }
+        return Shape::Variable;
+    }
 }
 
 impl Layer for Concat {
@@ -81,6 +86,12 @@ pub struct ConcatImpl {
     size: Shape,
 }
 
+impl ConcatImpl {
+    pub(crate) fn new(id: String, features: Shape, size: Shape) -> ConcatImpl {
+        return ConcatImpl { id, features, size };
+    }
+}
+
 impl LayerBase for ConcatImpl {
     fn init(&mut self) {}
 
@@ -110,6 +121,13 @@ impl LayerMultiInput for ConcatImpl {
     }
 }
 
+inventory::submit! {
+    LayerRegistration {
+        name: Concat::NAME,
+        create: ConcatImpl::create_from_ser,
+    }
+}
+
 /**
  * Serialization
  */
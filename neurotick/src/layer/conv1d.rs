@@ -0,0 +1,325 @@
+use ndarray::{s, Array2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    activation::{
+        abs::{Activation, ActivationSerialised},
+        none::NoneAct,
+    },
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    suppliers::suppliers::{GlorothNormalSupplier, Supplier, Suppliers, ZeroSupplier},
+    utils::json_wrap::JsonWrap,
+};
+
+use super::abs::{Layer, LayerBase, LayerPropagateEnum, LayerRef, LayerSingleInput};
+
+/**
+ * Padding of the input ends before the kernel slides over them, per axis for grids
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Padding {
+    /** No padding, only full windows */
+    Valid,
+    /** Zeros on both ends, size / stride steps rounded up */
+    Same,
+    /** Zeros before the start only, a step never sees later ones */
+    Causal,
+}
+
+/**
+ * Convolution over the size axis (time steps, rows of the matrix) with the features as
+ * channels, as laid out by Input::new(Shape::Const(features), Shape::Const(steps)).
+ * Each output row has filters features.
+ */
+pub struct Conv1D {
+    filters: usize,
+    kernel_size: usize,
+    stride: usize,
+    dilation: usize,
+    padding: Padding,
+    parent: LayerRef,
+    activation: Box<dyn Activation>,
+    weight_init: Suppliers,
+    bias_init: Suppliers,
+}
+
+impl Conv1D {
+    pub const NAME: &str = "Conv1D";
+
+    pub fn new<'a, F>(filters: usize, kernel_size: usize, uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let conv = Self::builder(filters, kernel_size, uplink);
+        return LayerRef::pin(conv);
+    }
+
+    pub fn builder<'a, F>(filters: usize, kernel_size: usize, uplink: F) -> Conv1D
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Conv1D {
+            filters,
+            kernel_size,
+            stride: 1,
+            dilation: 1,
+            padding: Padding::Valid,
+            parent: uplink().clone(),
+            activation: Box::new(NoneAct::default()),
+            weight_init: GlorothNormalSupplier::new().into_enum(),
+            bias_init: ZeroSupplier::new().into_enum(),
+        };
+    }
+
+    pub fn with_stride(mut self, stride: usize) -> Conv1D {
+        self.stride = stride;
+        return self;
+    }
+
+    pub fn with_dilation(mut self, dilation: usize) -> Conv1D {
+        self.dilation = dilation;
+        return self;
+    }
+
+    pub fn with_padding(mut self, padding: Padding) -> Conv1D {
+        self.padding = padding;
+        return self;
+    }
+
+    pub fn with_activation(mut self, activation: impl Activation) -> Conv1D {
+        self.activation = Box::new(activation);
+        return self;
+    }
+
+    pub fn with_weight_init(mut self, supplier: impl Supplier) -> Conv1D {
+        self.weight_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn with_bias_init(mut self, supplier: impl Supplier) -> Conv1D {
+        self.bias_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn build(self) -> LayerRef {
+        return LayerRef::pin(self);
+    }
+
+    fn window(&self) -> Window {
+        return Window {
+            kernel_size: self.kernel_size,
+            stride: self.stride,
+            dilation: self.dilation,
+            padding: self.padding,
+        };
+    }
+}
+
+impl Layer for Conv1D {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        let size = match self.parent.get_shape().1 {
+            Shape::Const(steps) => Shape::Const(self.window().output_len(steps)),
+            // the length only carries over when every step has an output
+            Shape::Repeat if self.stride == 1 && self.padding != Padding::Valid => Shape::Repeat,
+            _ => Shape::Variable,
+        };
+        return (Shape::Const(self.filters), size);
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let parent_feats = self.parent.get_shape().0.unwrap_to_conts();
+        if parent_feats == 0 {
+            panic!("Zero features in parent is not allowed, by: {}", id);
+        }
+        if self.kernel_size == 0 || self.stride == 0 || self.dilation == 0 {
+            panic!(
+                "Kernel size, stride and dilation must be positive, by: {}",
+                id
+            );
+        }
+
+        // one row per kernel tap and parent feature, see Conv1DImpl::columns
+        let weight_m = self
+            .weight_init
+            .supply_matrix(self.filters, self.kernel_size * parent_feats);
+        let bias_m = self.bias_init.supply_matrix(self.filters, 1);
+        let instance = Conv1DImpl {
+            id,
+            window: self.window(),
+            weight: weight_m,
+            bias: bias_m,
+            activation: self.activation.act_clone(),
+        };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+/**
+ * Kernel placement along one axis, shared by the convolutions and poolings
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Window {
+    pub(crate) kernel_size: usize,
+    pub(crate) stride: usize,
+    pub(crate) dilation: usize,
+    pub(crate) padding: Padding,
+}
+
+impl Window {
+    /**
+     * Steps covered by one application of the kernel
+     */
+    fn span(&self) -> usize {
+        return self.dilation * (self.kernel_size - 1) + 1;
+    }
+
+    pub(crate) fn output_len(&self, steps: usize) -> usize {
+        return match self.padding {
+            Padding::Valid if steps < self.span() => 0,
+            Padding::Valid => (steps - self.span()) / self.stride + 1,
+            Padding::Same | Padding::Causal => steps.div_ceil(self.stride),
+        };
+    }
+
+    /**
+     * Zeros before the first step
+     */
+    pub(crate) fn pad_start(&self, steps: usize) -> usize {
+        return match self.padding {
+            Padding::Valid => 0,
+            Padding::Same => {
+                let needed = (self.output_len(steps).max(1) - 1) * self.stride + self.span();
+                needed.saturating_sub(steps) / 2
+            }
+            Padding::Causal => self.span() - 1,
+        };
+    }
+
+    /**
+     * Input step seen by a kernel tap at an output step, None on padding
+     */
+    pub(crate) fn input_step(
+        &self,
+        output: usize,
+        tap: usize,
+        pad_start: usize,
+        steps: usize,
+    ) -> Option<usize> {
+        return (output * self.stride + tap * self.dilation)
+            .checked_sub(pad_start)
+            .filter(|step| *step < steps);
+    }
+}
+
+pub struct Conv1DImpl {
+    id: String,
+    window: Window,
+    /**
+     * kernel_size * features rows by filters, row tap * features + feature
+     */
+    weight: NDMatrix,
+    bias: NDMatrix,
+    activation: Box<dyn Activation>,
+}
+
+impl Conv1DImpl {
+    /**
+     * Windows of the input as rows (im2col), so the convolution is a single mat_mul with the
+     * weight. Padded steps stay zero.
+     */
+    fn columns(&self, input: &NDMatrix) -> NDMatrix {
+        let window = &self.window;
+        let features = input.width;
+        let steps = input.height;
+        let output_len = window.output_len(steps);
+        let pad_start = window.pad_start(steps);
+
+        let mut columns = Array2::<f32>::zeros((output_len, window.kernel_size * features));
+        for row in 0..output_len {
+            for tap in 0..window.kernel_size {
+                let Some(step) = window.input_step(row, tap, pad_start, steps) else {
+                    continue;
+                };
+                columns
+                    .slice_mut(s![row, tap * features..(tap + 1) * features])
+                    .assign(&input.values.row(step));
+            }
+        }
+        return NDMatrix::with(window.kernel_size * features, output_len, columns);
+    }
+}
+
+impl LayerBase for Conv1DImpl {
+    fn init(&mut self) {}
+
+    fn create_from_ser(json: &JsonWrap, model_reader: &ModelReader) -> LayerPropagateEnum {
+        let deserialized: Conv1DSerialization = json.to().unwrap();
+        let activation_ser = &deserialized.activation;
+        let impl_ref = Conv1DImpl {
+            id: deserialized.id,
+            window: deserialized.window,
+            weight: deserialized.weight,
+            bias: deserialized.bias,
+            activation: model_reader.get_activation_di().create(
+                &activation_ser.name,
+                &activation_ser.json,
+                model_reader,
+            ),
+        };
+
+        return LayerPropagateEnum::SingleInput(Box::new(impl_ref));
+    }
+
+    fn to_json(&self) -> JsonWrap {
+        let serial = Conv1DSerialization {
+            id: self.id.clone(),
+            window: self.window,
+            weight: self.weight.clone(),
+            bias: self.bias.clone(),
+            activation: self.activation.as_serialized(),
+        };
+        return JsonWrap::from(serial).unwrap();
+    }
+}
+
+impl LayerSingleInput for Conv1DImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        let weighted_mul = NDMatrix::mat_mul(&self.columns(input), &self.weight);
+        let with_bias = NDMatrix::add(&weighted_mul, &self.bias);
+        return self.activation.apply(&with_bias);
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Conv1D::NAME,
+        create: Conv1DImpl::create_from_ser,
+    }
+}
+
+/**
+ * Serialization
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Conv1DSerialization {
+    id: String,
+    #[serde(flatten)]
+    window: Window,
+    weight: NDMatrix,
+    bias: NDMatrix,
+    activation: ActivationSerialised,
+}
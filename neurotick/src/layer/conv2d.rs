@@ -0,0 +1,360 @@
+use ndarray::{s, Array2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    activation::{
+        abs::{Activation, ActivationSerialised},
+        none::NoneAct,
+    },
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    suppliers::suppliers::{GlorothNormalSupplier, Supplier, Suppliers, ZeroSupplier},
+    utils::json_wrap::JsonWrap,
+};
+
+use super::{
+    abs::{Layer, LayerBase, LayerPropagateEnum, LayerRef, LayerSingleInput},
+    conv1d::{Padding, Window},
+};
+
+/**
+ * Convolution over a Shape::Grid size with the features as channels, as laid out by
+ * Input::new(Shape::Const(channels), Shape::Grid(height, width)). Sizes are (height, width),
+ * each output cell has filters features. A matrix holds one image, a batch of images is
+ * propagated one image at a time.
+ */
+pub struct Conv2D {
+    filters: usize,
+    kernel: (usize, usize),
+    stride: (usize, usize),
+    dilation: (usize, usize),
+    padding: Padding,
+    parent: LayerRef,
+    activation: Box<dyn Activation>,
+    weight_init: Suppliers,
+    bias_init: Suppliers,
+}
+
+impl Conv2D {
+    pub const NAME: &str = "Conv2D";
+
+    pub fn new<'a, F>(filters: usize, kernel: (usize, usize), uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let conv = Self::builder(filters, kernel, uplink);
+        return LayerRef::pin(conv);
+    }
+
+    pub fn builder<'a, F>(filters: usize, kernel: (usize, usize), uplink: F) -> Conv2D
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Conv2D {
+            filters,
+            kernel,
+            stride: (1, 1),
+            dilation: (1, 1),
+            padding: Padding::Valid,
+            parent: uplink().clone(),
+            activation: Box::new(NoneAct::default()),
+            weight_init: GlorothNormalSupplier::new().into_enum(),
+            bias_init: ZeroSupplier::new().into_enum(),
+        };
+    }
+
+    pub fn with_stride(mut self, stride: (usize, usize)) -> Conv2D {
+        self.stride = stride;
+        return self;
+    }
+
+    pub fn with_dilation(mut self, dilation: (usize, usize)) -> Conv2D {
+        self.dilation = dilation;
+        return self;
+    }
+
+    pub fn with_padding(mut self, padding: Padding) -> Conv2D {
+        self.padding = padding;
+        return self;
+    }
+
+    pub fn with_activation(mut self, activation: impl Activation) -> Conv2D {
+        self.activation = Box::new(activation);
+        return self;
+    }
+
+    pub fn with_weight_init(mut self, supplier: impl Supplier) -> Conv2D {
+        self.weight_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn with_bias_init(mut self, supplier: impl Supplier) -> Conv2D {
+        self.bias_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn build(self) -> LayerRef {
+        return LayerRef::pin(self);
+    }
+
+    fn window(&self) -> GridWindow {
+        return GridWindow::new(
+            Self::NAME,
+            &self.parent,
+            self.kernel,
+            self.stride,
+            self.dilation,
+            self.padding,
+        );
+    }
+}
+
+impl Layer for Conv2D {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        let (height, width) = self.window().output_grid();
+        return (Shape::Const(self.filters), Shape::Grid(height, width));
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let parent_feats = self.parent.get_shape().0.unwrap_to_conts();
+        if parent_feats == 0 {
+            panic!("Zero features in parent is not allowed, by: {}", id);
+        }
+        let window = self.window();
+        window.check(&id);
+
+        // one row per kernel cell and parent channel, see GridWindow::cells
+        let taps = self.kernel.0 * self.kernel.1;
+        let weight_m = self
+            .weight_init
+            .supply_matrix(self.filters, taps * parent_feats);
+        let bias_m = self.bias_init.supply_matrix(self.filters, 1);
+        let instance = Conv2DImpl {
+            id,
+            window,
+            cells: window.cells(),
+            weight: weight_m,
+            bias: bias_m,
+            activation: self.activation.act_clone(),
+        };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+/**
+ * Kernel placement over an input grid, shared by Conv2D and the 2d poolings
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct GridWindow {
+    pub(crate) height: usize,
+    pub(crate) width: usize,
+    pub(crate) vertical: Window,
+    pub(crate) horizontal: Window,
+}
+
+impl GridWindow {
+    /**
+     * Panics when the parent size is not a Shape::Grid
+     */
+    pub(crate) fn new(
+        type_name: &str,
+        parent: &LayerRef,
+        kernel: (usize, usize),
+        stride: (usize, usize),
+        dilation: (usize, usize),
+        padding: Padding,
+    ) -> GridWindow {
+        let (height, width) = match parent.get_shape().1 {
+            Shape::Grid(height, width) => (height, width),
+            other => panic!("{} needs a Shape::Grid parent, got: {:?}", type_name, other),
+        };
+        let axis = |kernel_size, stride, dilation| Window {
+            kernel_size,
+            stride,
+            dilation,
+            padding,
+        };
+        return GridWindow {
+            height,
+            width,
+            vertical: axis(kernel.0, stride.0, dilation.0),
+            horizontal: axis(kernel.1, stride.1, dilation.1),
+        };
+    }
+
+    pub(crate) fn check(&self, id: &str) {
+        let axes = [self.vertical, self.horizontal];
+        if axes
+            .iter()
+            .any(|a| a.kernel_size == 0 || a.stride == 0 || a.dilation == 0)
+        {
+            panic!("Kernel, stride and dilation must be positive, by: {}", id);
+        }
+        if axes.iter().any(|a| a.padding == Padding::Causal) {
+            panic!("Causal padding is only for sequences, by: {}", id);
+        }
+    }
+
+    pub(crate) fn output_grid(&self) -> (usize, usize) {
+        return (
+            self.vertical.output_len(self.height),
+            self.horizontal.output_len(self.width),
+        );
+    }
+
+    pub(crate) fn taps(&self) -> usize {
+        return self.vertical.kernel_size * self.horizontal.kernel_size;
+    }
+
+    /**
+     * Panics unless the input is one image of the grid
+     */
+    pub(crate) fn check_input(&self, input: &NDMatrix, id: &str) {
+        if input.height != self.height * self.width {
+            panic!(
+                "Expected {} rows for one {}x{} grid, got: {}, by: {}",
+                self.height * self.width,
+                self.height,
+                self.width,
+                input.height,
+                id
+            );
+        }
+    }
+
+    /**
+     * Input cell under each kernel cell (row by row) for every output cell, None on padding.
+     * Computed once per layer instance.
+     */
+    pub(crate) fn cells(&self) -> Vec<Vec<Option<usize>>> {
+        let (output_height, output_width) = self.output_grid();
+        let pad_top = self.vertical.pad_start(self.height);
+        let pad_left = self.horizontal.pad_start(self.width);
+        let mut cells = Vec::with_capacity(output_height * output_width);
+        for y in 0..output_height {
+            for x in 0..output_width {
+                let mut taps = Vec::with_capacity(self.taps());
+                for ky in 0..self.vertical.kernel_size {
+                    for kx in 0..self.horizontal.kernel_size {
+                        let row = self.vertical.input_step(y, ky, pad_top, self.height);
+                        let column = self.horizontal.input_step(x, kx, pad_left, self.width);
+                        taps.push(row.zip(column).map(|(r, c)| r * self.width + c));
+                    }
+                }
+                cells.push(taps);
+            }
+        }
+        return cells;
+    }
+}
+
+pub struct Conv2DImpl {
+    id: String,
+    window: GridWindow,
+    cells: Vec<Vec<Option<usize>>>,
+    /**
+     * kernel cells * channels rows by filters, row (ky * kernel width + kx) * channels + channel
+     */
+    weight: NDMatrix,
+    bias: NDMatrix,
+    activation: Box<dyn Activation>,
+}
+
+impl Conv2DImpl {
+    /**
+     * Windows of the input as rows (im2col), so the convolution is a single mat_mul with the
+     * weight. Padded cells stay zero.
+     */
+    fn columns(&self, input: &NDMatrix) -> NDMatrix {
+        self.window.check_input(input, &self.id);
+        let channels = input.width;
+        let width = self.window.taps() * channels;
+
+        let mut columns = Array2::<f32>::zeros((self.cells.len(), width));
+        for (row, taps) in self.cells.iter().enumerate() {
+            for (tap, cell) in taps.iter().enumerate() {
+                let Some(cell) = cell else {
+                    continue;
+                };
+                columns
+                    .slice_mut(s![row, tap * channels..(tap + 1) * channels])
+                    .assign(&input.values.row(*cell));
+            }
+        }
+        return NDMatrix::with(width, self.cells.len(), columns);
+    }
+}
+
+impl LayerBase for Conv2DImpl {
+    fn init(&mut self) {}
+
+    fn create_from_ser(json: &JsonWrap, model_reader: &ModelReader) -> LayerPropagateEnum {
+        let deserialized: Conv2DSerialization = json.to().unwrap();
+        let activation_ser = &deserialized.activation;
+        let impl_ref = Conv2DImpl {
+            id: deserialized.id,
+            window: deserialized.window,
+            cells: deserialized.window.cells(),
+            weight: deserialized.weight,
+            bias: deserialized.bias,
+            activation: model_reader.get_activation_di().create(
+                &activation_ser.name,
+                &activation_ser.json,
+                model_reader,
+            ),
+        };
+
+        return LayerPropagateEnum::SingleInput(Box::new(impl_ref));
+    }
+
+    fn to_json(&self) -> JsonWrap {
+        let serial = Conv2DSerialization {
+            id: self.id.clone(),
+            window: self.window,
+            weight: self.weight.clone(),
+            bias: self.bias.clone(),
+            activation: self.activation.as_serialized(),
+        };
+        return JsonWrap::from(serial).unwrap();
+    }
+}
+
+impl LayerSingleInput for Conv2DImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        let weighted_mul = NDMatrix::mat_mul(&self.columns(input), &self.weight);
+        let with_bias = NDMatrix::add(&weighted_mul, &self.bias);
+        return self.activation.apply(&with_bias);
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Conv2D::NAME,
+        create: Conv2DImpl::create_from_ser,
+    }
+}
+
+/**
+ * Serialization
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Conv2DSerialization {
+    id: String,
+    #[serde(flatten)]
+    window: GridWindow,
+    weight: NDMatrix,
+    bias: NDMatrix,
+    activation: ActivationSerialised,
+}
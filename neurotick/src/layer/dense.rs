@@ -9,7 +9,7 @@ use crate::{
         meta::{node::LayerType, shape::Shape},
         nmatrix::NDMatrix,
     },
-    serial::model_reader::ModelReader,
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
     suppliers::suppliers::{GlorothNormalSupplier, Supplier, Suppliers, ZeroSupplier},
     utils::json_wrap::JsonWrap,
 };
@@ -114,6 +114,23 @@ pub struct DenseImpl {
     activation: Box<dyn Activation>,
 }
 
+impl DenseImpl {
+    pub(crate) fn new(
+        id: String,
+        weight: NDMatrix,
+        bias: NDMatrix,
+        activation: Box<dyn Activation>,
+    ) -> DenseImpl {
+        return DenseImpl {
+            id,
+            features: weight.width,
+            weight,
+            bias,
+            activation,
+        };
+    }
+}
+
 impl LayerBase for DenseImpl {
     fn init(&mut self) {}
 
@@ -153,6 +170,20 @@ impl LayerSingleInput for DenseImpl {
         let with_bias = NDMatrix::add(&weighted_mul, &self.bias);
         return self.activation.apply(&with_bias);
     }
+
+    /**
+     * Rows are independent, the environments propagate together
+     */
+    fn step(&self, input: &NDMatrix, _state: &mut Vec<NDMatrix>) -> NDMatrix {
+        return self.propagate(input);
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Dense::NAME,
+        create: DenseImpl::create_from_ser,
+    }
 }
 
 /**
@@ -9,7 +9,7 @@ use crate::{
         meta::{node::LayerType, shape::Shape},
         nmatrix::NDMatrix,
     },
-    serial::model_reader::ModelReader,
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
     suppliers::suppliers::{GlorothNormalSupplier, Supplier, Suppliers, ZeroSupplier},
     utils::json_wrap::JsonWrap,
 };
@@ -110,6 +110,22 @@ pub struct DirectImpl {
     activation: Box<dyn Activation>,
 }
 
+impl DirectImpl {
+    pub(crate) fn new(
+        id: String,
+        weight: NDMatrix,
+        bias: NDMatrix,
+        activation: Box<dyn Activation>,
+    ) -> DirectImpl {
+        return DirectImpl {
+            id,
+            weight,
+            bias,
+            activation,
+        };
+    }
+}
+
 impl LayerBase for DirectImpl {
     fn init(&mut self) {}
 
@@ -147,6 +163,20 @@ impl LayerSingleInput for DirectImpl {
         let with_bias = NDMatrix::add(&weight_hadamard, &self.bias);
         return self.activation.apply(&with_bias);
     }
+
+    /**
+     * Rows are independent, the environments propagate together
+     */
+    fn step(&self, input: &NDMatrix, _state: &mut Vec<NDMatrix>) -> NDMatrix {
+        return self.propagate(input);
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Direct::NAME,
+        create: DirectImpl::create_from_ser,
+    }
 }
 
 /**
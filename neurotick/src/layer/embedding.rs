@@ -0,0 +1,130 @@
+use crate::{
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    suppliers::suppliers::{RandomUniformSupplier, Supplier, Suppliers},
+};
+
+use super::abs::{Layer, LayerImpl, LayerPropagateEnum, LayerRef, LayerSingleInput};
+
+/**
+ * Lookup of dense vectors for categorical ids, ex.: item types or map tiles. Every feature of
+ * the parent is an index in 0..vocab stored as a float, its dim sized vector takes its place
+ * so a row of n ids becomes n * dim features.
+ */
+pub struct Embedding {
+    vocab: usize,
+    dim: usize,
+    parent: LayerRef,
+    embeddings_init: Suppliers,
+}
+
+impl Embedding {
+    pub const NAME: &str = "Embedding";
+
+    pub fn new<'a, F>(vocab: usize, dim: usize, uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let embedding = Self::builder(vocab, dim, uplink);
+        return LayerRef::pin(embedding);
+    }
+
+    pub fn builder<'a, F>(vocab: usize, dim: usize, uplink: F) -> Embedding
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Embedding {
+            vocab,
+            dim,
+            parent: uplink().clone(),
+            embeddings_init: RandomUniformSupplier::new(0.05, -0.05).into_enum(),
+        };
+    }
+
+    /**
+     * Of the vocab by dim table, uniform in [-0.05, 0.05] by default
+     */
+    pub fn with_embeddings_init(mut self, supplier: impl Supplier) -> Embedding {
+        self.embeddings_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn build(self) -> LayerRef {
+        return LayerRef::pin(self);
+    }
+}
+
+impl Layer for Embedding {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        let ids = self.parent.get_shape().0.unwrap_to_conts();
+        return (
+            Shape::Const(ids * self.dim),
+            self.parent.get_shape().1.clone(),
+        );
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        if self.parent.get_shape().0.unwrap_to_conts() == 0 {
+            panic!("Zero features in parent is not allowed, by: {}", id);
+        }
+        if self.vocab == 0 || self.dim == 0 {
+            panic!("Vocab and dim must be positive, by: {}", id);
+        }
+        let instance = EmbeddingImpl {
+            id,
+            embeddings: self.embeddings_init.supply_matrix(self.dim, self.vocab),
+        };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+#[derive(LayerImpl)]
+#[layer(name = Embedding::NAME, input = "single")]
+pub struct EmbeddingImpl {
+    id: String,
+    /**
+     * vocab rows by dim, row i is the vector of id i
+     */
+    embeddings: NDMatrix,
+}
+
+impl EmbeddingImpl {
+    /**
+     * Panics on ids that are not a whole number within the vocab
+     */
+    fn index(&self, value: f32) -> usize {
+        let vocab = self.embeddings.height;
+        if value.fract() != 0.0 || value < 0.0 || value >= vocab as f32 {
+            panic!(
+                "Id {} outside of the vocab of {}, by: {}",
+                value, vocab, self.id
+            );
+        }
+        return value as usize;
+    }
+}
+
+impl LayerSingleInput for EmbeddingImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        let dim = self.embeddings.width;
+        let mut output = NDMatrix::new(input.width * dim, input.height);
+        for ((row, column), value) in input.values.indexed_iter() {
+            let vector = self.embeddings.values.row(self.index(*value));
+            output
+                .values
+                .slice_mut(ndarray::s![row, column * dim..(column + 1) * dim])
+                .assign(&vector);
+        }
+        return output;
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
         meta::{node::LayerType, shape::Shape},
         nmatrix::NDMatrix,
     },
-    serial::model_reader::ModelReader,
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
     utils::json_wrap::JsonWrap,
 };
 
@@ -39,11 +39,11 @@ impl Layer for Flatten {
         let feat_count = match parent_shape.0 {
             Shape::Const(x) => match parent_shape.1 {
                 Shape::Const(y) => Shape::Const(x * y),
+                Shape::Grid(h, w) => Shape::Const(x * h * w),
                 Shape::Repeat => Shape::Variable,
                 Shape::Variable => Shape::Variable,
             },
-            Shape::Repeat => Shape::Variable,
-            Shape::Variable => Shape::Variable,
+            _ => Shape::Variable,
         };
 
         return (feat_count, Shape::Const(1));
@@ -63,6 +63,12 @@ pub struct FlattenImpl {
     id: String,
 }
 
+impl FlattenImpl {
+    pub(crate) fn new(id: String) -> FlattenImpl {
+        return FlattenImpl { id };
+    }
+}
+
 impl LayerBase for FlattenImpl {
     fn init(&mut self) {}
 
@@ -89,6 +95,13 @@ impl LayerSingleInput for FlattenImpl {
     }
 }
 
+inventory::submit! {
+    LayerRegistration {
+        name: Flatten::NAME,
+        create: FlattenImpl::create_from_ser,
+    }
+}
+
 /**
  * Serialization
  */
@@ -0,0 +1,276 @@
+use ndarray::{s, Array2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    activation::{
+        abs::{Activation, ActivationSerialised},
+        sigmoid::Sigmoid,
+        tanh::Tanh,
+    },
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    suppliers::suppliers::{
+        GlorothUniformSupplier, OrthogonalSupplier, Supplier, Suppliers, ZeroSupplier,
+    },
+    utils::json_wrap::JsonWrap,
+};
+
+use super::{
+    abs::{Layer, LayerBase, LayerPropagateEnum, LayerRef, LayerSingleInput},
+    recurrent,
+};
+
+/**
+ * Gated recurrent unit over the size axis, one step per row of the parent. Outputs the last
+ * hidden state as a single row, or one per step with return_sequences.
+ * Weights follow keras (reset_after) with the gates stacked as update, reset, candidate and
+ * a bias row for the input and one for the recurrent part.
+ */
+pub struct Gru {
+    units: usize,
+    return_sequences: bool,
+    parent: LayerRef,
+    activation: Box<dyn Activation>,
+    recurrent_activation: Box<dyn Activation>,
+    kernel_init: Suppliers,
+    recurrent_init: Suppliers,
+    bias_init: Suppliers,
+}
+
+impl Gru {
+    pub const NAME: &str = "GRU";
+    const GATES: usize = 3;
+
+    pub fn new<'a, F>(units: usize, uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let gru = Self::builder(units, uplink);
+        return LayerRef::pin(gru);
+    }
+
+    pub fn builder<'a, F>(units: usize, uplink: F) -> Gru
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Gru {
+            units,
+            return_sequences: false,
+            parent: uplink().clone(),
+            activation: Box::new(Tanh),
+            recurrent_activation: Box::new(Sigmoid),
+            kernel_init: GlorothUniformSupplier::new().into_enum(),
+            recurrent_init: OrthogonalSupplier::new().into_enum(),
+            bias_init: ZeroSupplier::new().into_enum(),
+        };
+    }
+
+    pub fn with_return_sequences(mut self, return_sequences: bool) -> Gru {
+        self.return_sequences = return_sequences;
+        return self;
+    }
+
+    /**
+     * Of the candidate state, tanh by default
+     */
+    pub fn with_activation(mut self, activation: impl Activation) -> Gru {
+        self.activation = Box::new(activation);
+        return self;
+    }
+
+    /**
+     * Of the gates, sigmoid by default
+     */
+    pub fn with_recurrent_activation(mut self, activation: impl Activation) -> Gru {
+        self.recurrent_activation = Box::new(activation);
+        return self;
+    }
+
+    pub fn with_kernel_init(mut self, supplier: impl Supplier) -> Gru {
+        self.kernel_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn with_recurrent_init(mut self, supplier: impl Supplier) -> Gru {
+        self.recurrent_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn with_bias_init(mut self, supplier: impl Supplier) -> Gru {
+        self.bias_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn build(self) -> LayerRef {
+        return LayerRef::pin(self);
+    }
+}
+
+impl Layer for Gru {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        return (
+            Shape::Const(self.units),
+            recurrent::output_size(&self.parent, self.return_sequences),
+        );
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let parent_feats = recurrent::input_features(&self.parent, &id);
+        let stacked = Self::GATES * self.units;
+
+        let instance = GruImpl {
+            id,
+            units: self.units,
+            return_sequences: self.return_sequences,
+            kernel: self.kernel_init.supply_matrix(stacked, parent_feats),
+            recurrent_kernel: self.recurrent_init.supply_matrix(stacked, self.units),
+            bias: self.bias_init.supply_matrix(stacked, 2),
+            activation: self.activation.act_clone(),
+            recurrent_activation: self.recurrent_activation.act_clone(),
+        };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+pub struct GruImpl {
+    id: String,
+    units: usize,
+    return_sequences: bool,
+    /**
+     * features by 3 * units
+     */
+    kernel: NDMatrix,
+    /**
+     * units by 3 * units
+     */
+    recurrent_kernel: NDMatrix,
+    /**
+     * Input bias row then recurrent bias row
+     */
+    bias: NDMatrix,
+    activation: Box<dyn Activation>,
+    recurrent_activation: Box<dyn Activation>,
+}
+
+impl GruImpl {
+    /**
+     * One step for a batch of rows, the state is [hidden] and starts at zeros
+     */
+    pub(crate) fn next_state(
+        &self,
+        input: &Array2<f32>,
+        state: &[Array2<f32>],
+    ) -> Vec<Array2<f32>> {
+        let batch = input.nrows();
+        let hidden = recurrent::state_or_zeros(state, 0, batch, self.units);
+        let units = self.units;
+
+        let from_input = input.dot(&self.kernel.values) + self.bias.values.slice(s![0..1, ..]);
+        let from_hidden =
+            hidden.dot(&self.recurrent_kernel.values) + self.bias.values.slice(s![1..2, ..]);
+        let gates =
+            &from_input.slice(s![.., ..2 * units]) + &from_hidden.slice(s![.., ..2 * units]);
+        let update = recurrent::gate(&gates, 0, units, self.recurrent_activation.as_ref());
+        let reset = recurrent::gate(&gates, 1, units, self.recurrent_activation.as_ref());
+
+        let candidate =
+            &from_input.slice(s![.., 2 * units..]) + reset * from_hidden.slice(s![.., 2 * units..]);
+        let candidate = self
+            .activation
+            .apply(&NDMatrix::with(units, batch, candidate))
+            .values;
+        let next = &update * &hidden + (1.0 - update) * candidate;
+        return vec![next];
+    }
+}
+
+impl LayerBase for GruImpl {
+    fn init(&mut self) {}
+
+    fn create_from_ser(json: &JsonWrap, model_reader: &ModelReader) -> LayerPropagateEnum {
+        let deserialized: GruSerialization = json.to().unwrap();
+        let activations = model_reader.get_activation_di();
+        let impl_ref = GruImpl {
+            id: deserialized.id,
+            units: deserialized.units,
+            return_sequences: deserialized.return_sequences,
+            kernel: deserialized.kernel,
+            recurrent_kernel: deserialized.recurrent_kernel,
+            bias: deserialized.bias,
+            activation: activations.create(
+                &deserialized.activation.name,
+                &deserialized.activation.json,
+                model_reader,
+            ),
+            recurrent_activation: activations.create(
+                &deserialized.recurrent_activation.name,
+                &deserialized.recurrent_activation.json,
+                model_reader,
+            ),
+        };
+
+        return LayerPropagateEnum::SingleInput(Box::new(impl_ref));
+    }
+
+    fn to_json(&self) -> JsonWrap {
+        let serial = GruSerialization {
+            id: self.id.clone(),
+            units: self.units,
+            return_sequences: self.return_sequences,
+            kernel: self.kernel.clone(),
+            recurrent_kernel: self.recurrent_kernel.clone(),
+            bias: self.bias.clone(),
+            activation: self.activation.as_serialized(),
+            recurrent_activation: self.recurrent_activation.as_serialized(),
+        };
+        return JsonWrap::from(serial).unwrap();
+    }
+}
+
+impl LayerSingleInput for GruImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        return recurrent::run_sequence(input, self.units, self.return_sequences, |x, state| {
+            self.next_state(x, state)
+        });
+    }
+
+    fn step(&self, input: &NDMatrix, state: &mut Vec<NDMatrix>) -> NDMatrix {
+        return recurrent::step_state(input, self.units, state, |x, state| {
+            self.next_state(x, state)
+        });
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Gru::NAME,
+        create: GruImpl::create_from_ser,
+    }
+}
+
+/**
+ * Serialization
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+struct GruSerialization {
+    id: String,
+    units: usize,
+    return_sequences: bool,
+    kernel: NDMatrix,
+    recurrent_kernel: NDMatrix,
+    bias: NDMatrix,
+    activation: ActivationSerialised,
+    recurrent_activation: ActivationSerialised,
+}
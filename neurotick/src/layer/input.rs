@@ -5,7 +5,7 @@ use crate::{
         meta::{node::LayerType, shape::Shape},
         nmatrix::NDMatrix,
     },
-    serial::model_reader::ModelReader,
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
     utils::json_wrap::JsonWrap,
 };
 
@@ -54,6 +54,12 @@ pub struct InputImpl {
     size: Shape,
 }
 
+impl InputImpl {
+    pub(crate) fn new(id: String, features: Shape, size: Shape) -> InputImpl {
+        return InputImpl { id, features, size };
+    }
+}
+
 impl LayerBase for InputImpl {
     fn init(&mut self) {}
 
@@ -83,6 +89,13 @@ impl LayerSingleInput for InputImpl {
     }
 }
 
+inventory::submit! {
+    LayerRegistration {
+        name: Input::NAME,
+        create: InputImpl::create_from_ser,
+    }
+}
+
 /**
  * Serialization
  */
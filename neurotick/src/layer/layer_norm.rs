@@ -0,0 +1,162 @@
+use ndarray::{Array2, Axis};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    utils::json_wrap::JsonWrap,
+};
+
+use super::abs::{Layer, LayerBase, LayerPropagateEnum, LayerRef, LayerSingleInput};
+
+/**
+ * Normalizes each row over its features to zero mean and unit variance, then scales by gamma
+ * and shifts by beta per feature. Rows are independent so it behaves the same for any size.
+ */
+pub struct LayerNorm {
+    epsilon: f32,
+    parent: LayerRef,
+}
+
+impl LayerNorm {
+    pub const NAME: &str = "LayerNorm";
+
+    pub fn new<'a, F>(uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let norm = Self::builder(uplink);
+        return LayerRef::pin(norm);
+    }
+
+    pub fn builder<'a, F>(uplink: F) -> LayerNorm
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return LayerNorm {
+            epsilon: 1e-3,
+            parent: uplink().clone(),
+        };
+    }
+
+    /**
+     * Added to the variance, 1e-3 by default
+     */
+    pub fn with_epsilon(mut self, epsilon: f32) -> LayerNorm {
+        self.epsilon = epsilon;
+        return self;
+    }
+
+    pub fn build(self) -> LayerRef {
+        return LayerRef::pin(self);
+    }
+}
+
+impl Layer for LayerNorm {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        return self.parent.get_shape();
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let parent_feats = self.parent.get_shape().0.unwrap_to_conts();
+        if parent_feats == 0 {
+            panic!("Zero features in parent is not allowed, by: {}", id);
+        }
+        let instance = LayerNormImpl {
+            id,
+            epsilon: self.epsilon,
+            gamma: NDMatrix::constant(parent_feats, 1, 1.0),
+            beta: NDMatrix::new(parent_feats, 1),
+        };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+/**
+ * Each row to zero mean and unit variance over its features, then scaled by gamma and
+ * shifted by beta (1 by features)
+ */
+pub(crate) fn normalize_rows(
+    values: &Array2<f32>,
+    gamma: &NDMatrix,
+    beta: &NDMatrix,
+    epsilon: f32,
+) -> Array2<f32> {
+    let mut normalized = values.clone();
+    for mut row in normalized.axis_iter_mut(Axis(0)) {
+        let mean = row.mean().unwrap_or(0.0);
+        let variance = row.mapv(|v| (v - mean).powi(2)).mean().unwrap_or(0.0);
+        let deviation = (variance + epsilon).sqrt();
+        row.mapv_inplace(|v| (v - mean) / deviation);
+    }
+    return normalized * &gamma.values + &beta.values;
+}
+
+pub struct LayerNormImpl {
+    id: String,
+    epsilon: f32,
+    gamma: NDMatrix,
+    beta: NDMatrix,
+}
+
+impl LayerBase for LayerNormImpl {
+    fn init(&mut self) {}
+
+    fn create_from_ser(json: &JsonWrap, _model_reader: &ModelReader) -> LayerPropagateEnum {
+        let deserialized: LayerNormSerialization = json.to().unwrap();
+        let impl_ref = LayerNormImpl {
+            id: deserialized.id,
+            epsilon: deserialized.epsilon,
+            gamma: deserialized.gamma,
+            beta: deserialized.beta,
+        };
+        return LayerPropagateEnum::SingleInput(Box::new(impl_ref));
+    }
+
+    fn to_json(&self) -> JsonWrap {
+        let serial = LayerNormSerialization {
+            id: self.id.clone(),
+            epsilon: self.epsilon,
+            gamma: self.gamma.clone(),
+            beta: self.beta.clone(),
+        };
+        return JsonWrap::from(serial).unwrap();
+    }
+}
+
+impl LayerSingleInput for LayerNormImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        let values = normalize_rows(&input.values, &self.gamma, &self.beta, self.epsilon);
+        return NDMatrix::with(input.width, input.height, values);
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: LayerNorm::NAME,
+        create: LayerNormImpl::create_from_ser,
+    }
+}
+
+/**
+ * Serialization
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+struct LayerNormSerialization {
+    id: String,
+    epsilon: f32,
+    gamma: NDMatrix,
+    beta: NDMatrix,
+}
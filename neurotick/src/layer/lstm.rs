@@ -0,0 +1,290 @@
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    activation::{
+        abs::{Activation, ActivationSerialised},
+        sigmoid::Sigmoid,
+        tanh::Tanh,
+    },
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    suppliers::suppliers::{
+        GlorothUniformSupplier, OrthogonalSupplier, Supplier, Suppliers, ZeroSupplier,
+    },
+    utils::json_wrap::JsonWrap,
+};
+
+use super::{
+    abs::{Layer, LayerBase, LayerPropagateEnum, LayerRef, LayerSingleInput},
+    recurrent,
+};
+
+/**
+ * Long short-term memory over the size axis, one step per row of the parent. Outputs the
+ * last hidden state as a single row, or one per step with return_sequences.
+ * Weights follow keras with the gates stacked as input, forget, cell, output.
+ */
+pub struct Lstm {
+    units: usize,
+    return_sequences: bool,
+    parent: LayerRef,
+    activation: Box<dyn Activation>,
+    recurrent_activation: Box<dyn Activation>,
+    kernel_init: Suppliers,
+    recurrent_init: Suppliers,
+    bias_init: Suppliers,
+    unit_forget_bias: bool,
+}
+
+impl Lstm {
+    pub const NAME: &str = "LSTM";
+    const GATES: usize = 4;
+
+    pub fn new<'a, F>(units: usize, uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let lstm = Self::builder(units, uplink);
+        return LayerRef::pin(lstm);
+    }
+
+    pub fn builder<'a, F>(units: usize, uplink: F) -> Lstm
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Lstm {
+            units,
+            return_sequences: false,
+            parent: uplink().clone(),
+            activation: Box::new(Tanh),
+            recurrent_activation: Box::new(Sigmoid),
+            kernel_init: GlorothUniformSupplier::new().into_enum(),
+            recurrent_init: OrthogonalSupplier::new().into_enum(),
+            bias_init: ZeroSupplier::new().into_enum(),
+            unit_forget_bias: true,
+        };
+    }
+
+    pub fn with_return_sequences(mut self, return_sequences: bool) -> Lstm {
+        self.return_sequences = return_sequences;
+        return self;
+    }
+
+    /**
+     * Of the candidate cell and the output, tanh by default
+     */
+    pub fn with_activation(mut self, activation: impl Activation) -> Lstm {
+        self.activation = Box::new(activation);
+        return self;
+    }
+
+    /**
+     * Of the gates, sigmoid by default
+     */
+    pub fn with_recurrent_activation(mut self, activation: impl Activation) -> Lstm {
+        self.recurrent_activation = Box::new(activation);
+        return self;
+    }
+
+    pub fn with_kernel_init(mut self, supplier: impl Supplier) -> Lstm {
+        self.kernel_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn with_recurrent_init(mut self, supplier: impl Supplier) -> Lstm {
+        self.recurrent_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn with_bias_init(mut self, supplier: impl Supplier) -> Lstm {
+        self.bias_init = supplier.into_enum();
+        return self;
+    }
+
+    /**
+     * Adds one to the initial forget gate bias so the cell keeps its state early on, on by
+     * default
+     */
+    pub fn with_unit_forget_bias(mut self, unit_forget_bias: bool) -> Lstm {
+        self.unit_forget_bias = unit_forget_bias;
+        return self;
+    }
+
+    pub fn build(self) -> LayerRef {
+        return LayerRef::pin(self);
+    }
+}
+
+impl Layer for Lstm {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        return (
+            Shape::Const(self.units),
+            recurrent::output_size(&self.parent, self.return_sequences),
+        );
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let parent_feats = recurrent::input_features(&self.parent, &id);
+        let stacked = Self::GATES * self.units;
+
+        let kernel = self.kernel_init.supply_matrix(stacked, parent_feats);
+        let recurrent_kernel = self.recurrent_init.supply_matrix(stacked, self.units);
+        let mut bias = self.bias_init.supply_matrix(stacked, 1);
+        if self.unit_forget_bias {
+            bias.values
+                .slice_mut(ndarray::s![.., self.units..2 * self.units])
+                .mapv_inplace(|b| b + 1.0);
+        }
+        let instance = LstmImpl {
+            id,
+            units: self.units,
+            return_sequences: self.return_sequences,
+            kernel,
+            recurrent_kernel,
+            bias,
+            activation: self.activation.act_clone(),
+            recurrent_activation: self.recurrent_activation.act_clone(),
+        };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+pub struct LstmImpl {
+    id: String,
+    units: usize,
+    return_sequences: bool,
+    /**
+     * features by 4 * units
+     */
+    kernel: NDMatrix,
+    /**
+     * units by 4 * units
+     */
+    recurrent_kernel: NDMatrix,
+    bias: NDMatrix,
+    activation: Box<dyn Activation>,
+    recurrent_activation: Box<dyn Activation>,
+}
+
+impl LstmImpl {
+    /**
+     * One step for a batch of rows, the state is [hidden, cell] and starts at zeros
+     */
+    pub(crate) fn next_state(
+        &self,
+        input: &Array2<f32>,
+        state: &[Array2<f32>],
+    ) -> Vec<Array2<f32>> {
+        let batch = input.nrows();
+        let hidden = recurrent::state_or_zeros(state, 0, batch, self.units);
+        let cell = recurrent::state_or_zeros(state, 1, batch, self.units);
+
+        let stacked = input.dot(&self.kernel.values)
+            + hidden.dot(&self.recurrent_kernel.values)
+            + &self.bias.values;
+        let gates = self.recurrent_activation.as_ref();
+        let input_gate = recurrent::gate(&stacked, 0, self.units, gates);
+        let forget_gate = recurrent::gate(&stacked, 1, self.units, gates);
+        let candidate = recurrent::gate(&stacked, 2, self.units, self.activation.as_ref());
+        let output_gate = recurrent::gate(&stacked, 3, self.units, gates);
+
+        let cell = forget_gate * &cell + input_gate * candidate;
+        let activated = self
+            .activation
+            .apply(&NDMatrix::with(self.units, batch, cell.clone()))
+            .values;
+        return vec![output_gate * activated, cell];
+    }
+}
+
+impl LayerBase for LstmImpl {
+    fn init(&mut self) {}
+
+    fn create_from_ser(json: &JsonWrap, model_reader: &ModelReader) -> LayerPropagateEnum {
+        let deserialized: LstmSerialization = json.to().unwrap();
+        let activations = model_reader.get_activation_di();
+        let impl_ref = LstmImpl {
+            id: deserialized.id,
+            units: deserialized.units,
+            return_sequences: deserialized.return_sequences,
+            kernel: deserialized.kernel,
+            recurrent_kernel: deserialized.recurrent_kernel,
+            bias: deserialized.bias,
+            activation: activations.create(
+                &deserialized.activation.name,
+                &deserialized.activation.json,
+                model_reader,
+            ),
+            recurrent_activation: activations.create(
+                &deserialized.recurrent_activation.name,
+                &deserialized.recurrent_activation.json,
+                model_reader,
+            ),
+        };
+
+        return LayerPropagateEnum::SingleInput(Box::new(impl_ref));
+    }
+
+    fn to_json(&self) -> JsonWrap {
+        let serial = LstmSerialization {
+            id: self.id.clone(),
+            units: self.units,
+            return_sequences: self.return_sequences,
+            kernel: self.kernel.clone(),
+            recurrent_kernel: self.recurrent_kernel.clone(),
+            bias: self.bias.clone(),
+            activation: self.activation.as_serialized(),
+            recurrent_activation: self.recurrent_activation.as_serialized(),
+        };
+        return JsonWrap::from(serial).unwrap();
+    }
+}
+
+impl LayerSingleInput for LstmImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        return recurrent::run_sequence(input, self.units, self.return_sequences, |x, state| {
+            self.next_state(x, state)
+        });
+    }
+
+    fn step(&self, input: &NDMatrix, state: &mut Vec<NDMatrix>) -> NDMatrix {
+        return recurrent::step_state(input, self.units, state, |x, state| {
+            self.next_state(x, state)
+        });
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Lstm::NAME,
+        create: LstmImpl::create_from_ser,
+    }
+}
+
+/**
+ * Serialization
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+struct LstmSerialization {
+    id: String,
+    units: usize,
+    return_sequences: bool,
+    kernel: NDMatrix,
+    recurrent_kernel: NDMatrix,
+    bias: NDMatrix,
+    activation: ActivationSerialised,
+    recurrent_activation: ActivationSerialised,
+}
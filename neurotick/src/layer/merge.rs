@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    utils::{extensions::Distinct, json_wrap::JsonWrap},
+};
+
+use super::abs::{Layer, LayerBase, LayerMultiInput, LayerPropagateEnum, LayerRef};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeKind {
+    Add,
+    /** First parent minus the second, exactly two parents */
+    Subtract,
+    Multiply,
+    Average,
+    Maximum,
+}
+
+/**
+ * Elementwise merge of parents with the same features and size, ex.: residual connections
+ * or gating. Built with Add, Subtract, Multiply, Average or Maximum.
+ */
+pub struct Merge {
+    kind: MergeKind,
+    parents: Vec<LayerRef>,
+    features: Shape,
+    size: Shape,
+}
+
+pub struct Add;
+
+pub struct Subtract;
+
+pub struct Multiply;
+
+pub struct Average;
+
+pub struct Maximum;
+
+macro_rules! merge_layer {
+    ($layer:ident, $kind:expr, $name:literal) => {
+        impl $layer {
+            pub const NAME: &str = $name;
+
+            pub fn new<'a, F>(uplinks: F) -> LayerRef
+            where
+                F: Fn() -> Vec<&'a LayerRef>,
+            {
+                return LayerRef::pin(Merge::builder($kind, uplinks));
+            }
+        }
+    };
+}
+
+merge_layer!(Add, MergeKind::Add, "Add");
+merge_layer!(Subtract, MergeKind::Subtract, "Subtract");
+merge_layer!(Multiply, MergeKind::Multiply, "Multiply");
+merge_layer!(Average, MergeKind::Average, "Average");
+merge_layer!(Maximum, MergeKind::Maximum, "Maximum");
+
+impl Merge {
+    /**
+     * Panics when the parents differ in features or size, or on a wrong parent count
+     */
+    pub fn builder<'a, F>(kind: MergeKind, uplinks: F) -> Merge
+    where
+        F: Fn() -> Vec<&'a LayerRef>,
+    {
+        let uplink_vec = uplinks();
+        let name = kind.type_name();
+        match kind {
+            MergeKind::Subtract if uplink_vec.len() != 2 => {
+                panic!("{} takes exactly 2 parents", name)
+            }
+            _ if uplink_vec.len() < 2 => panic!("{} takes at least 2 parents", name),
+            _ => {}
+        }
+
+        let features = uplink_vec
+            .iter()
+            .distinct_vec(|l| l.get_shape().0.unwrap_to_conts());
+        if features.len() != 1 {
+            panic!("Different features in {}: {:?}", name, features);
+        }
+        let size = Self::merged_size(name, &uplink_vec);
+
+        return Merge {
+            kind,
+            parents: uplink_vec.iter().map(|u| (*u).clone()).collect(),
+            features: Shape::Const(features[0]),
+            size,
+        };
+    }
+
+    /**
+     * Identical sizes are kept, a constant mixed with unknown ones is only checked when
+     * propagating
+     */
+    fn merged_size(name: &str, parents: &[&LayerRef]) -> Shape {
+        let sizes: Vec<Shape> = parents.iter().map(|l| l.get_shape().1).collect();
+        if sizes.iter().all(|s| *s == sizes[0]) {
+            return sizes[0].clone();
+        }
+        let fixed = sizes
+            .iter()
+            .filter(|s| matches!(s, Shape::Const(_) | Shape::Grid(..)))
+            .distinct_vec(|s| s.unwrap_to_conts());
+        if fixed.len() > 1 || sizes.iter().any(|s| matches!(s, Shape::Grid(..))) {
+            panic!("Different sizes in {}: {:?}", name, sizes);
+        }
+        return Shape::Variable;
+    }
+}
+
+impl MergeKind {
+    fn type_name(&self) -> &'static str {
+        return match self {
+            MergeKind::Add => Add::NAME,
+            MergeKind::Subtract => Subtract::NAME,
+            MergeKind::Multiply => Multiply::NAME,
+            MergeKind::Average => Average::NAME,
+            MergeKind::Maximum => Maximum::NAME,
+        };
+    }
+
+    fn merge(&self, inputs: &[&NDMatrix]) -> NDMatrix {
+        let first = inputs[0];
+        if let Some(other) = inputs
+            .iter()
+            .find(|m| m.width != first.width || m.height != first.height)
+        {
+            panic!(
+                "{} of {}x{} and {}x{} matrices",
+                self.type_name(),
+                first.width,
+                first.height,
+                other.width,
+                other.height
+            );
+        }
+
+        let mut values = first.values.clone();
+        for input in inputs.iter().skip(1) {
+            match self {
+                MergeKind::Add | MergeKind::Average => values += &input.values,
+                MergeKind::Subtract => values -= &input.values,
+                MergeKind::Multiply => values *= &input.values,
+                MergeKind::Maximum => values.zip_mut_with(&input.values, |a, b| *a = a.max(*b)),
+            }
+        }
+        if *self == MergeKind::Average {
+            values /= inputs.len() as f32;
+        }
+        return NDMatrix::with(first.width, first.height, values);
+    }
+}
+
+impl Layer for Merge {
+    fn type_name(&self) -> &'static str {
+        return self.kind.type_name();
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        return (self.features.clone(), self.size.clone());
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::MultipleParent(self.parents.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let instance = MergeImpl {
+            id,
+            kind: self.kind,
+        };
+        LayerPropagateEnum::MultipleInput(Box::new(instance))
+    }
+}
+
+/**
+ * Not derived with LayerImpl, the one instance is registered under every merge name
+ */
+pub struct MergeImpl {
+    id: String,
+    kind: MergeKind,
+}
+
+impl LayerBase for MergeImpl {
+    fn init(&mut self) {}
+
+    fn create_from_ser(json: &JsonWrap, _model_reader: &ModelReader) -> LayerPropagateEnum {
+        let deserialized: MergeSerialization = json.to().unwrap();
+        let impl_ref = MergeImpl {
+            id: deserialized.id,
+            kind: deserialized.kind,
+        };
+        return LayerPropagateEnum::MultipleInput(Box::new(impl_ref));
+    }
+
+    fn to_json(&self) -> JsonWrap {
+        let serial = MergeSerialization {
+            id: self.id.clone(),
+            kind: self.kind,
+        };
+        return JsonWrap::from(serial).unwrap();
+    }
+}
+
+impl LayerMultiInput for MergeImpl {
+    fn propagate_multi(&self, inputs: &Vec<&NDMatrix>) -> NDMatrix {
+        return self.kind.merge(inputs);
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Add::NAME,
+        create: MergeImpl::create_from_ser,
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Subtract::NAME,
+        create: MergeImpl::create_from_ser,
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Multiply::NAME,
+        create: MergeImpl::create_from_ser,
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Average::NAME,
+        create: MergeImpl::create_from_ser,
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Maximum::NAME,
+        create: MergeImpl::create_from_ser,
+    }
+}
+
+/**
+ * Serialization
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+struct MergeSerialization {
+    id: String,
+    kind: MergeKind,
+}
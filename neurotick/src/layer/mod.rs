@@ -1,7 +1,23 @@
 pub mod abs;
+pub mod attention;
+pub mod batch_norm;
 pub mod concat;
+pub mod conv1d;
+pub mod conv2d;
 pub mod dense;
 pub mod direct;
-pub mod input;
+pub mod embedding;
 pub mod flatten;
+pub mod gru;
+pub mod input;
+pub mod layer_norm;
+pub mod lstm;
+pub mod merge;
+pub mod noise;
+pub mod pooling;
+mod recurrent;
+pub mod reshape;
+pub mod slice;
 mod tests;
+pub mod transformer;
+pub mod transpose;
@@ -0,0 +1,299 @@
+use std::cell::RefCell;
+
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    utils::json_wrap::JsonWrap,
+};
+
+use super::abs::{Layer, LayerBase, LayerPropagateEnum, LayerRef, LayerSingleInput, Mode};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoiseKind {
+    /** Zeros a share of the values, the others are scaled up to keep the sum */
+    Dropout { rate: f32 },
+    /** Adds zero centered normal noise */
+    Gaussian { std_dev: f32 },
+    /**
+     * Sets a share of the values to the negative saturation of SeLu and keeps the mean and
+     * variance of the input, for self-normalizing networks
+     */
+    AlphaDropout { rate: f32 },
+}
+
+/**
+ * Random regularization active in Mode::Training only, the identity at inference. Built with
+ * Dropout, GaussianNoise or AlphaDropout. With a seed, every switch to training restarts the
+ * same noise sequence so noisy evaluations can be reproduced.
+ */
+pub struct Noise {
+    kind: NoiseKind,
+    seed: Option<u64>,
+    parent: LayerRef,
+}
+
+pub struct Dropout;
+
+pub struct GaussianNoise;
+
+pub struct AlphaDropout;
+
+impl Dropout {
+    pub const NAME: &str = "Dropout";
+
+    pub fn new<'a, F>(rate: f32, uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return LayerRef::pin(Self::builder(rate, uplink));
+    }
+
+    pub fn builder<'a, F>(rate: f32, uplink: F) -> Noise
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Noise::builder(NoiseKind::Dropout { rate }, uplink);
+    }
+}
+
+impl GaussianNoise {
+    pub const NAME: &str = "GaussianNoise";
+
+    pub fn new<'a, F>(std_dev: f32, uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return LayerRef::pin(Self::builder(std_dev, uplink));
+    }
+
+    pub fn builder<'a, F>(std_dev: f32, uplink: F) -> Noise
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Noise::builder(NoiseKind::Gaussian { std_dev }, uplink);
+    }
+}
+
+impl AlphaDropout {
+    pub const NAME: &str = "AlphaDropout";
+
+    pub fn new<'a, F>(rate: f32, uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return LayerRef::pin(Self::builder(rate, uplink));
+    }
+
+    pub fn builder<'a, F>(rate: f32, uplink: F) -> Noise
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Noise::builder(NoiseKind::AlphaDropout { rate }, uplink);
+    }
+}
+
+impl Noise {
+    pub fn builder<'a, F>(kind: NoiseKind, uplink: F) -> Noise
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Noise {
+            kind,
+            seed: None,
+            parent: uplink().clone(),
+        };
+    }
+
+    /**
+     * Seeds the noise, from entropy by default
+     */
+    pub fn with_seed(mut self, seed: u64) -> Noise {
+        self.seed = Some(seed);
+        return self;
+    }
+
+    pub fn build(self) -> LayerRef {
+        return LayerRef::pin(self);
+    }
+}
+
+impl NoiseKind {
+    /**
+     * -scale * alpha of SeLu
+     */
+    const ALPHA_PRIME: f32 = -1.7580993;
+
+    fn type_name(&self) -> &'static str {
+        return match self {
+            NoiseKind::Dropout { .. } => Dropout::NAME,
+            NoiseKind::Gaussian { .. } => GaussianNoise::NAME,
+            NoiseKind::AlphaDropout { .. } => AlphaDropout::NAME,
+        };
+    }
+
+    fn check(&self, id: &str) {
+        let valid = match self {
+            NoiseKind::Dropout { rate } | NoiseKind::AlphaDropout { rate } => {
+                (0.0..1.0).contains(rate)
+            }
+            NoiseKind::Gaussian { std_dev } => *std_dev >= 0.0,
+        };
+        if !valid {
+            panic!(
+                "Rate must be within [0, 1) and deviation positive, by: {}",
+                id
+            );
+        }
+    }
+
+    fn apply(&self, input: &NDMatrix, rng: &mut SmallRng) -> NDMatrix {
+        let values = match *self {
+            NoiseKind::Dropout { rate } => {
+                let scale = 1.0 / (1.0 - rate);
+                input.values.mapv(|v| {
+                    if rng.gen::<f32>() < rate {
+                        return 0.0;
+                    }
+                    return v * scale;
+                })
+            }
+            NoiseKind::Gaussian { std_dev } => input
+                .values
+                .mapv(|v| v + rng.sample::<f32, _>(StandardNormal) * std_dev),
+            NoiseKind::AlphaDropout { rate } => {
+                let alpha = Self::ALPHA_PRIME;
+                let a = ((1.0 - rate) * (1.0 + rate * alpha * alpha)).powf(-0.5);
+                let b = -a * alpha * rate;
+                input.values.mapv(|v| {
+                    if rng.gen::<f32>() < rate {
+                        return a * alpha + b;
+                    }
+                    return a * v + b;
+                })
+            }
+        };
+        return NDMatrix::with(input.width, input.height, values);
+    }
+}
+
+impl Layer for Noise {
+    fn type_name(&self) -> &'static str {
+        return self.kind.type_name();
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        return self.parent.get_shape();
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        self.kind.check(&id);
+        let instance = NoiseImpl::new(id, self.kind, self.seed);
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+pub struct NoiseImpl {
+    id: String,
+    kind: NoiseKind,
+    seed: Option<u64>,
+    training: bool,
+    rng: RefCell<SmallRng>,
+}
+
+impl NoiseImpl {
+    fn new(id: String, kind: NoiseKind, seed: Option<u64>) -> NoiseImpl {
+        return NoiseImpl {
+            id,
+            kind,
+            seed,
+            training: false,
+            rng: RefCell::new(Self::rng(seed)),
+        };
+    }
+
+    fn rng(seed: Option<u64>) -> SmallRng {
+        return match seed {
+            Some(seed) => SmallRng::seed_from_u64(seed),
+            None => SmallRng::from_entropy(),
+        };
+    }
+}
+
+impl LayerBase for NoiseImpl {
+    fn init(&mut self) {}
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.training = mode == Mode::Training;
+        if self.training {
+            self.rng = RefCell::new(Self::rng(self.seed));
+        }
+    }
+
+    fn create_from_ser(json: &JsonWrap, _model_reader: &ModelReader) -> LayerPropagateEnum {
+        let deserialized: NoiseSerialization = json.to().unwrap();
+        let impl_ref = NoiseImpl::new(deserialized.id, deserialized.kind, deserialized.seed);
+        return LayerPropagateEnum::SingleInput(Box::new(impl_ref));
+    }
+
+    fn to_json(&self) -> JsonWrap {
+        let serial = NoiseSerialization {
+            id: self.id.clone(),
+            kind: self.kind,
+            seed: self.seed,
+        };
+        return JsonWrap::from(serial).unwrap();
+    }
+}
+
+impl LayerSingleInput for NoiseImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        if !self.training {
+            return input.clone();
+        }
+        return self.kind.apply(input, &mut self.rng.borrow_mut());
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: Dropout::NAME,
+        create: NoiseImpl::create_from_ser,
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: GaussianNoise::NAME,
+        create: NoiseImpl::create_from_ser,
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: AlphaDropout::NAME,
+        create: NoiseImpl::create_from_ser,
+    }
+}
+
+/**
+ * Serialization
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+struct NoiseSerialization {
+    id: String,
+    kind: NoiseKind,
+    seed: Option<u64>,
+}
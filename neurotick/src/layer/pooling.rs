@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    utils::json_wrap::JsonWrap,
+};
+
+use super::{
+    abs::{Layer, LayerBase, LayerPropagateEnum, LayerRef, LayerSingleInput},
+    conv1d::Padding,
+    conv2d::GridWindow,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolKind {
+    Max,
+    /** Mean of the cells under the window, padding excluded */
+    Average,
+}
+
+/**
+ * Per channel pooling over a Shape::Grid size, built with MaxPool2D or AvgPool2D. The stride
+ * defaults to the pool size. Like Conv2D a matrix holds one image.
+ */
+pub struct Pool2D {
+    kind: PoolKind,
+    pool: (usize, usize),
+    stride: Option<(usize, usize)>,
+    padding: Padding,
+    parent: LayerRef,
+}
+
+pub struct MaxPool2D;
+
+pub struct AvgPool2D;
+
+impl MaxPool2D {
+    pub const NAME: &str = "MaxPool2D";
+
+    pub fn new<'a, F>(pool: (usize, usize), uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return LayerRef::pin(Self::builder(pool, uplink));
+    }
+
+    pub fn builder<'a, F>(pool: (usize, usize), uplink: F) -> Pool2D
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Pool2D::builder(PoolKind::Max, pool, uplink);
+    }
+}
+
+impl AvgPool2D {
+    pub const NAME: &str = "AvgPool2D";
+
+    pub fn new<'a, F>(pool: (usize, usize), uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return LayerRef::pin(Self::builder(pool, uplink));
+    }
+
+    pub fn builder<'a, F>(pool: (usize, usize), uplink: F) -> Pool2D
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Pool2D::builder(PoolKind::Average, pool, uplink);
+    }
+}
+
+impl Pool2D {
+    pub fn builder<'a, F>(kind: PoolKind, pool: (usize, usize), uplink: F) -> Pool2D
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return Pool2D {
+            kind,
+            pool,
+            stride: None,
+            padding: Padding::Valid,
+            parent: uplink().clone(),
+        };
+    }
+
+    pub fn with_stride(mut self, stride: (usize, usize)) -> Pool2D {
+        self.stride = Some(stride);
+        return self;
+    }
+
+    pub fn with_padding(mut self, padding: Padding) -> Pool2D {
+        self.padding = padding;
+        return self;
+    }
+
+    pub fn build(self) -> LayerRef {
+        return LayerRef::pin(self);
+    }
+
+    fn window(&self) -> GridWindow {
+        return GridWindow::new(
+            self.type_name(),
+            &self.parent,
+            self.pool,
+            self.stride.unwrap_or(self.pool),
+            (1, 1),
+            self.padding,
+        );
+    }
+}
+
+impl PoolKind {
+    fn type_name(&self) -> &'static str {
+        return match self {
+            PoolKind::Max => MaxPool2D::NAME,
+            PoolKind::Average => AvgPool2D::NAME,
+        };
+    }
+}
+
+impl Layer for Pool2D {
+    fn type_name(&self) -> &'static str {
+        return self.kind.type_name();
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        let (height, width) = self.window().output_grid();
+        return (
+            self.parent.get_shape().0.clone(),
+            Shape::Grid(height, width),
+        );
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let window = self.window();
+        window.check(&id);
+        let instance = Pool2DImpl {
+            id,
+            kind: self.kind,
+            window,
+            cells: window.cells(),
+        };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+pub struct Pool2DImpl {
+    id: String,
+    kind: PoolKind,
+    window: GridWindow,
+    cells: Vec<Vec<Option<usize>>>,
+}
+
+impl LayerBase for Pool2DImpl {
+    fn init(&mut self) {}
+
+    fn create_from_ser(json: &JsonWrap, _model_reader: &ModelReader) -> LayerPropagateEnum {
+        let deserialized: Pool2DSerialization = json.to().unwrap();
+        let impl_ref = Pool2DImpl {
+            id: deserialized.id,
+            kind: deserialized.kind,
+            window: deserialized.window,
+            cells: deserialized.window.cells(),
+        };
+        return LayerPropagateEnum::SingleInput(Box::new(impl_ref));
+    }
+
+    fn to_json(&self) -> JsonWrap {
+        let serial = Pool2DSerialization {
+            id: self.id.clone(),
+            kind: self.kind,
+            window: self.window,
+        };
+        return JsonWrap::from(serial).unwrap();
+    }
+}
+
+impl LayerSingleInput for Pool2DImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        self.window.check_input(input, &self.id);
+        let mut output = NDMatrix::new(input.width, self.cells.len());
+        for (row, taps) in self.cells.iter().enumerate() {
+            let rows: Vec<usize> = taps.iter().flatten().copied().collect();
+            for channel in 0..input.width {
+                let values = rows.iter().map(|r| input.values[[*r, channel]]);
+                output.values[[row, channel]] = match self.kind {
+                    PoolKind::Max => values.fold(f32::NEG_INFINITY, f32::max),
+                    PoolKind::Average => values.sum::<f32>() / rows.len().max(1) as f32,
+                };
+            }
+        }
+        return output;
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: MaxPool2D::NAME,
+        create: Pool2DImpl::create_from_ser,
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: AvgPool2D::NAME,
+        create: Pool2DImpl::create_from_ser,
+    }
+}
+
+/**
+ * Serialization
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Pool2DSerialization {
+    id: String,
+    kind: PoolKind,
+    #[serde(flatten)]
+    window: GridWindow,
+}
@@ -0,0 +1,122 @@
+use ndarray::{s, Array2, Axis};
+
+use crate::{
+    activation::abs::Activation,
+    matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+};
+
+use super::abs::LayerRef;
+
+/**
+ * Output size of a recurrent layer, one row per step of the parent or only the last one
+ */
+pub(crate) fn output_size(parent: &LayerRef, return_sequences: bool) -> Shape {
+    if return_sequences {
+        return parent.get_shape().1;
+    }
+    return Shape::Const(1);
+}
+
+pub(crate) fn input_features(parent: &LayerRef, id: &str) -> usize {
+    let parent_feats = parent.get_shape().0.unwrap_to_conts();
+    if parent_feats == 0 {
+        panic!("Zero features in parent is not allowed, by: {}", id);
+    }
+    return parent_feats;
+}
+
+/**
+ * Columns index * units..(index + 1) * units of stacked gate values, activated
+ */
+pub(crate) fn gate(
+    stacked: &Array2<f32>,
+    index: usize,
+    units: usize,
+    activation: &dyn Activation,
+) -> Array2<f32> {
+    let columns = stacked
+        .slice(s![.., index * units..(index + 1) * units])
+        .to_owned();
+    return activation
+        .apply(&NDMatrix::with(units, stacked.nrows(), columns))
+        .values;
+}
+
+/**
+ * Runs a step per row of the input, each seeing the row as a batch of one. The state is the
+ * hidden rows first, then whatever else the layer carries, e.g. the LSTM cell.
+ */
+pub(crate) fn run_sequence<F>(
+    input: &NDMatrix,
+    units: usize,
+    return_sequences: bool,
+    step: F,
+) -> NDMatrix
+where
+    F: Fn(&Array2<f32>, &[Array2<f32>]) -> Vec<Array2<f32>>,
+{
+    let mut state: Vec<Array2<f32>> = vec![];
+    let mut hidden: Vec<Array2<f32>> = Vec::with_capacity(input.height);
+    for row in input.values.axis_chunks_iter(Axis(0), 1) {
+        state = step(&row.to_owned(), &state);
+        if return_sequences {
+            hidden.push(state[0].clone());
+        }
+    }
+    if !return_sequences {
+        let last = state
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| Array2::zeros((1, units)));
+        return NDMatrix::with(units, 1, last);
+    }
+    let views: Vec<_> = hidden.iter().map(|h| h.view()).collect();
+    let stacked =
+        ndarray::concatenate(Axis(0), &views).unwrap_or_else(|_| Array2::zeros((0, units)));
+    return NDMatrix::with(units, input.height, stacked);
+}
+
+/**
+ * Entry of a state, zeros for a missing one
+ */
+pub(crate) fn state_or_zeros(
+    state: &[Array2<f32>],
+    index: usize,
+    batch: usize,
+    units: usize,
+) -> Array2<f32> {
+    return state
+        .get(index)
+        .cloned()
+        .unwrap_or_else(|| Array2::zeros((batch, units)));
+}
+
+/**
+ * LayerSingleInput::step of a recurrent layer: one step for the batch of input rows from the
+ * state kept between ticks, returns the new hidden rows
+ */
+pub(crate) fn step_state<F>(
+    input: &NDMatrix,
+    units: usize,
+    state: &mut Vec<NDMatrix>,
+    step: F,
+) -> NDMatrix
+where
+    F: Fn(&Array2<f32>, &[Array2<f32>]) -> Vec<Array2<f32>>,
+{
+    if let Some(previous) = state.first() {
+        if previous.height != input.height {
+            panic!(
+                "State of {} rows for a batch of {}, reset it when the batch changes",
+                previous.height, input.height
+            );
+        }
+    }
+    let current: Vec<Array2<f32>> = state.iter().map(|m| m.values.clone()).collect();
+    let next = step(&input.values, &current);
+    *state = next
+        .into_iter()
+        .map(|values| NDMatrix::with(units, input.height, values))
+        .collect();
+    return state[0].clone();
+}
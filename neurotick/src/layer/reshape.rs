@@ -0,0 +1,104 @@
+use crate::matrix::{
+    meta::{node::LayerType, shape::Shape},
+    nmatrix::NDMatrix,
+};
+
+use super::abs::{Layer, LayerImpl, LayerPropagateEnum, LayerRef, LayerSingleInput};
+
+/**
+ * Reads the values of the parent row by row into rows of the given features, ex.: 12 features
+ * in 1 row to 3 features in 4 rows. A Shape::Variable size takes as many rows as the values
+ * fill, a constant or grid size must hold them all.
+ */
+pub struct Reshape {
+    features: usize,
+    size: Shape,
+    parent: LayerRef,
+}
+
+impl Reshape {
+    pub const NAME: &str = "Reshape";
+
+    /**
+     * Panics when both shapes are known and the value counts differ
+     */
+    pub fn new<'a, F>(features: usize, size: Shape, uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let parent = uplink().clone();
+        if features == 0 {
+            panic!("Zero features in Reshape");
+        }
+        if let Shape::Repeat = size {
+            panic!("Reshape to a repeated size, use Shape::Variable");
+        }
+        let (parent_features, parent_size) = parent.get_shape();
+        let known = |s: &Shape| matches!(s, Shape::Const(_) | Shape::Grid(..));
+        if known(&parent_features) && known(&parent_size) && known(&size) {
+            let count = parent_features.unwrap_to_conts() * parent_size.unwrap_to_conts();
+            if count != features * size.unwrap_to_conts() {
+                panic!(
+                    "Reshape of {} values to {} features by {:?}",
+                    count, features, size
+                );
+            }
+        }
+        let reshape = Reshape {
+            features,
+            size,
+            parent,
+        };
+        return LayerRef::pin(reshape);
+    }
+}
+
+impl Layer for Reshape {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        return (Shape::Const(self.features), self.size.clone());
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let instance = ReshapeImpl {
+            id,
+            features: self.features,
+            size: self.size.clone(),
+        };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+#[derive(LayerImpl)]
+#[layer(name = Reshape::NAME, input = "single")]
+pub struct ReshapeImpl {
+    id: String,
+    features: usize,
+    size: Shape,
+}
+
+impl LayerSingleInput for ReshapeImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        let count = input.width * input.height;
+        let rows = count / self.features;
+        let fits = match self.size {
+            Shape::Const(_) | Shape::Grid(..) => self.size.unwrap_to_conts() == rows,
+            _ => true,
+        };
+        if count % self.features != 0 || !fits {
+            panic!(
+                "Reshape of {} values to {} features by {:?}, by: {}",
+                count, self.features, self.size, self.id
+            );
+        }
+        let values = input.values.iter().copied().collect();
+        return NDMatrix::from_raw_vec(self.features, rows, values);
+    }
+}
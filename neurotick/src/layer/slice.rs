@@ -0,0 +1,123 @@
+use std::ops::Range;
+
+use indexmap::IndexMap;
+
+use crate::matrix::{
+    meta::{node::LayerType, shape::Shape},
+    nmatrix::NDMatrix,
+};
+
+use super::abs::{Layer, LayerImpl, LayerPropagateEnum, LayerRef, LayerSingleInput};
+
+/**
+ * Keeps the features of the parent within the range, ex.: the position part of an observation
+ * vector. The size is the one of the parent.
+ */
+pub struct Slice {
+    range: Range<usize>,
+    parent: LayerRef,
+}
+
+impl Slice {
+    pub const NAME: &str = "Slice";
+
+    /**
+     * Panics on an empty range or one past the features of the parent
+     */
+    pub fn new<'a, F>(range: Range<usize>, uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let parent = uplink().clone();
+        let features = parent.get_shape().0.unwrap_to_conts();
+        if range.is_empty() || range.end > features {
+            panic!("Slice of {:?} out of {} features", range, features);
+        }
+        let slice = Slice { range, parent };
+        return LayerRef::pin(slice);
+    }
+}
+
+/**
+ * Fans one parent out into named branches of consecutive features, one Slice each. The widths
+ * must add up to the features of the parent.
+ */
+pub struct Split;
+
+impl Split {
+    pub fn new<'a, F>(sections: Vec<(&str, usize)>, uplink: F) -> IndexMap<String, LayerRef>
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let parent = uplink();
+        let features = parent.get_shape().0.unwrap_to_conts();
+        let total: usize = sections.iter().map(|(_, width)| width).sum();
+        if total != features {
+            panic!(
+                "Split of {} features into sections of {} features",
+                features, total
+            );
+        }
+
+        let mut branches = IndexMap::new();
+        let mut start = 0;
+        for (name, width) in sections {
+            let slice = Slice::new(start..start + width, || parent);
+            if branches.insert(name.to_string(), slice).is_some() {
+                panic!("Duplicate branch {} in Split", name);
+            }
+            start += width;
+        }
+        return branches;
+    }
+}
+
+impl Layer for Slice {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        return (
+            Shape::Const(self.range.len()),
+            self.parent.get_shape().1.clone(),
+        );
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let instance = SliceImpl {
+            id,
+            start: self.range.start,
+            end: self.range.end,
+        };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+#[derive(LayerImpl)]
+#[layer(name = Slice::NAME, input = "single")]
+pub struct SliceImpl {
+    id: String,
+    start: usize,
+    end: usize,
+}
+
+impl LayerSingleInput for SliceImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        if self.end > input.width {
+            panic!(
+                "Slice of {}..{} out of {} features, by: {}",
+                self.start, self.end, input.width, self.id
+            );
+        }
+        let values = input
+            .values
+            .slice(ndarray::s![.., self.start..self.end])
+            .to_owned();
+        return NDMatrix::with(self.end - self.start, input.height, values);
+    }
+}
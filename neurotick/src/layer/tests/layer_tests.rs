@@ -3,13 +3,38 @@ mod test {
     use indexmap::IndexMap;
 
     use crate::{
+        activation::{abs::Activation, sigmoid::Sigmoid, softmax::SoftMax, tanh::Tanh},
         builder::builder::ModelBuilder,
         layer::{
-            abs::LayerRef, concat::Concat, dense::Dense, direct::Direct, flatten::Flatten,
+            abs::{LayerRef, Mode},
+            attention::{AttentionMask, MultiHeadAttention},
+            batch_norm::BatchNorm,
+            concat::Concat,
+            conv1d::{Conv1D, Padding},
+            conv2d::Conv2D,
+            dense::Dense,
+            direct::Direct,
+            embedding::Embedding,
+            flatten::Flatten,
+            gru::Gru,
             input::Input,
+            layer_norm::LayerNorm,
+            lstm::Lstm,
+            merge::{Add, Average, Maximum, Multiply, Subtract},
+            noise::{AlphaDropout, Dropout, GaussianNoise},
+            pooling::{AvgPool2D, MaxPool2D},
+            reshape::Reshape,
+            slice::{Slice, Split},
+            transformer::TransformerEncoder,
+            transpose::Transpose,
         },
         map,
         matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        serial::{
+            model_reader::ModelReader, model_weights::ModelWeights,
+            weight_serial::WeightsSerialized,
+        },
+        suppliers::suppliers::{RandomNormalSupplier, RandomUniformSupplier},
     };
 
     #[test]
@@ -65,6 +90,22 @@ mod test {
         dbg!(&output_data);
     }
 
+    #[test]
+    #[should_panic(expected = "Different sizes in Concat: [Grid(2, 3), Const(6)]")]
+    fn concat_grid_first_mismatch_test() {
+        let grid = Input::new(Shape::Const(2), Shape::Grid(2, 3));
+        let rows = Input::new(Shape::Const(2), Shape::Const(6));
+        Concat::new(|| vec![&grid, &rows]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Different sizes in Concat: [Const(6), Grid(2, 3)]")]
+    fn concat_grid_last_mismatch_test() {
+        let rows = Input::new(Shape::Const(2), Shape::Const(6));
+        let grid = Input::new(Shape::Const(2), Shape::Grid(2, 3));
+        Concat::new(|| vec![&rows, &grid]);
+    }
+
     #[test]
     fn flatten_full_const_layer_test() {
         let input = Input::new(Shape::Const(3), Shape::Const(2));
@@ -115,4 +156,868 @@ mod test {
         assert!(output_data.width == 9 && output_data.height == 1);
         dbg!(&output_data);
     }
+
+    /**
+     * Output step t of filter f is bias[f] + sum over taps k and features c of
+     * x[t * stride + k * dilation - pad][c] * weight[k * features + c][f]
+     */
+    fn conv1d_reference(
+        x: &NDMatrix,
+        weight: &NDMatrix,
+        bias: &NDMatrix,
+        (kernel, stride, dilation, pad, steps): (usize, usize, usize, usize, usize),
+    ) -> NDMatrix {
+        let mut out = NDMatrix::new(weight.width, steps);
+        for t in 0..steps {
+            for f in 0..weight.width {
+                let mut sum = bias.values[[0, f]];
+                for k in 0..kernel {
+                    let step = (t * stride + k * dilation) as isize - pad as isize;
+                    if step < 0 || step >= x.height as isize {
+                        continue;
+                    }
+                    for c in 0..x.width {
+                        sum += x.values[[step as usize, c]] * weight.values[[k * x.width + c, f]];
+                    }
+                }
+                out.values[[t, f]] = sum;
+            }
+        }
+        return out;
+    }
+
+    #[test]
+    fn conv1d_layer_test() {
+        // padding, stride, dilation, expected steps and zeros before the first step
+        let cases = [
+            (Padding::Valid, 1, 1, 8, 0),
+            (Padding::Valid, 2, 2, 3, 0),
+            (Padding::Same, 1, 1, 10, 1),
+            (Padding::Same, 3, 1, 4, 1),
+            (Padding::Causal, 1, 2, 10, 4),
+            (Padding::Causal, 2, 1, 5, 2),
+        ];
+        for (padding, stride, dilation, steps, pad) in cases {
+            let input = Input::new(Shape::Const(2), Shape::Const(10));
+            let conv = Conv1D::builder(4, 3, || &input)
+                .with_padding(padding)
+                .with_stride(stride)
+                .with_dilation(dilation)
+                .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+                .build();
+            assert_eq!(conv.get_shape(), (Shape::Const(4), Shape::Const(steps)));
+            let model = ModelBuilder::from_straight(input, conv).build();
+
+            let x = NDMatrix::from_supply(2, 10, RandomUniformSupplier::new(1.0, -1.0));
+            let output = model.propagate_single(x.clone());
+            let weights = WeightsSerialized::from_model(&model).to_matrices().unwrap();
+            let expected = conv1d_reference(
+                &x,
+                &weights["Conv1D_1.weight"],
+                &weights["Conv1D_1.bias"],
+                (3, stride, dilation, pad, steps),
+            );
+            assert_eq!((output.width, output.height), (4, steps));
+            output
+                .iter_all()
+                .zip(expected.iter_all())
+                .for_each(|(a, b)| assert!((a - b).abs() < 1e-5, "{:?}", padding));
+        }
+    }
+
+    #[test]
+    fn conv1d_variable_layer_test() {
+        let input = Input::new(Shape::Const(3), Shape::Repeat);
+        let same = Conv1D::builder(2, 3, || &input)
+            .with_padding(Padding::Same)
+            .build();
+        assert_eq!(same.get_shape().1, Shape::Repeat);
+        let valid = Conv1D::new(5, 2, || &same);
+        assert_eq!(valid.get_shape(), (Shape::Const(5), Shape::Variable));
+        let flatten = Flatten::new(|| &valid);
+        let model = ModelBuilder::from_straight(input, flatten).build();
+
+        let output = model.propagate_single(NDMatrix::constant(3, 7, 1.0));
+        assert!(output.width == 30 && output.height == 1);
+        // shorter than the kernel
+        let output = model.propagate_single(NDMatrix::constant(3, 1, 1.0));
+        assert_eq!(output.width, 0);
+    }
+
+    /**
+     * Cell (y, x) of filter f is bias[f] + sum over kernel cells (ky, kx) and channels c of
+     * x[y * stride + ky * dilation - pad y][x * stride + kx * dilation - pad x][c] times
+     * weight[(ky * kernel width + kx) * channels + c][f]
+     */
+    fn conv2d_reference(
+        x: &NDMatrix,
+        (height, width): (usize, usize),
+        weight: &NDMatrix,
+        bias: &NDMatrix,
+        (kernel, stride, dilation): (usize, usize, usize),
+        (pad, output): ((usize, usize), (usize, usize)),
+    ) -> NDMatrix {
+        let mut out = NDMatrix::new(weight.width, output.0 * output.1);
+        for y in 0..output.0 {
+            for x_out in 0..output.1 {
+                for f in 0..weight.width {
+                    let mut sum = bias.values[[0, f]];
+                    for ky in 0..kernel {
+                        for kx in 0..kernel {
+                            let iy = (y * stride + ky * dilation) as isize - pad.0 as isize;
+                            let ix = (x_out * stride + kx * dilation) as isize - pad.1 as isize;
+                            if iy < 0 || ix < 0 || iy >= height as isize || ix >= width as isize {
+                                continue;
+                            }
+                            let cell = iy as usize * width + ix as usize;
+                            for c in 0..x.width {
+                                sum += x.values[[cell, c]]
+                                    * weight.values[[(ky * kernel + kx) * x.width + c, f]];
+                            }
+                        }
+                    }
+                    out.values[[y * output.1 + x_out, f]] = sum;
+                }
+            }
+        }
+        return out;
+    }
+
+    #[test]
+    fn conv2d_layer_test() {
+        // padding, stride, dilation, zeros above and left of the grid, output grid
+        let cases = [
+            (Padding::Valid, 1, 1, (0, 0), (4, 5)),
+            (Padding::Valid, 2, 1, (0, 0), (2, 3)),
+            (Padding::Valid, 1, 2, (0, 0), (2, 3)),
+            (Padding::Same, 1, 1, (1, 1), (6, 7)),
+            (Padding::Same, 2, 1, (0, 1), (3, 4)),
+        ];
+        for (padding, stride, dilation, pad, output) in cases {
+            let input = Input::new(Shape::Const(2), Shape::Grid(6, 7));
+            let conv = Conv2D::builder(3, (3, 3), || &input)
+                .with_padding(padding)
+                .with_stride((stride, stride))
+                .with_dilation((dilation, dilation))
+                .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+                .build();
+            assert_eq!(
+                conv.get_shape(),
+                (Shape::Const(3), Shape::Grid(output.0, output.1))
+            );
+            let model = ModelBuilder::from_straight(input, conv).build();
+
+            let x = NDMatrix::from_supply(2, 42, RandomUniformSupplier::new(1.0, -1.0));
+            let result = model.propagate_single(x.clone());
+            let weights = WeightsSerialized::from_model(&model).to_matrices().unwrap();
+            let expected = conv2d_reference(
+                &x,
+                (6, 7),
+                &weights["Conv2D_1.weight"],
+                &weights["Conv2D_1.bias"],
+                (3, stride, dilation),
+                (pad, output),
+            );
+            assert_eq!((result.width, result.height), (3, output.0 * output.1));
+            result
+                .iter_all()
+                .zip(expected.iter_all())
+                .for_each(|(a, b)| assert!((a - b).abs() < 1e-5, "{:?}", padding));
+        }
+    }
+
+    #[test]
+    fn pool2d_layer_test() {
+        // 3x3 grid, channel 0 counts up and channel 1 counts down
+        let x =
+            NDMatrix::from_raw_vec(2, 9, (0..9).flat_map(|i| [i as f32, -(i as f32)]).collect());
+
+        let input = Input::new(Shape::Const(2), Shape::Grid(3, 3));
+        let max = MaxPool2D::new((2, 2), || &input);
+        assert_eq!(max.get_shape(), (Shape::Const(2), Shape::Grid(1, 1)));
+        let model = ModelBuilder::from_straight(input, max).build();
+        assert_eq!(
+            model.propagate_single(x.clone()).values.into_raw_vec(),
+            vec![4.0, 0.0]
+        );
+
+        // padded cells are left out of the average
+        let input = Input::new(Shape::Const(2), Shape::Grid(3, 3));
+        let avg = AvgPool2D::builder((2, 2), || &input)
+            .with_padding(Padding::Same)
+            .build();
+        assert_eq!(avg.get_shape(), (Shape::Const(2), Shape::Grid(2, 2)));
+        let model = ModelBuilder::from_straight(input, avg).build();
+        let output = model.propagate_single(x.clone());
+        let channel: Vec<f32> = output.values.column(0).to_vec();
+        assert_eq!(channel, vec![2.0, 3.5, 6.5, 8.0]);
+
+        let input = Input::new(Shape::Const(2), Shape::Grid(3, 3));
+        let max = MaxPool2D::builder((2, 2), || &input)
+            .with_stride((1, 1))
+            .build();
+        let model = ModelBuilder::from_straight(input, max).build();
+        let output = model.propagate_single(x);
+        assert_eq!(output.values.column(0).to_vec(), vec![4.0, 5.0, 7.0, 8.0]);
+        assert_eq!(
+            output.values.column(1).to_vec(),
+            vec![0.0, -1.0, -3.0, -4.0]
+        );
+    }
+
+    #[test]
+    fn conv2d_flatten_dense_test() {
+        let input = Input::new(Shape::Const(3), Shape::Grid(8, 8));
+        let conv = Conv2D::builder(4, (3, 3), || &input)
+            .with_padding(Padding::Same)
+            .build();
+        let pool = MaxPool2D::new((2, 2), || &conv);
+        let flatten = Flatten::new(|| &pool);
+        assert_eq!(flatten.get_shape().0, Shape::Const(4 * 4 * 4));
+        let dense = Dense::new(5, || &flatten);
+        let model = ModelBuilder::from_straight(input, dense).build();
+
+        let output = model.propagate_single(NDMatrix::constant(3, 64, 1.0));
+        assert!(output.width == 5 && output.height == 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected 9 rows for one 3x3 grid, got: 18, by: Conv2D_1")]
+    fn conv2d_one_image_test() {
+        // two images stacked in one matrix are not a batch
+        let input = Input::new(Shape::Const(2), Shape::Grid(3, 3));
+        let conv = Conv2D::new(1, (2, 2), || &input);
+        let model = ModelBuilder::from_straight(input, conv).build();
+        model.propagate_single(NDMatrix::constant(2, 18, 1.0));
+    }
+
+    fn row(matrix: &NDMatrix, index: usize) -> NDMatrix {
+        let values = matrix
+            .values
+            .row(index)
+            .to_owned()
+            .insert_axis(ndarray::Axis(0));
+        return NDMatrix::with(matrix.width, 1, values);
+    }
+
+    /**
+     * Columns index * units..(index + 1) * units of a single row
+     */
+    fn part(matrix: &NDMatrix, index: usize, units: usize) -> NDMatrix {
+        let values = matrix
+            .values
+            .slice(ndarray::s![.., index * units..(index + 1) * units])
+            .to_owned();
+        return NDMatrix::with(units, 1, values);
+    }
+
+    fn assert_close(a: &NDMatrix, b: &NDMatrix) {
+        assert_eq!((a.width, a.height), (b.width, b.height));
+        a.iter_all()
+            .zip(b.iter_all())
+            .for_each(|(x, y)| assert!((x - y).abs() < 1e-5, "{} != {}", x, y));
+    }
+
+    #[test]
+    fn lstm_layer_test() {
+        let input = Input::new(Shape::Const(3), Shape::Const(5));
+        let lstm = Lstm::builder(4, || &input)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .with_return_sequences(true)
+            .build();
+        assert_eq!(lstm.get_shape(), (Shape::Const(4), Shape::Const(5)));
+        let model = ModelBuilder::from_straight(input, lstm).build();
+
+        let x = NDMatrix::from_supply(3, 5, RandomUniformSupplier::new(1.0, -1.0));
+        let output = model.propagate_single(x.clone());
+        let weights = WeightsSerialized::from_model(&model).to_matrices().unwrap();
+        let (kernel, recurrent, bias) = (
+            &weights["LSTM_1.kernel"],
+            &weights["LSTM_1.recurrent_kernel"],
+            &weights["LSTM_1.bias"],
+        );
+        assert_eq!((kernel.width, kernel.height), (16, 3));
+        assert_eq!((recurrent.width, recurrent.height), (16, 4));
+
+        let mut hidden = NDMatrix::new(4, 1);
+        let mut cell = NDMatrix::new(4, 1);
+        for t in 0..5 {
+            let z = NDMatrix::add(
+                &NDMatrix::add(
+                    &NDMatrix::mat_mul(&row(&x, t), kernel),
+                    &NDMatrix::mat_mul(&hidden, recurrent),
+                ),
+                bias,
+            );
+            let i = Sigmoid.apply(&part(&z, 0, 4));
+            let f = Sigmoid.apply(&part(&z, 1, 4));
+            let g = Tanh.apply(&part(&z, 2, 4));
+            let o = Sigmoid.apply(&part(&z, 3, 4));
+            let values = &f.values * &cell.values + &i.values * &g.values;
+            cell = NDMatrix::with(4, 1, values);
+            hidden = NDMatrix::with(4, 1, &o.values * &Tanh.apply(&cell).values);
+            assert_close(&row(&output, t), &hidden);
+        }
+    }
+
+    #[test]
+    fn gru_layer_test() {
+        let input = Input::new(Shape::Const(3), Shape::Repeat);
+        let gru = Gru::builder(4, || &input)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        assert_eq!(gru.get_shape(), (Shape::Const(4), Shape::Const(1)));
+        let model = ModelBuilder::from_straight(input, gru).build();
+
+        let x = NDMatrix::from_supply(3, 6, RandomUniformSupplier::new(1.0, -1.0));
+        let output = model.propagate_single(x.clone());
+        let weights = WeightsSerialized::from_model(&model).to_matrices().unwrap();
+        let (kernel, recurrent, bias) = (
+            &weights["GRU_1.kernel"],
+            &weights["GRU_1.recurrent_kernel"],
+            &weights["GRU_1.bias"],
+        );
+        assert_eq!((bias.width, bias.height), (12, 2));
+
+        let mut hidden = NDMatrix::new(4, 1);
+        for t in 0..6 {
+            let from_input = NDMatrix::add(&NDMatrix::mat_mul(&row(&x, t), kernel), &row(bias, 0));
+            let from_hidden = NDMatrix::add(&NDMatrix::mat_mul(&hidden, recurrent), &row(bias, 1));
+            let z = Sigmoid.apply(&NDMatrix::add(
+                &part(&from_input, 0, 4),
+                &part(&from_hidden, 0, 4),
+            ));
+            let r = Sigmoid.apply(&NDMatrix::add(
+                &part(&from_input, 1, 4),
+                &part(&from_hidden, 1, 4),
+            ));
+            let candidate =
+                &part(&from_input, 2, 4).values + &(&r.values * &part(&from_hidden, 2, 4).values);
+            let candidate = Tanh.apply(&NDMatrix::with(4, 1, candidate));
+            let values = &z.values * &hidden.values + (1.0 - &z.values) * &candidate.values;
+            hidden = NDMatrix::with(4, 1, values);
+        }
+        assert_close(&output, &hidden);
+
+        // no steps, the initial state
+        let empty = model.propagate_single(NDMatrix::new(3, 0));
+        assert_eq!(empty.values, NDMatrix::new(4, 1).values);
+    }
+
+    #[test]
+    fn recurrent_stack_test() {
+        let input = Input::new(Shape::Const(2), Shape::Variable);
+        let lstm = Lstm::builder(6, || &input)
+            .with_return_sequences(true)
+            .build();
+        assert_eq!(lstm.get_shape().1, Shape::Variable);
+        let gru = Gru::new(3, || &lstm);
+        let dense = Dense::new(2, || &gru);
+        let model = ModelBuilder::from_straight(input, dense).build();
+
+        let output = model.propagate_single(NDMatrix::constant(2, 9, 0.5));
+        assert!(output.width == 2 && output.height == 1);
+    }
+
+    fn rows(matrix: &NDMatrix, indices: &[usize]) -> NDMatrix {
+        let values = matrix.values.select(ndarray::Axis(0), indices);
+        return NDMatrix::with(matrix.width, indices.len(), values);
+    }
+
+    #[test]
+    fn attention_layer_test() {
+        let input = Input::new(Shape::Const(3), Shape::Variable);
+        let attention = MultiHeadAttention::builder(2, 4, || &input)
+            .with_output_features(5)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        assert_eq!(attention.get_shape(), (Shape::Const(5), Shape::Variable));
+        let model = ModelBuilder::from_straight(input, attention).build();
+
+        let x = NDMatrix::from_supply(3, 6, RandomUniformSupplier::new(1.0, -1.0));
+        let output = model.propagate_single(x.clone());
+        let weights = WeightsSerialized::from_model(&model).to_matrices().unwrap();
+        let project = |name: &str| {
+            let weight = &weights[&format!("MultiHeadAttention_1.{}", name)];
+            let bias = &weights[&format!("MultiHeadAttention_1.{}_bias", name)];
+            return NDMatrix::add(&NDMatrix::mat_mul(&x, weight), bias);
+        };
+        let (q, k, v) = (project("query"), project("key"), project("value"));
+
+        let mut heads = NDMatrix::new(8, 6);
+        for head in 0..2 {
+            let columns = ndarray::s![.., head * 4..(head + 1) * 4];
+            let scores = q.values.slice(columns).dot(&k.values.slice(columns).t()) / 2.0;
+            let scores = SoftMax::default().apply(&NDMatrix::with(6, 6, scores));
+            let attended = scores.values.dot(&v.values.slice(columns));
+            heads.values.slice_mut(columns).assign(&attended);
+        }
+        let expected = NDMatrix::add(
+            &NDMatrix::mat_mul(&heads, &weights["MultiHeadAttention_1.output"]),
+            &weights["MultiHeadAttention_1.output_bias"],
+        );
+        assert_close(&output, &expected);
+
+        // a set of entities, the order of the rows only moves the outputs
+        let order = [3, 1, 5, 0, 2, 4];
+        let permuted = model.propagate_single(rows(&x, &order));
+        assert_close(&permuted, &rows(&output, &order));
+
+        // any size
+        let shorter = model.propagate_single(rows(&x, &[0, 1]));
+        assert!(shorter.width == 5 && shorter.height == 2);
+    }
+
+    #[test]
+    fn attention_mask_test() {
+        let input = Input::new(Shape::Const(3), Shape::Variable);
+        let causal = MultiHeadAttention::builder(2, 3, || &input)
+            .with_mask(AttentionMask::Causal)
+            .build();
+        let model = ModelBuilder::from_straight(input, causal).build();
+
+        // earlier rows never see later ones
+        let x = NDMatrix::from_supply(3, 5, RandomUniformSupplier::new(1.0, -1.0));
+        let output = model.propagate_single(x.clone());
+        let prefix = model.propagate_single(rows(&x, &[0, 1, 2]));
+        assert_close(&rows(&output, &[0, 1, 2]), &prefix);
+
+        let input = Input::new(Shape::Const(3), Shape::Variable);
+        let padded = MultiHeadAttention::builder(2, 3, || &input)
+            .with_mask(AttentionMask::Padding(0.0))
+            .build();
+        let model = ModelBuilder::from_straight(input, padded).build();
+
+        // empty entity slots change nothing for the others
+        let mut slots = NDMatrix::new(3, 5);
+        slots
+            .values
+            .slice_mut(ndarray::s![0..3, ..])
+            .assign(&x.values.slice(ndarray::s![0..3, ..]));
+        let output = model.propagate_single(slots);
+        let entities = model.propagate_single(rows(&x, &[0, 1, 2]));
+        assert_close(&rows(&output, &[0, 1, 2]), &entities);
+    }
+
+    #[test]
+    fn transformer_encoder_test() {
+        let input = Input::new(Shape::Const(4), Shape::Variable);
+        let encoder = TransformerEncoder::builder(2, 3, 8, || &input)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        assert_eq!(encoder.get_shape(), (Shape::Const(4), Shape::Variable));
+        let stacked = TransformerEncoder::new(2, 3, 8, || &encoder);
+        let dense = Dense::new(2, || &stacked);
+        let model = ModelBuilder::from_straight(input, dense).build();
+
+        let x = NDMatrix::from_supply(4, 7, RandomUniformSupplier::new(1.0, -1.0));
+        let output = model.propagate_single(x.clone());
+        assert!(output.width == 2 && output.height == 7);
+
+        // the block ends in a layer norm with gamma 1 and beta 0
+        let input = Input::new(Shape::Const(4), Shape::Variable);
+        let encoder = TransformerEncoder::new(1, 4, 6, || &input);
+        let single = ModelBuilder::from_straight(input, encoder).build();
+        let normalized = single.propagate_single(x.clone());
+        for row in normalized.values.rows() {
+            let mean = row.mean().unwrap();
+            let variance = row.mapv(|v| (v - mean).powi(2)).mean().unwrap();
+            assert!(mean.abs() < 1e-4 && (variance - 1.0).abs() < 1e-3);
+        }
+
+        let order = [6, 5, 4, 3, 2, 1, 0];
+        let permuted = model.propagate_single(rows(&x, &order));
+        assert_close(&permuted, &rows(&output, &order));
+    }
+
+    fn assert_normalized(values: ndarray::ArrayView1<f32>) {
+        let mean = values.mean().unwrap();
+        let variance = values.mapv(|v| (v - mean).powi(2)).mean().unwrap();
+        assert!(mean.abs() < 1e-4, "mean {}", mean);
+        assert!((variance - 1.0).abs() < 1e-2, "variance {}", variance);
+    }
+
+    #[test]
+    fn layer_norm_layer_test() {
+        let input = Input::new(Shape::Const(5), Shape::Variable);
+        let norm = LayerNorm::builder(|| &input).with_epsilon(1e-6).build();
+        let mut model = ModelBuilder::from_straight(input, norm).build();
+
+        let x = NDMatrix::from_supply(5, 4, RandomUniformSupplier::new(3.0, -1.0));
+        let output = model.propagate_single(x.clone());
+        output.values.rows().into_iter().for_each(assert_normalized);
+
+        // gamma and beta are parameters like any other
+        let mut weights = ModelWeights::collect(&model);
+        assert_eq!(
+            weights.keys().collect::<Vec<_>>(),
+            vec!["LayerNorm_1.gamma", "LayerNorm_1.beta"]
+        );
+        weights["LayerNorm_1.gamma"] = NDMatrix::constant(5, 1, 2.0);
+        weights["LayerNorm_1.beta"] = NDMatrix::constant(5, 1, 1.0);
+        ModelWeights::apply(&mut model, &weights, &ModelReader::default()).unwrap();
+        let scaled = model.propagate_single(x);
+        assert_close(&scaled, &NDMatrix::with(5, 4, output.values * 2.0 + 1.0));
+    }
+
+    #[test]
+    fn batch_norm_layer_test() {
+        let input = Input::new(Shape::Const(3), Shape::Repeat);
+        let norm = BatchNorm::builder(|| &input)
+            .with_epsilon(1e-6)
+            .with_momentum(0.5)
+            .build();
+        let mut model = ModelBuilder::from_straight(input, norm).build();
+        model.set_mode(Mode::Training);
+
+        let x = NDMatrix::from_supply(3, 8, RandomUniformSupplier::new(3.0, -1.0));
+        let output = model.propagate_single(x.clone());
+        output
+            .values
+            .columns()
+            .into_iter()
+            .for_each(assert_normalized);
+
+        // the running statistics moved halfway to the batch ones and serialize with the model
+        let weights = ModelWeights::collect(&model);
+        let mean = x.values.mean_axis(ndarray::Axis(0)).unwrap();
+        let variance = x.values.var_axis(ndarray::Axis(0), 0.0);
+        let expected_mean = NDMatrix::with(3, 1, (mean * 0.5).insert_axis(ndarray::Axis(0)));
+        let expected_variance =
+            NDMatrix::with(3, 1, (variance * 0.5 + 0.5).insert_axis(ndarray::Axis(0)));
+        assert_close(&weights["BatchNorm_1.running_mean"], &expected_mean);
+        assert_close(&weights["BatchNorm_1.running_variance"], &expected_variance);
+
+        // inference normalizes with the running statistics, each row on its own
+        let input = Input::new(Shape::Const(3), Shape::Repeat);
+        let norm = BatchNorm::builder(|| &input).with_epsilon(1e-6).build();
+        let mut inference = ModelBuilder::from_straight(input, norm).build();
+        let mut loaded = ModelWeights::collect(&inference);
+        loaded["BatchNorm_1.running_mean"] = weights["BatchNorm_1.running_mean"].clone();
+        loaded["BatchNorm_1.running_variance"] = weights["BatchNorm_1.running_variance"].clone();
+        ModelWeights::apply(&mut inference, &loaded, &ModelReader::default()).unwrap();
+
+        let full = inference.propagate_single(x.clone());
+        let single = inference.propagate_single(row(&x, 2));
+        assert_close(&row(&full, 2), &single);
+        let expected = (&x.values - &expected_mean.values)
+            / expected_variance.values.mapv(|v| (v + 1e-6).sqrt());
+        assert_close(&full, &NDMatrix::with(3, 8, expected));
+        assert_close(
+            &ModelWeights::collect(&inference)["BatchNorm_1.running_mean"],
+            &expected_mean,
+        );
+    }
+
+    #[test]
+    fn noise_layer_test() {
+        let build = |seed: u64| {
+            let input = Input::new(Shape::Const(50), Shape::Repeat);
+            let dropout = Dropout::builder(0.25, || &input).with_seed(seed).build();
+            let noise = GaussianNoise::builder(0.0, || &dropout)
+                .with_seed(seed)
+                .build();
+            return ModelBuilder::from_straight(input, noise).build();
+        };
+        let mut model = build(7);
+        assert_eq!(model.mode(), Mode::Inference);
+
+        let x = NDMatrix::constant(50, 40, 2.0);
+        assert_eq!(model.propagate_single(x.clone()).values, x.values);
+
+        model.set_mode(Mode::Training);
+        let dropped = model.propagate_single(x.clone());
+        let zeros = dropped.iter_all().filter(|v| **v == 0.0).count();
+        assert!((400..600).contains(&zeros), "{} dropped of 2000", zeros);
+        assert!(dropped
+            .iter_all()
+            .all(|v| *v == 0.0 || (*v - 2.0 / 0.75).abs() < 1e-6));
+
+        // the same seed gives the same noise, again on every switch to training
+        let mut other = build(7);
+        other.set_mode(Mode::Training);
+        assert_eq!(other.propagate_single(x.clone()).values, dropped.values);
+        assert_ne!(model.propagate_single(x.clone()).values, dropped.values);
+        model.set_mode(Mode::Training);
+        assert_eq!(model.propagate_single(x.clone()).values, dropped.values);
+
+        model.set_mode(Mode::Inference);
+        assert_eq!(model.propagate_single(x.clone()).values, x.values);
+    }
+
+    #[test]
+    fn gaussian_alpha_noise_layer_test() {
+        let input = Input::new(Shape::Const(100), Shape::Repeat);
+        let noise = GaussianNoise::builder(0.5, || &input).with_seed(3).build();
+        let mut model = ModelBuilder::from_straight(input, noise).build();
+        model.set_mode(Mode::Training);
+        let noisy = model.propagate_single(NDMatrix::constant(100, 100, 1.0));
+        let mean = noisy.values.mean().unwrap();
+        let std_dev = noisy.values.std(0.0);
+        assert!((mean - 1.0).abs() < 0.05 && (std_dev - 0.5).abs() < 0.05);
+
+        // keeps zero mean and unit variance
+        let input = Input::new(Shape::Const(100), Shape::Repeat);
+        let alpha = AlphaDropout::builder(0.2, || &input).with_seed(3).build();
+        let mut model = ModelBuilder::from_straight(input, alpha).build();
+        model.set_mode(Mode::Training);
+        let x = NDMatrix::from_supply(100, 100, RandomNormalSupplier::new(0.0, 1.0));
+        let dropped = model.propagate_single(x);
+        let mean = dropped.values.mean().unwrap();
+        let std_dev = dropped.values.std(0.0);
+        assert!(
+            mean.abs() < 0.1 && (std_dev - 1.0).abs() < 0.1,
+            "{} {}",
+            mean,
+            std_dev
+        );
+    }
+
+    #[test]
+    fn mode_survives_weight_load_test() {
+        let input = Input::new(Shape::Const(3), Shape::Repeat);
+        let norm = BatchNorm::new(|| &input);
+        let mut model = ModelBuilder::from_straight(input, norm).build();
+        model.set_mode(Mode::Training);
+
+        let weights = ModelWeights::collect(&model);
+        ModelWeights::apply(&mut model, &weights, &ModelReader::default()).unwrap();
+        let x = NDMatrix::from_supply(3, 6, RandomUniformSupplier::new(3.0, -1.0));
+        let output = model.propagate_single(x);
+        output
+            .values
+            .columns()
+            .into_iter()
+            .for_each(assert_normalized);
+    }
+
+    #[test]
+    fn merge_layer_test() {
+        let input_1 = Input::new(Shape::Const(3), Shape::Repeat);
+        let input_2 = Input::new(Shape::Const(3), Shape::Repeat);
+        let input_3 = Input::new(Shape::Const(3), Shape::Repeat);
+        let merges = [
+            Add::new(|| vec![&input_1, &input_2, &input_3]),
+            Subtract::new(|| vec![&input_1, &input_2]),
+            Multiply::new(|| vec![&input_1, &input_2, &input_3]),
+            Average::new(|| vec![&input_1, &input_2, &input_3]),
+            Maximum::new(|| vec![&input_1, &input_2, &input_3]),
+        ];
+        let outputs: IndexMap<LayerRef, String> = merges
+            .iter()
+            .map(|merge| (merge.clone(), merge.type_name().to_owned()))
+            .collect();
+        let inputs: IndexMap<LayerRef, String> = map! {
+            input_1 => "1".to_owned(),
+            input_2 => "2".to_owned(),
+            input_3 => "3".to_owned(),
+        };
+        let model = ModelBuilder::from(inputs, outputs).build();
+
+        let data = |values: [f32; 3]| NDMatrix::from_raw_vec(3, 2, [values, values].concat());
+        let output = model.propagate(&map! {
+            "1".to_owned() => data([1.0, -2.0, 3.0]),
+            "2".to_owned() => data([4.0, 5.0, -6.0]),
+            "3".to_owned() => data([-1.0, 0.5, 2.0]),
+        });
+        let expected = [
+            ("Add", [4.0, 3.5, -1.0]),
+            ("Subtract", [-3.0, -7.0, 9.0]),
+            ("Multiply", [-4.0, -5.0, -36.0]),
+            ("Average", [4.0 / 3.0, 3.5 / 3.0, -1.0 / 3.0]),
+            ("Maximum", [4.0, 5.0, 3.0]),
+        ];
+        for (name, values) in expected {
+            assert_close(&output[name], &data(values));
+        }
+    }
+
+    #[test]
+    fn merge_residual_test() {
+        let input = Input::new(Shape::Const(4), Shape::Variable);
+        let dense = Dense::new(4, || &input);
+        let residual = Add::new(|| vec![&input, &dense]);
+        assert_eq!(residual.get_shape(), (Shape::Const(4), Shape::Variable));
+        let model = ModelBuilder::from_straight(input, residual).build();
+        let output = model.propagate_single(NDMatrix::constant(4, 5, 1.0));
+        assert!(output.width == 4 && output.height == 5);
+
+        let input_1 = Input::new(Shape::Const(4), Shape::Const(2));
+        let input_2 = Input::new(Shape::Const(4), Shape::Variable);
+        let mixed = Multiply::new(|| vec![&input_1, &input_2]);
+        assert_eq!(mixed.get_shape().1, Shape::Variable);
+    }
+
+    #[test]
+    #[should_panic(expected = "Different features in Add")]
+    fn merge_features_mismatch_test() {
+        let input_1 = Input::new(Shape::Const(3), Shape::Repeat);
+        let input_2 = Input::new(Shape::Const(4), Shape::Repeat);
+        Add::new(|| vec![&input_1, &input_2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Different sizes in Maximum")]
+    fn merge_size_mismatch_test() {
+        let input_1 = Input::new(Shape::Const(3), Shape::Const(2));
+        let input_2 = Input::new(Shape::Const(3), Shape::Const(5));
+        Maximum::new(|| vec![&input_1, &input_2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Subtract takes exactly 2 parents")]
+    fn merge_subtract_parents_test() {
+        let input_1 = Input::new(Shape::Const(3), Shape::Repeat);
+        let input_2 = Input::new(Shape::Const(3), Shape::Repeat);
+        let input_3 = Input::new(Shape::Const(3), Shape::Repeat);
+        Subtract::new(|| vec![&input_1, &input_2, &input_3]);
+    }
+
+    #[test]
+    fn embedding_layer_test() {
+        let ids = Input::new(Shape::Const(2), Shape::Variable);
+        let embedding = Embedding::builder(5, 3, || &ids)
+            .with_embeddings_init(RandomNormalSupplier::new(0.0, 1.0))
+            .build();
+        assert_eq!(embedding.get_shape(), (Shape::Const(6), Shape::Variable));
+        let model = ModelBuilder::from_straight(ids, embedding).build();
+
+        let table = &ModelWeights::collect(&model)["Embedding_1.embeddings"];
+        assert_eq!((table.width, table.height), (3, 5));
+
+        let input = NDMatrix::from_raw_vec(2, 3, vec![0.0, 4.0, 2.0, 2.0, 4.0, 1.0]);
+        let output = model.propagate_single(input.clone());
+        assert!(output.width == 6 && output.height == 3);
+        for (r, c) in (0..3).flat_map(|r| (0..2).map(move |c| (r, c))) {
+            let id = input.values[[r, c]] as usize;
+            let vector = output.values.slice(ndarray::s![r, c * 3..(c + 1) * 3]);
+            assert_eq!(vector, table.values.row(id));
+        }
+    }
+
+    #[test]
+    fn embedding_with_features_test() {
+        let tiles = Input::new(Shape::Const(1), Shape::Repeat);
+        let position = Input::new(Shape::Const(2), Shape::Repeat);
+        let embedding = Embedding::new(10, 4, || &tiles);
+        let concat = Concat::new(|| vec![&embedding, &position]);
+        let dense = Dense::new(3, || &concat);
+
+        let inputs: IndexMap<LayerRef, String> = map! {
+            tiles => "tiles".to_owned(),
+            position => "position".to_owned(),
+        };
+        let model = ModelBuilder::from_single_o(inputs, dense).build();
+        let output = model.propagate_single_output(map! {
+            "tiles".to_owned() => NDMatrix::from_raw_vec(1, 4, vec![9.0, 0.0, 3.0, 3.0]),
+            "position".to_owned() => NDMatrix::constant(2, 4, 0.5),
+        });
+        assert!(output.width == 3 && output.height == 4);
+        assert_eq!(output.values.row(2), output.values.row(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Id 5 outside of the vocab of 5")]
+    fn embedding_out_of_vocab_test() {
+        let ids = Input::new(Shape::Const(1), Shape::Repeat);
+        let embedding = Embedding::new(5, 2, || &ids);
+        let model = ModelBuilder::from_straight(ids, embedding).build();
+        model.propagate_single(NDMatrix::constant(1, 2, 5.0));
+    }
+
+    #[test]
+    fn reshape_layer_test() {
+        let input = Input::new(Shape::Const(6), Shape::Const(2));
+        let reshape = Reshape::new(3, Shape::Const(4), || &input);
+        assert_eq!(reshape.get_shape(), (Shape::Const(3), Shape::Const(4)));
+        let model = ModelBuilder::from_straight(input, reshape).build();
+
+        let values: Vec<f32> = (0..12).map(|v| v as f32).collect();
+        let output = model.propagate_single(NDMatrix::from_raw_vec(6, 2, values.clone()));
+        assert_close(&output, &NDMatrix::from_raw_vec(3, 4, values));
+    }
+
+    #[test]
+    fn reshape_variable_test() {
+        let input = Input::new(Shape::Const(4), Shape::Variable);
+        let reshape = Reshape::new(2, Shape::Variable, || &input);
+        let model = ModelBuilder::from_straight(input, reshape).build();
+
+        let output = model.propagate_single(NDMatrix::constant(4, 3, 1.0));
+        assert!(output.width == 2 && output.height == 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Reshape of 12 values to 5 features")]
+    fn reshape_mismatch_test() {
+        let input = Input::new(Shape::Const(6), Shape::Const(2));
+        Reshape::new(5, Shape::Const(2), || &input);
+    }
+
+    #[test]
+    fn transpose_layer_test() {
+        let input = Input::new(Shape::Const(3), Shape::Const(2));
+        let transpose = Transpose::new(|| &input);
+        assert_eq!(transpose.get_shape(), (Shape::Const(2), Shape::Const(3)));
+        let model = ModelBuilder::from_straight(input, transpose).build();
+
+        let input_data = NDMatrix::from_raw_vec(3, 2, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let output = model.propagate_single(input_data);
+        let expected = NDMatrix::from_raw_vec(2, 3, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+        assert_close(&output, &expected);
+    }
+
+    #[test]
+    fn slice_layer_test() {
+        let input = Input::new(Shape::Const(5), Shape::Variable);
+        let slice = Slice::new(1..4, || &input);
+        assert_eq!(slice.get_shape(), (Shape::Const(3), Shape::Variable));
+        let model = ModelBuilder::from_straight(input, slice).build();
+
+        let values: Vec<f32> = (0..10).map(|v| v as f32).collect();
+        let output = model.propagate_single(NDMatrix::from_raw_vec(5, 2, values));
+        let expected = NDMatrix::from_raw_vec(3, 2, vec![1.0, 2.0, 3.0, 6.0, 7.0, 8.0]);
+        assert_close(&output, &expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Slice of 2..6 out of 5 features")]
+    fn slice_out_of_features_test() {
+        let input = Input::new(Shape::Const(5), Shape::Repeat);
+        Slice::new(2..6, || &input);
+    }
+
+    #[test]
+    fn split_layer_test() {
+        let observation = Input::new(Shape::Const(6), Shape::Repeat);
+        let branches = Split::new(vec![("position", 2), ("tiles", 4)], || &observation);
+        assert_eq!(branches.keys().collect::<Vec<_>>(), ["position", "tiles"]);
+        let position = Dense::new(3, || &branches["position"]);
+        let tiles = Dense::new(5, || &branches["tiles"]);
+        let concat = Concat::new(|| vec![&position, &tiles]);
+
+        let inputs: IndexMap<LayerRef, String> = map! {
+            observation => "observation".to_owned(),
+        };
+        let outputs: IndexMap<LayerRef, String> = map! {
+            concat => "concat".to_owned(),
+            branches["tiles"].clone() => "tiles".to_owned(),
+        };
+        let model = ModelBuilder::from(inputs, outputs).build();
+
+        let values: Vec<f32> = (0..12).map(|v| v as f32).collect();
+        let output = model.propagate(&map! {
+            "observation".to_owned() => NDMatrix::from_raw_vec(6, 2, values),
+        });
+        assert!(output["concat"].width == 8 && output["concat"].height == 2);
+        let expected = NDMatrix::from_raw_vec(4, 2, vec![2.0, 3.0, 4.0, 5.0, 8.0, 9.0, 10.0, 11.0]);
+        assert_close(&output["tiles"], &expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Split of 6 features into sections of 5 features")]
+    fn split_widths_mismatch_test() {
+        let observation = Input::new(Shape::Const(6), Shape::Repeat);
+        Split::new(vec![("position", 2), ("tiles", 3)], || &observation);
+    }
 }
@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    activation::{
+        abs::{Activation, ActivationSerialised},
+        relu::ReLu,
+    },
+    matrix::{
+        meta::{node::LayerType, shape::Shape},
+        nmatrix::NDMatrix,
+    },
+    serial::{model_reader::ModelReader, registry::LayerRegistration},
+    suppliers::suppliers::{GlorothUniformSupplier, Supplier, Suppliers, ZeroSupplier},
+    utils::json_wrap::JsonWrap,
+};
+
+use super::{
+    abs::{Layer, LayerBase, LayerPropagateEnum, LayerRef, LayerSingleInput},
+    attention::{Attention, AttentionMask},
+    layer_norm::normalize_rows,
+};
+
+/**
+ * Encoder block of a transformer (post-norm): self-attention and a feed-forward of two dense
+ * layers, each added back to its input and layer normalized. Keeps the parent features and
+ * size, which may be Shape::Variable.
+ */
+pub struct TransformerEncoder {
+    heads: usize,
+    key_dim: usize,
+    feed_forward: usize,
+    mask: AttentionMask,
+    epsilon: f32,
+    parent: LayerRef,
+    activation: Box<dyn Activation>,
+    weight_init: Suppliers,
+    bias_init: Suppliers,
+}
+
+impl TransformerEncoder {
+    pub const NAME: &str = "TransformerEncoder";
+
+    pub fn new<'a, F>(heads: usize, key_dim: usize, feed_forward: usize, uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let encoder = Self::builder(heads, key_dim, feed_forward, uplink);
+        return LayerRef::pin(encoder);
+    }
+
+    pub fn builder<'a, F>(
+        heads: usize,
+        key_dim: usize,
+        feed_forward: usize,
+        uplink: F,
+    ) -> TransformerEncoder
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        return TransformerEncoder {
+            heads,
+            key_dim,
+            feed_forward,
+            mask: AttentionMask::None,
+            epsilon: 1e-5,
+            parent: uplink().clone(),
+            activation: Box::new(ReLu::default()),
+            weight_init: GlorothUniformSupplier::new().into_enum(),
+            bias_init: ZeroSupplier::new().into_enum(),
+        };
+    }
+
+    pub fn with_mask(mut self, mask: AttentionMask) -> TransformerEncoder {
+        self.mask = mask;
+        return self;
+    }
+
+    /**
+     * Added to the variance of the layer norms
+     */
+    pub fn with_epsilon(mut self, epsilon: f32) -> TransformerEncoder {
+        self.epsilon = epsilon;
+        return self;
+    }
+
+    /**
+     * Of the hidden feed-forward layer, relu by default
+     */
+    pub fn with_activation(mut self, activation: impl Activation) -> TransformerEncoder {
+        self.activation = Box::new(activation);
+        return self;
+    }
+
+    pub fn with_weight_init(mut self, supplier: impl Supplier) -> TransformerEncoder {
+        self.weight_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn with_bias_init(mut self, supplier: impl Supplier) -> TransformerEncoder {
+        self.bias_init = supplier.into_enum();
+        return self;
+    }
+
+    pub fn build(self) -> LayerRef {
+        return LayerRef::pin(self);
+    }
+}
+
+impl Layer for TransformerEncoder {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        return self.parent.get_shape();
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let features = self.parent.get_shape().0.unwrap_to_conts();
+        if self.feed_forward == 0 {
+            panic!("Feed-forward size must be positive, by: {}", id);
+        }
+        let attention = Attention::create(
+            &id,
+            &self.parent,
+            self.heads,
+            self.key_dim,
+            features,
+            self.mask,
+            &self.weight_init,
+            &self.bias_init,
+        );
+        let instance = TransformerEncoderImpl {
+            attention,
+            epsilon: self.epsilon,
+            norm_1_gamma: NDMatrix::constant(features, 1, 1.0),
+            norm_1_beta: NDMatrix::new(features, 1),
+            feed_forward: self.weight_init.supply_matrix(self.feed_forward, features),
+            feed_forward_bias: self.bias_init.supply_matrix(self.feed_forward, 1),
+            feed_forward_output: self.weight_init.supply_matrix(features, self.feed_forward),
+            feed_forward_output_bias: self.bias_init.supply_matrix(features, 1),
+            norm_2_gamma: NDMatrix::constant(features, 1, 1.0),
+            norm_2_beta: NDMatrix::new(features, 1),
+            activation: self.activation.act_clone(),
+            id,
+        };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+pub struct TransformerEncoderImpl {
+    id: String,
+    attention: Attention,
+    epsilon: f32,
+    norm_1_gamma: NDMatrix,
+    norm_1_beta: NDMatrix,
+    /**
+     * features by feed-forward size
+     */
+    feed_forward: NDMatrix,
+    feed_forward_bias: NDMatrix,
+    feed_forward_output: NDMatrix,
+    feed_forward_output_bias: NDMatrix,
+    norm_2_gamma: NDMatrix,
+    norm_2_beta: NDMatrix,
+    activation: Box<dyn Activation>,
+}
+
+impl LayerBase for TransformerEncoderImpl {
+    fn init(&mut self) {}
+
+    fn create_from_ser(json: &JsonWrap, model_reader: &ModelReader) -> LayerPropagateEnum {
+        let deserialized: TransformerEncoderSerialization = json.to().unwrap();
+        let activation_ser = &deserialized.activation;
+        let impl_ref = TransformerEncoderImpl {
+            id: deserialized.id,
+            attention: deserialized.attention,
+            epsilon: deserialized.epsilon,
+            norm_1_gamma: deserialized.norm_1_gamma,
+            norm_1_beta: deserialized.norm_1_beta,
+            feed_forward: deserialized.feed_forward,
+            feed_forward_bias: deserialized.feed_forward_bias,
+            feed_forward_output: deserialized.feed_forward_output,
+            feed_forward_output_bias: deserialized.feed_forward_output_bias,
+            norm_2_gamma: deserialized.norm_2_gamma,
+            norm_2_beta: deserialized.norm_2_beta,
+            activation: model_reader.get_activation_di().create(
+                &activation_ser.name,
+                &activation_ser.json,
+                model_reader,
+            ),
+        };
+        return LayerPropagateEnum::SingleInput(Box::new(impl_ref));
+    }
+
+    fn to_json(&self) -> JsonWrap {
+        let serial = TransformerEncoderSerialization {
+            id: self.id.clone(),
+            attention: self.attention.clone(),
+            epsilon: self.epsilon,
+            norm_1_gamma: self.norm_1_gamma.clone(),
+            norm_1_beta: self.norm_1_beta.clone(),
+            feed_forward: self.feed_forward.clone(),
+            feed_forward_bias: self.feed_forward_bias.clone(),
+            feed_forward_output: self.feed_forward_output.clone(),
+            feed_forward_output_bias: self.feed_forward_output_bias.clone(),
+            norm_2_gamma: self.norm_2_gamma.clone(),
+            norm_2_beta: self.norm_2_beta.clone(),
+            activation: self.activation.as_serialized(),
+        };
+        return JsonWrap::from(serial).unwrap();
+    }
+}
+
+impl LayerSingleInput for TransformerEncoderImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        let attended = self.attention.attend(input);
+        let residual = &input.values + &attended.values;
+        let normalized = normalize_rows(
+            &residual,
+            &self.norm_1_gamma,
+            &self.norm_1_beta,
+            self.epsilon,
+        );
+        let normalized = NDMatrix::with(input.width, input.height, normalized);
+
+        let hidden = NDMatrix::mat_mul(&normalized, &self.feed_forward);
+        let hidden = self
+            .activation
+            .apply(&NDMatrix::add(&hidden, &self.feed_forward_bias));
+        let output = NDMatrix::mat_mul(&hidden, &self.feed_forward_output);
+        let output = NDMatrix::add(&output, &self.feed_forward_output_bias);
+
+        let residual = &normalized.values + &output.values;
+        let values = normalize_rows(
+            &residual,
+            &self.norm_2_gamma,
+            &self.norm_2_beta,
+            self.epsilon,
+        );
+        return NDMatrix::with(input.width, input.height, values);
+    }
+}
+
+inventory::submit! {
+    LayerRegistration {
+        name: TransformerEncoder::NAME,
+        create: TransformerEncoderImpl::create_from_ser,
+    }
+}
+
+/**
+ * Serialization
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+struct TransformerEncoderSerialization {
+    id: String,
+    #[serde(flatten)]
+    attention: Attention,
+    epsilon: f32,
+    norm_1_gamma: NDMatrix,
+    norm_1_beta: NDMatrix,
+    feed_forward: NDMatrix,
+    feed_forward_bias: NDMatrix,
+    feed_forward_output: NDMatrix,
+    feed_forward_output_bias: NDMatrix,
+    norm_2_gamma: NDMatrix,
+    norm_2_beta: NDMatrix,
+    activation: ActivationSerialised,
+}
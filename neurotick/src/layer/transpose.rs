@@ -0,0 +1,64 @@
+use crate::matrix::{
+    meta::{node::LayerType, shape::Shape},
+    nmatrix::NDMatrix,
+};
+
+use super::abs::{Layer, LayerImpl, LayerPropagateEnum, LayerRef, LayerSingleInput};
+
+/**
+ * Swaps rows and features, ex.: time steps of features to a row per feature over time.
+ * The parent needs a constant or grid size to become the features.
+ */
+pub struct Transpose {
+    parent: LayerRef,
+}
+
+impl Transpose {
+    pub const NAME: &str = "Transpose";
+
+    pub fn new<'a, F>(uplink: F) -> LayerRef
+    where
+        F: Fn() -> &'a LayerRef,
+    {
+        let parent = uplink().clone();
+        let size = parent.get_shape().1;
+        if !matches!(size, Shape::Const(_) | Shape::Grid(..)) {
+            panic!("Transpose of a {:?} size, rows become features", size);
+        }
+        let transpose = Transpose { parent };
+        return LayerRef::pin(transpose);
+    }
+}
+
+impl Layer for Transpose {
+    fn type_name(&self) -> &'static str {
+        return Self::NAME;
+    }
+
+    fn get_shape(&self) -> (Shape, Shape) {
+        let (features, size) = self.parent.get_shape();
+        return (Shape::Const(size.unwrap_to_conts()), features);
+    }
+
+    fn get_node(&self) -> LayerType {
+        return LayerType::SingleParent(self.parent.clone());
+    }
+
+    fn create_instance(&self, id: String) -> LayerPropagateEnum {
+        let instance = TransposeImpl { id };
+        LayerPropagateEnum::SingleInput(Box::new(instance))
+    }
+}
+
+#[derive(LayerImpl)]
+#[layer(name = Transpose::NAME, input = "single")]
+pub struct TransposeImpl {
+    id: String,
+}
+
+impl LayerSingleInput for TransposeImpl {
+    fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+        let values = input.values.t().as_standard_layout().to_owned();
+        return NDMatrix::with(input.height, input.width, values);
+    }
+}
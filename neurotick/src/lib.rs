@@ -1,3 +1,8 @@
+/**
+ * Lets the LayerImpl derive, which emits ::neurotick paths, be used by the built-in layers
+ */
+extern crate self as neurotick;
+
 pub mod activation;
 pub mod builder;
 pub mod layer;
@@ -6,3 +11,12 @@ pub mod model;
 pub mod serial;
 pub mod suppliers;
 pub mod utils;
+
+/**
+ * Dependencies referenced by the code generated in neurotick_derive
+ */
+#[doc(hidden)]
+pub mod __private {
+    pub use inventory;
+    pub use serde;
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Shape {
     /** Specified size, ex.: of features or timeseries size */
     Const(usize),
@@ -8,15 +8,21 @@ pub enum Shape {
     Repeat,
     /** Up to the layer impl to handle, may be used in tokenising, variable timeseries, etc. */
     Variable,
+    /**
+     * Height x width size of a 2d grid, ex.: image pixels. Each row of the matrix is one cell,
+     * top to bottom then left to right, with the channels as features
+     */
+    Grid(usize, usize),
 }
 
 impl Shape {
     /**
-     * Will panic if the shape in not constant
+     * Will panic if the shape in not constant, a grid counts its cells
      */
     pub fn unwrap_to_conts(&self) -> usize {
         return match self {
             Shape::Const(c) => *c,
+            Shape::Grid(height, width) => height * width,
             Shape::Repeat => panic!("No feature count"),
             Shape::Variable => panic!("No feature count"),
         };
@@ -1,12 +1,18 @@
 use std::fmt::Debug;
+use std::io::Error;
 use std::ops::{Add, BitAnd, Mul};
+use std::path::Path;
 
 use base64::Engine;
 use ndarray::iter::{AxisIter, Iter};
 use ndarray::{Array2, ArrayView, Axis, Ix1, Ix2};
 use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::serial::matrix_serial::{MatrixPack, MatrixSerial};
+use crate::serial::binary_serial::{BinaryModel, MatrixRef};
+use crate::serial::matrix_serial::{
+    MatrixEncoding, MatrixPack, MatrixPackError, MatrixSerial, PackData,
+};
+use crate::serial::npy_serial::Npy;
 use crate::suppliers::suppliers::Supplier;
 use crate::utils::extensions::Distinct;
 
@@ -168,6 +174,19 @@ impl NDMatrix {
     }
 }
 
+impl NDMatrix {
+    /**
+     * Little-endian f32 .npy of shape (height, width)
+     */
+    pub fn write_npy<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        return std::fs::write(path, Npy::encode(self));
+    }
+
+    pub fn read_npy<P: AsRef<Path>>(path: P) -> Result<NDMatrix, Error> {
+        return Npy::decode(&std::fs::read(path)?);
+    }
+}
+
 impl NDMatrix {
     pub fn concat_horizontal(array: &[&NDMatrix]) -> NDMatrix {
         let width = array.iter().map(|m| m.width).sum();
@@ -230,35 +249,74 @@ impl Debug for NDMatrix {
 }
 
 impl MatrixSerial<NDMatrix> for NDMatrix {
-    fn pack(&self) -> MatrixPack {
-        let bytes: Vec<u8> = self
-            .values
-            .clone()
-            .iter()
-            .map(|f| f.to_be_bytes())
-            .flatten()
-            .collect();
+    fn pack_as(&self, encoding: MatrixEncoding) -> MatrixPack {
+        if encoding == MatrixEncoding::Text {
+            let decimals = MatrixEncoding::text_decimals();
+            let rows = self
+                .values
+                .rows()
+                .into_iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|v| MatrixEncoding::text_value(*v, decimals))
+                        .collect()
+                })
+                .collect();
+            return MatrixPack {
+                width: self.width,
+                height: self.height,
+                data: PackData::Rows(rows),
+                checksum: None,
+                encoding,
+            };
+        }
+        let values: Vec<f32> = self.iter_all().copied().collect();
+        let bytes = encoding.encode(&values);
+        let checksum = crc32fast::hash(&bytes);
         let encoded = base64::engine::general_purpose::STANDARD_NO_PAD.encode(bytes);
         MatrixPack {
             width: self.width,
             height: self.height,
-            data: encoded,
+            data: PackData::Encoded(encoded),
+            checksum: Some(checksum),
+            encoding,
         }
     }
 
     /**
-     * This should panic if the byte packing is wrong
+     * Rejects data that doesn't decode to width x height values or fails the checksum
      */
-    fn unpack(pack: &MatrixPack) -> NDMatrix {
+    fn unpack(pack: &MatrixPack) -> Result<NDMatrix, MatrixPackError> {
+        let data = match (&pack.data, pack.encoding) {
+            (PackData::Rows(rows), MatrixEncoding::Text) => {
+                let values = MatrixEncoding::from_rows(rows, pack.width, pack.height)?;
+                return Ok(NDMatrix::from_raw_vec(pack.width, pack.height, values));
+            }
+            (PackData::Encoded(data), encoding) if encoding != MatrixEncoding::Text => data,
+            (_, encoding) => {
+                return Err(MatrixPackError::Encoding(format!(
+                    "{:?} data doesn't match its encoding",
+                    encoding
+                )))
+            }
+        };
         let decoded = base64::engine::general_purpose::STANDARD_NO_PAD
-            .decode(&pack.data)
-            .unwrap();
-        let float_array = decoded
-            .chunks_exact(4)
-            .into_iter()
-            .map(|be| f32::from_be_bytes(be.try_into().unwrap()))
-            .collect();
-        NDMatrix::from_raw_vec(pack.width, pack.height, float_array)
+            .decode(data)
+            .map_err(|e| MatrixPackError::Encoding(e.to_string()))?;
+        if let Some(checksum) = pack.checksum {
+            let found = crc32fast::hash(&decoded);
+            if found != checksum {
+                return Err(MatrixPackError::Checksum {
+                    expected: checksum,
+                    found,
+                });
+            }
+        }
+        let len = pack.width.checked_mul(pack.height).ok_or_else(|| {
+            MatrixPackError::Encoding(format!("Shape overflow: {}x{}", pack.width, pack.height))
+        })?;
+        let float_array = pack.encoding.decode(&decoded, len)?;
+        Ok(NDMatrix::from_raw_vec(pack.width, pack.height, float_array))
     }
 }
 
@@ -267,7 +325,15 @@ impl Serialize for NDMatrix {
     where
         S: serde::Serializer,
     {
-        self.pack().serialize(serializer)
+        match BinaryModel::collect_tensor(self) {
+            Some(index) => MatrixRef {
+                width: self.width,
+                height: self.height,
+                tensor: index,
+            }
+            .serialize(serializer),
+            None => self.pack().serialize(serializer),
+        }
     }
 }
 
@@ -276,7 +342,21 @@ impl<'de> Deserialize<'de> for NDMatrix {
     where
         D: Deserializer<'de>,
     {
-        let pack: MatrixPack = MatrixPack::deserialize(deserializer)?;
-        return Ok(NDMatrix::unpack(&pack));
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum PackOrRef {
+            Pack(MatrixPack),
+            Ref(MatrixRef),
+        }
+
+        return match PackOrRef::deserialize(deserializer)? {
+            PackOrRef::Pack(pack) => NDMatrix::unpack(&pack).map_err(serde::de::Error::custom),
+            PackOrRef::Ref(reference) => BinaryModel::provide_tensor(&reference).ok_or_else(|| {
+                serde::de::Error::custom(format!(
+                    "Unresolved tensor reference outside of a binary model: {:?}",
+                    reference
+                ))
+            }),
+        };
     }
 }
@@ -1,2 +1,3 @@
 pub mod model;
-mod tests;
\ No newline at end of file
+pub mod model_state;
+mod tests;
@@ -1,4 +1,9 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter, Error},
+    path::Path,
+};
 
 use indexmap::IndexMap;
 
@@ -6,10 +11,24 @@ use crate::{
     builder::{
         builder::ModelBuilder,
         graph_elements::{BuilderNode, ModelPropagationNode},
+        graph_export::GraphExport,
     },
+    layer::abs::Mode,
     map,
     matrix::nmatrix::NDMatrix,
-    serial::model_serial::{ModelGraph, ModelIO, ModelMeta, ModelSerialized},
+    model::model_state::ModelState,
+    serial::{
+        binary_serial::BinaryModel,
+        keras::{keras_error::KerasError, keras_import::KerasImport},
+        matrix_serial::{EncodingScope, MatrixEncoding},
+        model_reader::{ModelReadError, ModelReader},
+        model_serial::{ModelGraph, ModelIO, ModelMeta, ModelSerialized},
+        model_weights::{ModelWeights, WeightReport},
+        npy_serial::Npy,
+        onnx::{onnx_error::OnnxError, onnx_export::OnnxExport, onnx_import::OnnxImport},
+        safetensors_serial::SafeTensors,
+        weight_serial::{WeightLoadMode, WeightsSerialized},
+    },
     utils::json_wrap::JsonWrap,
 };
 
@@ -18,6 +37,7 @@ pub struct Model {
     pub output_layer_to_data_name: IndexMap<String, String>,
     pub sequential_prop: IndexMap<String, ModelPropagationNode>,
     pub builder_ref: IndexMap<String, BuilderNode>,
+    pub(crate) mode: Mode,
 }
 
 impl Model {
@@ -43,32 +63,90 @@ impl Model {
     }
 
     pub fn propagate(&self, inputs: &HashMap<String, NDMatrix>) -> HashMap<String, NDMatrix> {
+        return self.run(inputs, None);
+    }
+
+    pub fn mode(&self) -> Mode {
+        return self.mode;
+    }
+
+    /**
+     * Switches every layer between training and inference, ex.: dropout only drops while
+     * training and batch norm uses the batch statistics. Models start in inference.
+     */
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.sequential_prop
+            .values_mut()
+            .for_each(|node| node.set_mode(mode));
+    }
+
+    /**
+     * Empty state for Model::step, the recurrent layers start from zeros
+     */
+    pub fn initial_state(&self) -> ModelState {
+        return ModelState::new();
+    }
+
+    /**
+     * One tick of a stateful model, e.g. an observation per environment. Each input row is
+     * its own environment and the recurrent layers advance one step from the state of that
+     * row, which is replaced. See ModelState::reset_rows for episode ends.
+     */
+    pub fn step(
+        &self,
+        inputs: &HashMap<String, NDMatrix>,
+        state: &mut ModelState,
+    ) -> HashMap<String, NDMatrix> {
+        return self.run(inputs, Some(state));
+    }
+
+    pub fn step_single(&self, input: NDMatrix, state: &mut ModelState) -> NDMatrix {
+        let input_map: HashMap<String, NDMatrix> = map! {
+            ModelBuilder::SINGLE_IO.to_string() => input
+        };
+        return self
+            .step(&input_map, state)
+            .remove(ModelBuilder::SINGLE_IO)
+            .unwrap();
+    }
+
+    fn run(
+        &self,
+        inputs: &HashMap<String, NDMatrix>,
+        mut state: Option<&mut ModelState>,
+    ) -> HashMap<String, NDMatrix> {
         let mut data_buffer: HashMap<String, NDMatrix> = HashMap::new();
 
-        self.sequential_prop.iter().for_each(|seq| match seq.1 {
-            ModelPropagationNode::DeadEnd(callable) => {
-                let data_name = self.input_layer_to_data_name.get(seq.0);
-                let data = match data_name {
-                    Some(name) => inputs.get(name).unwrap(),
-                    None => panic!("Missing branch layer: {}", seq.0),
-                };
-                let result = callable.propagate(data);
-                data_buffer.insert(seq.0.clone(), result);
-            }
-            ModelPropagationNode::SingleInput(parent, callable) => {
-                let data = data_buffer.get(parent).unwrap();
-                let result = callable.propagate(data);
-                data_buffer.insert(seq.0.clone(), result);
+        for seq in self.sequential_prop.iter() {
+            match seq.1 {
+                ModelPropagationNode::DeadEnd(callable) => {
+                    let data_name = self.input_layer_to_data_name.get(seq.0);
+                    let data = match data_name {
+                        Some(name) => inputs.get(name).unwrap(),
+                        None => panic!("Missing branch layer: {}", seq.0),
+                    };
+                    let result = callable.propagate(data);
+                    data_buffer.insert(seq.0.clone(), result);
+                }
+                ModelPropagationNode::SingleInput(parent, callable) => {
+                    let data = data_buffer.get(parent).unwrap();
+                    let result = match state.as_deref_mut() {
+                        Some(state) => callable.step(data, state.layer_mut(seq.0)),
+                        None => callable.propagate(data),
+                    };
+                    data_buffer.insert(seq.0.clone(), result);
+                }
+                ModelPropagationNode::MultipleInput(parents, callable) => {
+                    let data: Vec<&NDMatrix> = parents
+                        .iter()
+                        .map(|p| &data_buffer.get(p).unwrap() as &NDMatrix)
+                        .collect();
+                    let result = callable.propagate_multi(&data);
+                    data_buffer.insert(seq.0.clone(), result);
+                }
             }
-            ModelPropagationNode::MultipleInput(parents, callable) => {
-                let data: Vec<&NDMatrix> = parents
-                    .iter()
-                    .map(|p| &data_buffer.get(p).unwrap() as &NDMatrix)
-                    .collect();
-                let result = callable.propagate_multi(&data);
-                data_buffer.insert(seq.0.clone(), result);
-            }
-        });
+        }
 
         let output = self
             .output_layer_to_data_name
@@ -86,6 +164,14 @@ impl Model {
         return output;
     }
 
+    /**
+     * Matrices stored with the given encoding instead of f32, see MatrixEncoding
+     */
+    pub fn to_serialized_model_with(&self, encoding: MatrixEncoding) -> ModelSerialized {
+        let _scope = EncodingScope::open(encoding);
+        return self.to_serialized_model();
+    }
+
     pub fn to_serialized_model(&self) -> ModelSerialized {
         let io = ModelIO {
             inputs: self.input_layer_to_data_name.clone(),
@@ -103,16 +189,239 @@ impl Model {
             .collect();
         let meta = ModelMeta { meta: meta_map };
         return ModelSerialized {
+            version: ModelSerialized::FORMAT_VERSION,
             io: io,
             graph: graph,
             meta: meta,
         };
     }
 
+    /**
+     * Graph exporter without shapes, as those are not kept after building
+     */
+    pub fn graph_export(&self) -> GraphExport {
+        return GraphExport::new(
+            self.builder_ref.clone(),
+            self.input_layer_to_data_name.clone(),
+            self.output_layer_to_data_name.clone(),
+        );
+    }
+
+    pub fn to_dot(&self) -> String {
+        return self.graph_export().to_dot();
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        return self.graph_export().to_mermaid();
+    }
+
     pub fn to_json(&self) -> String {
         return self.to_serialized_model().to_json();
     }
+
+    /**
+     * Reads a model document of any supported format version, see ModelReader::read_json
+     */
+    pub fn from_json(json: &str, reader: &ModelReader) -> Result<Model, ModelReadError> {
+        return Ok(reader.read_json(json)?.build_model(reader));
+    }
     pub fn to_json_pretty(&self) -> String {
         return self.to_serialized_model().to_json_pretty();
     }
+
+    /**
+     * MatrixEncoding::Text gives weights as arrays of numbers that can be read in a diff
+     */
+    pub fn to_json_pretty_with(&self, encoding: MatrixEncoding) -> String {
+        return self.to_serialized_model_with(encoding).to_json_pretty();
+    }
+
+    /**
+     * Weights as arrays of numbers rounded to the given decimals, see MatrixEncoding::Text
+     */
+    pub fn to_json_pretty_text(&self, decimals: u32) -> String {
+        let _scope = EncodingScope::text(decimals);
+        return self.to_serialized_model().to_json_pretty();
+    }
+
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(file, &self.to_serialized_model().sealed(None))?;
+        return Ok(());
+    }
+
+    /**
+     * Streams the file into the model, for models too large to hold their json in memory,
+     * see ModelReader::read_stream
+     */
+    pub fn load_json<P: AsRef<Path>>(path: P, reader: &ModelReader) -> Result<Model, Error> {
+        let file = BufReader::new(File::open(path)?);
+        return Ok(reader.read_stream(file)?);
+    }
+
+    /**
+     * Smaller documents, e.g. MatrixEncoding::F16 halves the weights at a loss of precision
+     * and MatrixEncoding::Zstd compresses them losslessly
+     */
+    pub fn to_json_with(&self, encoding: MatrixEncoding) -> String {
+        return self.to_serialized_model_with(encoding).to_json();
+    }
+
+    /**
+     * Signed with a local key, see ModelReader::require_signature
+     */
+    pub fn to_json_signed(&self, key: &[u8]) -> String {
+        return self.to_serialized_model().to_json_signed(key);
+    }
+
+    /**
+     * Binary container with raw tensors, see BinaryModel for the layout
+     */
+    pub fn to_binary(&self) -> Vec<u8> {
+        return BinaryModel::encode(self);
+    }
+
+    /**
+     * Signed with a local key, see ModelReader::require_signature
+     */
+    pub fn to_binary_signed(&self, key: &[u8]) -> Vec<u8> {
+        return BinaryModel::encode_signed(self, key);
+    }
+
+    pub fn from_binary(bytes: &[u8], reader: &ModelReader) -> Result<Model, Error> {
+        return BinaryModel::decode(bytes, reader);
+    }
+
+    pub fn save_binary<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        return std::fs::write(path, self.to_binary());
+    }
+
+    /**
+     * Reads the whole file and copies every tensor out of it, so loading peaks at about twice
+     * the file size. Only the layout is ready for memory-mapping, see BinaryModel.
+     */
+    pub fn load_binary<P: AsRef<Path>>(path: P, reader: &ModelReader) -> Result<Model, Error> {
+        let bytes = std::fs::read(path)?;
+        return Self::from_binary(&bytes, reader);
+    }
+
+    /**
+     * Onnx protobuf of the model, see OnnxExport for the supported layers and activations
+     */
+    pub fn to_onnx(&self) -> Result<Vec<u8>, OnnxError> {
+        return OnnxExport::to_bytes(&self.to_serialized_model());
+    }
+
+    pub fn save_onnx<P: AsRef<Path>>(&self, path: P) -> Result<(), OnnxError> {
+        std::fs::write(path, self.to_onnx()?)?;
+        return Ok(());
+    }
+
+    /**
+     * Feed-forward onnx graph, see OnnxImport for the supported operators
+     */
+    pub fn from_onnx(bytes: &[u8]) -> Result<Model, OnnxError> {
+        return OnnxImport::from_bytes(bytes);
+    }
+
+    pub fn load_onnx<P: AsRef<Path>>(path: P) -> Result<Model, OnnxError> {
+        let bytes = std::fs::read(path)?;
+        return Self::from_onnx(&bytes);
+    }
+
+    /**
+     * Keras model.to_json() config with its weights by keras name, see KerasImport for the
+     * supported layers
+     */
+    pub fn from_keras(
+        json: &str,
+        weights: &IndexMap<String, NDMatrix>,
+    ) -> Result<Model, KerasError> {
+        return KerasImport::from_json(json, weights);
+    }
+
+    pub fn load_keras<P: AsRef<Path>, W: AsRef<Path>>(
+        json_path: P,
+        weights_npz: W,
+    ) -> Result<Model, KerasError> {
+        let json = std::fs::read_to_string(json_path)?;
+        let weights = Npy::read_npz(weights_npz)?;
+        return Self::from_keras(&json, &weights);
+    }
+
+    /**
+     * Every weight and bias as "{layer}.{field}" .npy arrays, see ModelWeights for the names
+     */
+    pub fn export_weights_npz<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        return Npy::write_npz(path, &ModelWeights::collect(self));
+    }
+
+    /**
+     * Replaces all weights of the model, the arrays must match names and shapes exactly
+     */
+    pub fn import_weights_npz<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        reader: &ModelReader,
+    ) -> Result<(), Error> {
+        let weights = Npy::read_npz(path)?;
+        ModelWeights::apply(self, &weights, reader)?;
+        return Ok(());
+    }
+
+    /**
+     * Weights in safetensors layout, tensors are named "{layer}.{field}" after builder_ref
+     */
+    pub fn to_safetensors(&self) -> Vec<u8> {
+        return SafeTensors::encode(&ModelWeights::collect(self));
+    }
+
+    pub fn save_safetensors<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        return std::fs::write(path, self.to_safetensors());
+    }
+
+    /**
+     * Replaces all weights of the model, the tensors must match names and shapes exactly
+     */
+    pub fn load_safetensors<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        reader: &ModelReader,
+    ) -> Result<(), Error> {
+        let weights = SafeTensors::decode(&std::fs::read(path)?)?;
+        ModelWeights::apply(self, &weights, reader)?;
+        return Ok(());
+    }
+
+    /**
+     * Loads only the tensors that match by name and shape and reports the rest
+     */
+    pub fn load_safetensors_partial<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        reader: &ModelReader,
+    ) -> Result<WeightReport, Error> {
+        let weights = SafeTensors::decode(&std::fs::read(path)?)?;
+        return ModelWeights::apply_partial(self, &weights, reader);
+    }
+
+    /**
+     * Weights-only json, the parameters without the graph
+     */
+    pub fn save_weights<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        return std::fs::write(path, WeightsSerialized::from_model(self).to_json());
+    }
+
+    /**
+     * Matches weights by layer name and shape, see WeightLoadMode
+     */
+    pub fn load_weights<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        mode: WeightLoadMode,
+        reader: &ModelReader,
+    ) -> Result<WeightReport, Error> {
+        let serialized: WeightsSerialized = serde_json::from_slice(&std::fs::read(path)?)?;
+        return serialized.apply(self, mode, reader);
+    }
 }
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use crate::matrix::nmatrix::NDMatrix;
+
+/**
+ * State of the recurrent layers carried between Model::step calls, by layer name.
+ * Each row belongs to one environment of the batch. A layer's state is created with zeros
+ * on its first step, so the batch size is set by the first inputs and stays fixed until
+ * reset.
+ */
+#[derive(Default)]
+pub struct ModelState {
+    layers: HashMap<String, Vec<NDMatrix>>,
+}
+
+impl ModelState {
+    pub fn new() -> ModelState {
+        return ModelState::default();
+    }
+
+    /**
+     * Matrices of a layer, e.g. [hidden, cell] of an LSTM, empty before its first step
+     */
+    pub fn layer(&self, name: &str) -> &[NDMatrix] {
+        return self.layers.get(name).map_or(&[], |state| state.as_slice());
+    }
+
+    pub(crate) fn layer_mut(&mut self, name: &str) -> &mut Vec<NDMatrix> {
+        return self.layers.entry(name.to_string()).or_default();
+    }
+
+    /**
+     * Rows in the state, None before the first step
+     */
+    pub fn batch(&self) -> Option<usize> {
+        return self
+            .layers
+            .values()
+            .flat_map(|state| state.first())
+            .map(|matrix| matrix.height)
+            .next();
+    }
+
+    /**
+     * Zeros the rows of environments whose episode ended, the others keep their state.
+     * Panics on rows outside of the batch.
+     */
+    pub fn reset_rows(&mut self, rows: &[usize]) {
+        for matrix in self.layers.values_mut().flatten() {
+            for row in rows {
+                if *row >= matrix.height {
+                    panic!("Row {} outside of a batch of {}", row, matrix.height);
+                }
+                matrix.values.row_mut(*row).fill(0.0);
+            }
+        }
+    }
+
+    /**
+     * Back to the initial state, the next step may use another batch size
+     */
+    pub fn reset(&mut self) {
+        self.layers.clear();
+    }
+}
@@ -3,7 +3,14 @@ mod test {
     use crate::{
         activation::{relu::ReLu, tanh::Tanh},
         builder::builder::ModelBuilder,
-        layer::{dense::Dense, input::Input, concat::Concat},
+        layer::{
+            concat::Concat,
+            conv1d::{Conv1D, Padding},
+            dense::Dense,
+            flatten::Flatten,
+            input::Input,
+            lstm::Lstm,
+        },
         matrix::{meta::shape::Shape, nmatrix::NDMatrix},
         suppliers::suppliers::{GlorothNormalSupplier, ZeroSupplier, RandomUniformSupplier}, utils::extensions::eq_vecs,
     };
@@ -81,5 +88,80 @@ mod test {
         });
     }
 
+    fn assert_row(output: &NDMatrix, row: usize, expected: &NDMatrix, close: bool) {
+        let same = output
+            .values
+            .row(row)
+            .iter()
+            .zip(expected.iter_all())
+            .all(|(a, b)| (a - b).abs() < 1e-5);
+        assert_eq!(same, close, "row {} of {:?} against {:?}", row, output, expected);
+    }
 
+    #[test]
+    pub fn model_test_step_state() {
+        let input_1 = Input::new(Shape::Const(3), Shape::Repeat);
+        let lstm = Lstm::builder(4, || &input_1)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let d1 = Dense::builder(2, || &lstm)
+            .with_activation(Tanh::default())
+            .build();
+        let model = ModelBuilder::from_straight(input_1, d1).build();
+
+        let first = NDMatrix::from_supply(3, 5, RandomUniformSupplier::new(1.0, -1.0));
+        let second = NDMatrix::from_supply(3, 5, RandomUniformSupplier::new(1.0, -1.0));
+
+        // one environment per row, each fed a step of its own sequence per tick
+        let mut state = model.initial_state();
+        assert_eq!(state.batch(), None);
+        let mut output = NDMatrix::new(2, 2);
+        for tick in 0..5 {
+            let values = ndarray::stack(
+                ndarray::Axis(0),
+                &[first.values.row(tick), second.values.row(tick)],
+            )
+            .unwrap();
+            output = model.step_single(NDMatrix::with(3, 2, values), &mut state);
+        }
+        assert_eq!(state.batch(), Some(2));
+        assert_eq!(state.layer("LSTM_1").len(), 2);
+        assert_row(&output, 0, &model.propagate_single(first), true);
+        assert_row(&output, 1, &model.propagate_single(second), true);
+
+        // the first environment starts a new episode, the second keeps its memory
+        state.reset_rows(&[0]);
+        let output = model.step_single(NDMatrix::constant(3, 2, 0.5), &mut state);
+        let fresh = model.propagate_single(NDMatrix::constant(3, 1, 0.5));
+        assert_row(&output, 0, &fresh, true);
+        assert_row(&output, 1, &fresh, false);
+
+        state.reset();
+        assert_eq!(state.batch(), None);
+    }
+
+    #[test]
+    pub fn model_test_step_environments() {
+        let input_1 = Input::new(Shape::Const(3), Shape::Repeat);
+        let conv = Conv1D::builder(2, 3, || &input_1)
+            .with_padding(Padding::Same)
+            .build();
+        let flatten = Flatten::new(|| &conv);
+        let model = ModelBuilder::from_straight(input_1, flatten).build();
+
+        // rows mixed by the convolution and flatten stay within their environment
+        let observations = NDMatrix::from_supply(3, 2, RandomUniformSupplier::new(1.0, -1.0));
+        let mut state = model.initial_state();
+        let output = model.step_single(observations.clone(), &mut state);
+        assert_eq!((output.width, output.height), (2, 2));
+        for row in 0..2 {
+            let values = observations
+                .values
+                .row(row)
+                .to_owned()
+                .insert_axis(ndarray::Axis(0));
+            let alone = model.propagate_single(NDMatrix::with(3, 1, values));
+            assert_row(&output, row, &alone, true);
+        }
+    }
 }
@@ -0,0 +1,337 @@
+use std::{
+    cell::RefCell,
+    io::{Error, ErrorKind, Write},
+    ops::Range,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{matrix::nmatrix::NDMatrix, model::model::Model};
+
+use super::{
+    integrity::{IntegrityHasher, ModelIntegrity},
+    model_reader::{ModelReadError, ModelReader},
+};
+
+/**
+ * Binary container of a serialized model:
+ *
+ * | magic "NTCK" | version u32 | json length u64 | json header | padding | tensor data |
+ *
+ * All integers are little-endian. The json header holds the model graph/io/meta with every
+ * matrix replaced by a reference into the tensor table. Tensors are raw little-endian f32
+ * in the row-major (height, width) layout of NDMatrix::values. The data section and every
+ * tensor in it start at a multiple of TENSOR_ALIGNMENT bytes, so a reader can memory-map
+ * them. decode doesn't, every tensor is copied into its NDMatrix.
+ *
+ * The integrity entry of the json header covers the header and the tensor bytes, see
+ * ModelIntegrity. Files written before it only have one in the model document.
+ */
+pub struct BinaryModel;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TensorEntry {
+    pub width: usize,
+    pub height: usize,
+    /**
+     * Byte offset from the start of the data section, see BinaryModel::data_start
+     */
+    pub offset: usize,
+    /**
+     * crc32 of the tensor bytes, missing in files written before checksums
+     */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BinaryHeader<M> {
+    tensors: Vec<TensorEntry>,
+    model: M,
+}
+
+/**
+ * Serialized form of a matrix inside a binary model
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MatrixRef {
+    pub width: usize,
+    pub height: usize,
+    pub tensor: usize,
+}
+
+enum TensorTable {
+    Collect(Vec<NDMatrix>),
+    Provide(Vec<Option<NDMatrix>>),
+}
+
+thread_local! {
+    static TENSOR_TABLE: RefCell<Option<TensorTable>> = const { RefCell::new(None) };
+}
+
+/**
+ * Clears the tensor table when leaving a binary (de)serialization, also on panic
+ */
+struct TensorScope;
+
+impl TensorScope {
+    fn open(table: TensorTable) -> TensorScope {
+        TENSOR_TABLE.with(|t| *t.borrow_mut() = Some(table));
+        return TensorScope;
+    }
+
+    fn collected(&self) -> Vec<NDMatrix> {
+        return TENSOR_TABLE.with(|t| match t.borrow_mut().take() {
+            Some(TensorTable::Collect(tensors)) => tensors,
+            _ => vec![],
+        });
+    }
+}
+
+impl Drop for TensorScope {
+    fn drop(&mut self) {
+        TENSOR_TABLE.with(|t| *t.borrow_mut() = None);
+    }
+}
+
+impl BinaryModel {
+    pub const MAGIC: &[u8; 4] = b"NTCK";
+    pub const VERSION: u32 = 1;
+    pub const TENSOR_ALIGNMENT: usize = 64;
+
+    /**
+     * Used by the NDMatrix serialization, returns a tensor index when inside a binary save
+     */
+    pub fn collect_tensor(matrix: &NDMatrix) -> Option<usize> {
+        return TENSOR_TABLE.with(|t| match t.borrow_mut().as_mut() {
+            Some(TensorTable::Collect(tensors)) => {
+                tensors.push(matrix.clone());
+                Some(tensors.len() - 1)
+            }
+            _ => None,
+        });
+    }
+
+    /**
+     * Used by the NDMatrix deserialization, resolves a reference when inside a binary load
+     */
+    pub fn provide_tensor(reference: &MatrixRef) -> Option<NDMatrix> {
+        return TENSOR_TABLE.with(|t| match t.borrow_mut().as_mut() {
+            Some(TensorTable::Provide(tensors)) => tensors
+                .get_mut(reference.tensor)
+                .and_then(|tensor| tensor.take())
+                .filter(|m| m.width == reference.width && m.height == reference.height),
+            _ => None,
+        });
+    }
+
+    pub fn encode(model: &Model) -> Vec<u8> {
+        return Self::encode_sealed(model, None);
+    }
+
+    /**
+     * Signed with a local key, see ModelReader::require_signature
+     */
+    pub fn encode_signed(model: &Model, key: &[u8]) -> Vec<u8> {
+        return Self::encode_sealed(model, Some(key));
+    }
+
+    fn encode_sealed(model: &Model, key: Option<&[u8]>) -> Vec<u8> {
+        let scope = TensorScope::open(TensorTable::Collect(vec![]));
+        let serialized = model.to_serialized_model();
+        let tensors = scope.collected();
+        drop(scope);
+
+        let mut data_len = 0usize;
+        let raw_tensors: Vec<Vec<u8>> = tensors
+            .iter()
+            .map(|m| m.iter_all().flat_map(|f| f.to_le_bytes()).collect())
+            .collect();
+        let entries: Vec<TensorEntry> = tensors
+            .iter()
+            .zip(raw_tensors.iter())
+            .map(|(m, raw)| {
+                let entry = TensorEntry {
+                    width: m.width,
+                    height: m.height,
+                    offset: data_len,
+                    checksum: Some(crc32fast::hash(raw)),
+                };
+                data_len = Self::align(data_len + m.width * m.height * 4);
+                entry
+            })
+            .collect();
+
+        let header = BinaryHeader {
+            tensors: entries,
+            model: serialized,
+        };
+        let mut document = serde_json::to_value(&header).unwrap();
+        let mut hasher = IntegrityHasher::new(key);
+        serde_json::to_writer(&mut hasher, &document).unwrap();
+        raw_tensors
+            .iter()
+            .for_each(|raw| hasher.write_all(raw).unwrap());
+        document.as_object_mut().unwrap().insert(
+            ModelIntegrity::INTEGRITY_KEY.to_string(),
+            serde_json::to_value(hasher.seal()).unwrap(),
+        );
+        let header_json = serde_json::to_vec(&document).unwrap();
+        let data_start = Self::data_start(header_json.len());
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(data_start + data_len);
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.extend_from_slice(&Self::VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(header_json.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&header_json);
+        header
+            .tensors
+            .iter()
+            .zip(raw_tensors.iter())
+            .for_each(|(entry, raw)| {
+                bytes.resize(data_start + entry.offset, 0);
+                bytes.extend_from_slice(raw);
+            });
+        bytes.resize(data_start + data_len, 0);
+        return bytes;
+    }
+
+    pub fn decode(bytes: &[u8], reader: &ModelReader) -> Result<Model, Error> {
+        if bytes.len() < 16 || &bytes[0..4] != Self::MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "Not a binary model"));
+        }
+        let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        if version != Self::VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Unsupported binary model version: {}", version),
+            ));
+        }
+        let json_len = usize::try_from(u64::from_le_bytes(bytes[8..16].try_into().unwrap()))
+            .ok()
+            .filter(|len| len.checked_add(16).is_some())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Model header length overflow"))?;
+        let json = bytes
+            .get(16..16 + json_len)
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "Truncated model header"))?;
+        let mut document: Value = serde_json::from_slice(json)?;
+        let integrity = document
+            .as_object_mut()
+            .and_then(|object| object.remove(ModelIntegrity::INTEGRITY_KEY));
+        let mut hasher = IntegrityHasher::new(reader.signing_key());
+        serde_json::to_writer(&mut hasher, &document)?;
+        let header: BinaryHeader<Value> = serde_json::from_value(document)?;
+        let data_start = Self::data_start(json_len);
+
+        let tensors = header
+            .tensors
+            .iter()
+            .map(|entry| {
+                let range = Self::tensor_range(data_start, entry).ok_or_else(|| {
+                    Error::new(ErrorKind::InvalidData, "Tensor offset or size overflow")
+                })?;
+                let raw = bytes
+                    .get(range)
+                    .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "Truncated tensor"))?;
+                if entry.checksum.is_some_and(|c| c != crc32fast::hash(raw)) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "Tensor checksum mismatch",
+                    ));
+                }
+                hasher.write_all(raw)?;
+                let values = raw
+                    .chunks_exact(4)
+                    .map(|le| f32::from_le_bytes(le.try_into().unwrap()))
+                    .collect();
+                Ok(Some(NDMatrix::from_raw_vec(
+                    entry.width,
+                    entry.height,
+                    values,
+                )))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // the model document is migrated like a json model, once nothing was modified
+        let sealed = integrity.is_some();
+        hasher.check(integrity).map_err(ModelReadError::Integrity)?;
+        let model = if sealed {
+            reader.read_verified(header.model)?
+        } else {
+            reader.read_value(header.model)?
+        };
+
+        // layers unwrap their matrices, so every reference is resolved up front
+        let mut used = vec![false; header.tensors.len()];
+        for (layer, meta) in model.meta.meta.iter() {
+            Self::verify_refs(meta.as_value(), &header.tensors, &mut used).map_err(|e| {
+                Error::new(ErrorKind::InvalidData, format!("Layer {}: {}", layer, e))
+            })?;
+        }
+
+        let _scope = TensorScope::open(TensorTable::Provide(tensors));
+        return Ok(model.build_model(reader));
+    }
+
+    /**
+     * Checks that every MatrixRef in a layer meta points to an unused tensor of its shape
+     */
+    fn verify_refs(
+        value: &Value,
+        entries: &[TensorEntry],
+        used: &mut [bool],
+    ) -> Result<(), String> {
+        match value {
+            Value::Object(fields) => {
+                if let Ok(reference) = MatrixRef::deserialize(value) {
+                    let entry = entries.get(reference.tensor).filter(|entry| {
+                        entry.width == reference.width && entry.height == reference.height
+                    });
+                    if entry.is_none() || used[reference.tensor] {
+                        return Err(format!(
+                            "Tensor reference {:?} doesn't match the tensor table",
+                            reference
+                        ));
+                    }
+                    used[reference.tensor] = true;
+                    return Ok(());
+                }
+                for field in fields.values() {
+                    Self::verify_refs(field, entries, used)?;
+                }
+            }
+            Value::Array(items) => {
+                for item in items.iter() {
+                    Self::verify_refs(item, entries, used)?;
+                }
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    /**
+     * Tensor offsets are relative to this position, the first aligned byte after the header
+     */
+    pub fn data_start(json_len: usize) -> usize {
+        return Self::align(4 + 4 + 8 + json_len);
+    }
+
+    /**
+     * Bytes of a tensor in the file, None when its offset or size overflow
+     */
+    fn tensor_range(data_start: usize, entry: &TensorEntry) -> Option<Range<usize>> {
+        let start = data_start.checked_add(entry.offset)?;
+        let len = entry.width.checked_mul(entry.height)?.checked_mul(4)?;
+        return Some(start..start.checked_add(len)?);
+    }
+
+    fn align(offset: usize) -> usize {
+        let rest = offset % Self::TENSOR_ALIGNMENT;
+        if rest == 0 {
+            return offset;
+        }
+        return offset + Self::TENSOR_ALIGNMENT - rest;
+    }
+}
@@ -0,0 +1,164 @@
+use indexmap::IndexMap;
+
+use crate::{
+    activation::{abs::Activation, none::NoneAct},
+    builder::graph_elements::{
+        BuilderNode, DeadEndStruct, ModelPropagationNode, MultipleParentStruct, SingleParentStruct,
+    },
+    layer::{
+        abs::Mode,
+        concat::{Concat, ConcatImpl},
+        dense::{Dense, DenseImpl},
+        direct::{Direct, DirectImpl},
+        flatten::{Flatten, FlattenImpl},
+        input::{Input, InputImpl},
+    },
+    matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+    model::model::Model,
+};
+
+/**
+ * Layer of a model converted from another framework (onnx, keras), parents are indices of
+ * earlier layers
+ */
+pub(crate) struct ImportedLayer {
+    pub(crate) kind: ImportedKind,
+    pub(crate) features: Shape,
+    pub(crate) size: Shape,
+    /**
+     * Latest tensor produced by the layer, only this one can be fused into it
+     */
+    pub(crate) output: String,
+}
+
+pub(crate) enum ImportedKind {
+    Input,
+    Dense(usize, Weighted),
+    Direct(usize, Weighted),
+    Concat(Vec<usize>),
+    Flatten(usize),
+}
+
+pub(crate) struct Weighted {
+    pub(crate) weight: NDMatrix,
+    pub(crate) bias: Option<NDMatrix>,
+    pub(crate) activation: Option<Box<dyn Activation>>,
+}
+
+impl ImportedLayer {
+    /**
+     * "{Type}_{index}" like the builder names layers
+     */
+    pub(crate) fn names(layers: &[ImportedLayer]) -> Vec<String> {
+        return layers
+            .iter()
+            .enumerate()
+            .map(|(i, l)| format!("{}_{}", l.kind.type_name(), i))
+            .collect();
+    }
+
+    /**
+     * Missing biases are zero and missing activations NoneAct
+     */
+    pub(crate) fn into_model(
+        layers: Vec<ImportedLayer>,
+        names: &[String],
+        input_layer_to_data_name: IndexMap<String, String>,
+        output_layer_to_data_name: IndexMap<String, String>,
+    ) -> Model {
+        let mut sequential_prop: IndexMap<String, ModelPropagationNode> = IndexMap::new();
+        let mut builder_ref: IndexMap<String, BuilderNode> = IndexMap::new();
+        for (layer, name) in layers.into_iter().zip(names.iter()) {
+            let type_name = layer.kind.type_name().to_string();
+            let (builder_node, prop_node) = match layer.kind {
+                ImportedKind::Input => {
+                    let instance = InputImpl::new(name.clone(), layer.features, layer.size);
+                    (
+                        BuilderNode::DeadEnd(DeadEndStruct {
+                            layer_name: name.clone(),
+                            type_name,
+                        }),
+                        ModelPropagationNode::DeadEnd(Box::new(instance)),
+                    )
+                }
+                ImportedKind::Dense(parent, w) => {
+                    let bias = w.bias.unwrap_or_else(|| NDMatrix::new(w.weight.width, 1));
+                    let activation = w.activation.unwrap_or_else(|| Box::new(NoneAct));
+                    let instance = DenseImpl::new(name.clone(), w.weight, bias, activation);
+                    (
+                        Self::single_node(name, type_name, &names[parent]),
+                        ModelPropagationNode::SingleInput(
+                            names[parent].clone(),
+                            Box::new(instance),
+                        ),
+                    )
+                }
+                ImportedKind::Direct(parent, w) => {
+                    let bias = w.bias.unwrap_or_else(|| NDMatrix::new(w.weight.width, 1));
+                    let activation = w.activation.unwrap_or_else(|| Box::new(NoneAct));
+                    let instance = DirectImpl::new(name.clone(), w.weight, bias, activation);
+                    (
+                        Self::single_node(name, type_name, &names[parent]),
+                        ModelPropagationNode::SingleInput(
+                            names[parent].clone(),
+                            Box::new(instance),
+                        ),
+                    )
+                }
+                ImportedKind::Concat(parents) => {
+                    let parent_names: Vec<String> =
+                        parents.iter().map(|p| names[*p].clone()).collect();
+                    let instance = ConcatImpl::new(name.clone(), layer.features, layer.size);
+                    (
+                        BuilderNode::MultipleParent(MultipleParentStruct {
+                            layer_name: name.clone(),
+                            type_name,
+                            parent_names: parent_names.clone(),
+                        }),
+                        ModelPropagationNode::MultipleInput(parent_names, Box::new(instance)),
+                    )
+                }
+                ImportedKind::Flatten(parent) => {
+                    let instance = FlattenImpl::new(name.clone());
+                    (
+                        Self::single_node(name, type_name, &names[parent]),
+                        ModelPropagationNode::SingleInput(
+                            names[parent].clone(),
+                            Box::new(instance),
+                        ),
+                    )
+                }
+            };
+            builder_ref.insert(name.clone(), builder_node);
+            sequential_prop.insert(name.clone(), prop_node);
+        }
+
+        return Model {
+            input_layer_to_data_name,
+            output_layer_to_data_name,
+            sequential_prop,
+            builder_ref,
+            mode: Mode::default(),
+        };
+    }
+
+    fn single_node(name: &str, type_name: String, parent: &str) -> BuilderNode {
+        return BuilderNode::SingleParent(SingleParentStruct {
+            layer_name: name.to_string(),
+            type_name,
+            parent_name: parent.to_string(),
+        });
+    }
+}
+
+impl ImportedKind {
+    fn type_name(&self) -> &'static str {
+        return match self {
+            ImportedKind::Input => Input::NAME,
+            ImportedKind::Dense(..) => Dense::NAME,
+            ImportedKind::Direct(..) => Direct::NAME,
+            ImportedKind::Concat(..) => Concat::NAME,
+            ImportedKind::Flatten(..) => Flatten::NAME,
+        };
+    }
+}
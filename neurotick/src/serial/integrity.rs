@@ -0,0 +1,163 @@
+use std::{fmt::Display, io::Write};
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/**
+ * Whole-document checksum and optional signature of a serialized model, stored under
+ * INTEGRITY_KEY. Both cover the compact json of the document without that key, so a
+ * document can be reformatted but not edited.
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ModelIntegrity {
+    /**
+     * sha256, hex
+     */
+    pub checksum: String,
+    /**
+     * hmac-sha256 with the writer's key, hex
+     */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum IntegrityError {
+    Malformed(String),
+    Checksum,
+    /**
+     * A signing key is required but the document isn't signed
+     */
+    Unsigned,
+    Signature,
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+impl ModelIntegrity {
+    pub const INTEGRITY_KEY: &str = "integrity";
+
+    /**
+     * Adds the checksum, and the signature when a key is given, replacing previous ones
+     */
+    pub fn seal(document: &mut Value, key: Option<&[u8]>) {
+        let Some(object) = document.as_object_mut() else {
+            return;
+        };
+        object.remove(Self::INTEGRITY_KEY);
+        let mut hasher = IntegrityHasher::new(key);
+        serde_json::to_writer(&mut hasher, object).unwrap();
+        object.insert(
+            Self::INTEGRITY_KEY.to_string(),
+            serde_json::to_value(hasher.seal()).unwrap(),
+        );
+    }
+
+    /**
+     * Removes and checks the integrity of the document. Documents written before checksums
+     * have none and pass, unless a key is given: then a valid signature is required.
+     */
+    pub fn verify(document: &mut Value, key: Option<&[u8]>) -> Result<(), IntegrityError> {
+        let Some(object) = document.as_object_mut() else {
+            return Err(IntegrityError::Malformed(
+                "Model document is not an object".to_string(),
+            ));
+        };
+        let integrity = object.remove(Self::INTEGRITY_KEY);
+        let mut hasher = IntegrityHasher::new(key);
+        serde_json::to_writer(&mut hasher, object).unwrap();
+        return hasher.check(integrity);
+    }
+}
+
+/**
+ * Checksum and signature of a document written in its compact form, piece by piece when
+ * it is streamed
+ */
+pub struct IntegrityHasher {
+    digest: Sha256,
+    mac: Option<HmacSha256>,
+}
+
+impl IntegrityHasher {
+    pub fn new(key: Option<&[u8]>) -> IntegrityHasher {
+        return IntegrityHasher {
+            digest: Sha256::new(),
+            mac: key.map(|k| HmacSha256::new_from_slice(k).expect("hmac accepts any key")),
+        };
+    }
+
+    pub fn seal(self) -> ModelIntegrity {
+        return ModelIntegrity {
+            checksum: hex(&self.digest.finalize()),
+            signature: self.mac.map(|mac| hex(&mac.finalize().into_bytes())),
+        };
+    }
+
+    /**
+     * Checks the integrity entry of the document, see ModelIntegrity::verify
+     */
+    pub fn check(self, integrity: Option<Value>) -> Result<(), IntegrityError> {
+        let integrity = match integrity {
+            Some(value) => serde_json::from_value::<ModelIntegrity>(value)
+                .map_err(|e| IntegrityError::Malformed(e.to_string()))?,
+            None if self.mac.is_some() => return Err(IntegrityError::Unsigned),
+            None => return Ok(()),
+        };
+        if hex(&self.digest.finalize()) != integrity.checksum.to_lowercase() {
+            return Err(IntegrityError::Checksum);
+        }
+        if let Some(mac) = self.mac {
+            let signature = integrity.signature.ok_or(IntegrityError::Unsigned)?;
+            let signature = unhex(&signature).ok_or(IntegrityError::Malformed(
+                "Signature is not hex".to_string(),
+            ))?;
+            mac.verify_slice(&signature)
+                .map_err(|_| IntegrityError::Signature)?;
+        }
+        return Ok(());
+    }
+}
+
+impl Write for IntegrityHasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.digest.update(buf);
+        if let Some(mac) = self.mac.as_mut() {
+            mac.update(buf);
+        }
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return Ok(());
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|b| format!("{:02x}", b)).collect();
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    return (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect();
+}
+
+impl Display for IntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegrityError::Malformed(e) => write!(f, "Malformed model integrity: {}", e),
+            IntegrityError::Checksum => write!(f, "Model checksum mismatch, the file was modified"),
+            IntegrityError::Unsigned => write!(f, "Model is not signed"),
+            IntegrityError::Signature => write!(f, "Model signature is invalid"),
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
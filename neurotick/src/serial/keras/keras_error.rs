@@ -0,0 +1,40 @@
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum KerasError {
+    /**
+     * A layer, activation or option that has no Neurotick mapping
+     */
+    Unsupported(String),
+    /**
+     * The config or the weights are malformed or inconsistent
+     */
+    Invalid(String),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl Display for KerasError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KerasError::Unsupported(m) => write!(f, "Unsupported by keras import: {}", m),
+            KerasError::Invalid(m) => write!(f, "Invalid keras model: {}", m),
+            KerasError::Io(e) => write!(f, "Keras io error: {}", e),
+            KerasError::Json(e) => write!(f, "Keras json error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for KerasError {}
+
+impl From<std::io::Error> for KerasError {
+    fn from(value: std::io::Error) -> Self {
+        return KerasError::Io(value);
+    }
+}
+
+impl From<serde_json::Error> for KerasError {
+    fn from(value: serde_json::Error) -> Self {
+        return KerasError::Json(value);
+    }
+}
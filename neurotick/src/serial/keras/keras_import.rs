@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use serde_json::Value;
+
+use crate::{
+    activation::{
+        abs::Activation, lerelu::LeakyReLu, relu::ReLu, sigmoid::Sigmoid, softmax::SoftMax,
+        tanh::Tanh,
+    },
+    builder::builder::ModelBuilder,
+    matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+    model::model::Model,
+    serial::imported_graph::{ImportedKind, ImportedLayer, Weighted},
+};
+
+use super::keras_error::KerasError;
+
+/**
+ * Maps a keras model.to_json() config of a Sequential or Functional model onto Neurotick
+ * layers, with the weights of a dump such as
+ * `np.savez("weights.npz", **{w.path: w.numpy() for w in model.weights})`:
+ *
+ * InputLayer -> Input, Dense -> Dense, Concatenate (last axis) -> Concat,
+ * Flatten -> Flatten (passthrough on 2d tensors), Activation, ReLU, LeakyReLU, Softmax -> Direct
+ * with unit weights
+ *
+ * Weights are matched by the last two segments of their name, "{layer}/{kernel|bias}", a
+ * ":0" suffix of keras 2 names is ignored. A model with one input and one output uses
+ * ModelBuilder::SINGLE_IO, otherwise the keras names of the input and output layers are the
+ * data names.
+ */
+pub struct KerasImport<'w> {
+    weights: HashMap<(String, String), &'w NDMatrix>,
+    layers: Vec<ImportedLayer>,
+    /**
+     * Rank of the keras tensor of each layer, batch axis included
+     */
+    ranks: Vec<usize>,
+    /**
+     * Keras layer name to the layer producing its output
+     */
+    tensors: HashMap<String, usize>,
+}
+
+impl<'w> KerasImport<'w> {
+    pub fn from_json(
+        json: &str,
+        weights: &'w IndexMap<String, NDMatrix>,
+    ) -> Result<Model, KerasError> {
+        return Self::import(&serde_json::from_str(json)?, weights);
+    }
+
+    pub fn import(
+        config: &Value,
+        weights: &'w IndexMap<String, NDMatrix>,
+    ) -> Result<Model, KerasError> {
+        let mut import = KerasImport {
+            weights: HashMap::new(),
+            layers: vec![],
+            ranks: vec![],
+            tensors: HashMap::new(),
+        };
+        for (name, matrix) in weights.iter() {
+            let name = name.split(':').next().unwrap_or(name);
+            let mut segments = name.rsplit('/');
+            if let (Some(param), Some(layer)) = (segments.next(), segments.next()) {
+                import
+                    .weights
+                    .insert((layer.to_string(), param.to_string()), matrix);
+            }
+        }
+
+        let class_name = config["class_name"].as_str().unwrap_or("");
+        let model_config = &config["config"];
+        // keras 2.0 serialized sequential layers as the config itself
+        let keras_layers = model_config["layers"]
+            .as_array()
+            .or(model_config.as_array())
+            .ok_or_else(|| KerasError::Invalid("Model config without layers".to_string()))?;
+
+        let (inputs, outputs) = match class_name {
+            "Sequential" => import.sequential(keras_layers)?,
+            "Functional" | "Model" => {
+                for layer in keras_layers.iter() {
+                    let parents = Self::inbound(layer)?;
+                    import.layer(layer, &parents)?;
+                }
+                (
+                    Self::io_layers(&model_config["input_layers"])?,
+                    Self::io_layers(&model_config["output_layers"])?,
+                )
+            }
+            other => return Err(KerasError::Unsupported(format!("model class {}", other))),
+        };
+
+        let names = ImportedLayer::names(&import.layers);
+        let single = inputs.len() == 1 && outputs.len() == 1;
+        let data_names =
+            |keras_names: Vec<String>| -> Result<IndexMap<String, String>, KerasError> {
+                let mut map: IndexMap<String, String> = IndexMap::new();
+                for keras_name in keras_names {
+                    let index = import.layer_of(&keras_name)?;
+                    let data_name = match single {
+                        true => ModelBuilder::SINGLE_IO.to_string(),
+                        false => keras_name.clone(),
+                    };
+                    if map.insert(names[index].clone(), data_name).is_some() {
+                        return Err(KerasError::Unsupported(format!(
+                            "layer {} used twice as input or output",
+                            keras_name
+                        )));
+                    }
+                }
+                return Ok(map);
+            };
+        let input_layer_to_data_name = data_names(inputs)?;
+        let output_layer_to_data_name = data_names(outputs)?;
+
+        return Ok(ImportedLayer::into_model(
+            import.layers,
+            &names,
+            input_layer_to_data_name,
+            output_layer_to_data_name,
+        ));
+    }
+
+    /**
+     * Each layer reads the previous one, the first one may carry the input shape itself
+     */
+    fn sequential(
+        &mut self,
+        keras_layers: &[Value],
+    ) -> Result<(Vec<String>, Vec<String>), KerasError> {
+        let mut previous: Option<String> = None;
+        let mut input: Option<String> = None;
+        for layer in keras_layers.iter() {
+            if previous.is_none() && Self::class_name(layer) != "InputLayer" {
+                let name = format!("{}_input", Self::layer_name(layer)?);
+                self.input(&name, &layer["config"])?;
+                previous = Some(name);
+            }
+            let parents: Vec<String> = previous.iter().cloned().collect();
+            self.layer(layer, &parents)?;
+            if input.is_none() {
+                input = previous.clone().or(Some(Self::layer_name(layer)?));
+            }
+            previous = Some(Self::layer_name(layer)?);
+        }
+        return match (input, previous) {
+            (Some(input), Some(output)) => Ok((vec![input], vec![output])),
+            _ => Err(KerasError::Invalid(
+                "Sequential model without layers".to_string(),
+            )),
+        };
+    }
+
+    fn layer(&mut self, layer: &Value, parents: &[String]) -> Result<(), KerasError> {
+        let name = Self::layer_name(layer)?;
+        let config = &layer["config"];
+        let class_name = Self::class_name(layer);
+        if class_name == "InputLayer" {
+            return self.input(&name, config);
+        }
+        if class_name == "Concatenate" {
+            return self.concatenate(&name, config, parents);
+        }
+
+        let parent = match parents {
+            [parent] => self.layer_of(parent)?,
+            _ => {
+                return Err(KerasError::Invalid(format!(
+                    "{} {} needs one input, got {}",
+                    class_name,
+                    name,
+                    parents.len()
+                )))
+            }
+        };
+        match class_name {
+            "Dense" => self.dense(&name, config, parent),
+            "Flatten" => self.flatten(&name, parent),
+            "Activation" => match Self::activation(&config["activation"])? {
+                Some(activation) => self.activation_layer(&name, parent, activation),
+                None => {
+                    self.tensors.insert(name, parent);
+                    Ok(())
+                }
+            },
+            "ReLU" => {
+                let cap = config["max_value"].as_f64().map_or(f32::MAX, |c| c as f32);
+                let slope = config["negative_slope"].as_f64().unwrap_or(0.0) as f32;
+                if config["threshold"].as_f64().unwrap_or(0.0) != 0.0 {
+                    return Err(KerasError::Unsupported(format!(
+                        "ReLU {} with a threshold",
+                        name
+                    )));
+                }
+                let activation: Box<dyn Activation> = if slope == 0.0 {
+                    Box::new(ReLu { cap })
+                } else if cap == f32::MAX {
+                    Box::new(LeakyReLu { beta: slope })
+                } else {
+                    return Err(KerasError::Unsupported(format!(
+                        "ReLU {} with both max_value and negative_slope",
+                        name
+                    )));
+                };
+                self.activation_layer(&name, parent, activation)
+            }
+            "LeakyReLU" => {
+                // alpha in keras 2, negative_slope in keras 3
+                let beta = config["negative_slope"]
+                    .as_f64()
+                    .or(config["alpha"].as_f64())
+                    .unwrap_or(0.3) as f32;
+                self.activation_layer(&name, parent, Box::new(LeakyReLu { beta }))
+            }
+            "Softmax" => {
+                Self::check_last_axis(&name, &config["axis"], self.ranks[parent])?;
+                self.activation_layer(&name, parent, Box::new(SoftMax::default()))
+            }
+            other => Err(KerasError::Unsupported(format!(
+                "layer {} of class {}",
+                name, other
+            ))),
+        }
+    }
+
+    /**
+     * [batch, features] or [batch, rows, features], the batch axis becomes repeated rows
+     */
+    fn input(&mut self, name: &str, config: &Value) -> Result<(), KerasError> {
+        let shape = config["batch_shape"]
+            .as_array()
+            .or(config["batch_input_shape"].as_array())
+            .ok_or_else(|| KerasError::Invalid(format!("Input {} without a shape", name)))?;
+        let dim = |d: &Value| {
+            d.as_u64()
+                .map_or(Shape::Variable, |d| Shape::Const(d as usize))
+        };
+        let (features, size) = match shape.as_slice() {
+            [_, features] => (dim(features), Shape::Repeat),
+            [_, rows, features] => (dim(features), dim(rows)),
+            _ => {
+                return Err(KerasError::Unsupported(format!(
+                    "input {} of shape {:?}",
+                    name, shape
+                )))
+            }
+        };
+        let rank = shape.len();
+        self.push(name, ImportedKind::Input, features, size, rank);
+        return Ok(());
+    }
+
+    fn dense(&mut self, name: &str, config: &Value, parent: usize) -> Result<(), KerasError> {
+        let units = config["units"]
+            .as_u64()
+            .ok_or_else(|| KerasError::Invalid(format!("Dense {} without units", name)))?
+            as usize;
+        let kernel = self.weight(name, "kernel")?;
+        let inputs = match self.layers[parent].features {
+            Shape::Const(c) => c,
+            _ => kernel.height,
+        };
+        if (kernel.width, kernel.height) != (units, inputs) {
+            return Err(KerasError::Invalid(format!(
+                "Dense {} kernel is {}x{}, expected {}x{}",
+                name, kernel.height, kernel.width, inputs, units
+            )));
+        }
+        let bias = match config["use_bias"].as_bool().unwrap_or(true) {
+            true => {
+                let bias = self.weight(name, "bias")?;
+                if bias.width * bias.height != units {
+                    return Err(KerasError::Invalid(format!(
+                        "Dense {} bias has {} values, expected {}",
+                        name,
+                        bias.width * bias.height,
+                        units
+                    )));
+                }
+                Some(NDMatrix::from_raw_vec(
+                    units,
+                    1,
+                    bias.iter_all().copied().collect(),
+                ))
+            }
+            false => None,
+        };
+        let weighted = Weighted {
+            weight: kernel.clone(),
+            bias,
+            activation: Self::activation(&config["activation"])?,
+        };
+        let size = self.layers[parent].size.clone();
+        let rank = self.ranks[parent];
+        self.push(
+            name,
+            ImportedKind::Dense(parent, weighted),
+            Shape::Const(units),
+            size,
+            rank,
+        );
+        return Ok(());
+    }
+
+    fn concatenate(
+        &mut self,
+        name: &str,
+        config: &Value,
+        parents: &[String],
+    ) -> Result<(), KerasError> {
+        let parents = parents
+            .iter()
+            .map(|p| self.layer_of(p))
+            .collect::<Result<Vec<usize>, KerasError>>()?;
+        let first = *parents
+            .first()
+            .ok_or_else(|| KerasError::Invalid(format!("Concatenate {} without inputs", name)))?;
+        let rank = self.ranks[first];
+        Self::check_last_axis(name, &config["axis"], rank)?;
+        let size = self.layers[first].size.clone();
+        if parents.iter().any(|p| self.layers[*p].size != size) {
+            return Err(KerasError::Invalid(format!(
+                "Concatenate {} of inputs with different rows",
+                name
+            )));
+        }
+        let features = parents
+            .iter()
+            .map(|p| match self.layers[*p].features {
+                Shape::Const(c) => Some(c),
+                _ => None,
+            })
+            .sum::<Option<usize>>()
+            .map_or(Shape::Variable, Shape::Const);
+        self.push(name, ImportedKind::Concat(parents), features, size, rank);
+        return Ok(());
+    }
+
+    fn flatten(&mut self, name: &str, parent: usize) -> Result<(), KerasError> {
+        if self.ranks[parent] <= 2 {
+            self.tensors.insert(name.to_string(), parent);
+            return Ok(());
+        }
+        let features = match (&self.layers[parent].features, &self.layers[parent].size) {
+            (Shape::Const(x), Shape::Const(y)) => Shape::Const(x * y),
+            _ => Shape::Variable,
+        };
+        self.push(
+            name,
+            ImportedKind::Flatten(parent),
+            features,
+            Shape::Const(1),
+            2,
+        );
+        return Ok(());
+    }
+
+    fn activation_layer(
+        &mut self,
+        name: &str,
+        parent: usize,
+        activation: Box<dyn Activation>,
+    ) -> Result<(), KerasError> {
+        let features = match self.layers[parent].features {
+            Shape::Const(c) => c,
+            _ => {
+                return Err(KerasError::Unsupported(format!(
+                    "activation {} on a variable feature count",
+                    name
+                )))
+            }
+        };
+        let weighted = Weighted {
+            weight: NDMatrix::constant(features, 1, 1.0),
+            bias: None,
+            activation: Some(activation),
+        };
+        let size = self.layers[parent].size.clone();
+        let rank = self.ranks[parent];
+        self.push(
+            name,
+            ImportedKind::Direct(parent, weighted),
+            Shape::Const(features),
+            size,
+            rank,
+        );
+        return Ok(());
+    }
+
+    /**
+     * None for a linear activation. Keras 3 serializes the name inside an object.
+     */
+    fn activation(value: &Value) -> Result<Option<Box<dyn Activation>>, KerasError> {
+        let name = value
+            .as_str()
+            .or(value["config"].as_str())
+            .or(value["config"]["name"].as_str())
+            .unwrap_or("linear");
+        return match name {
+            "linear" => Ok(None),
+            "relu" => Ok(Some(Box::new(ReLu { cap: f32::MAX }))),
+            "relu6" => Ok(Some(Box::new(ReLu { cap: 6.0 }))),
+            "leaky_relu" => Ok(Some(Box::new(LeakyReLu { beta: 0.2 }))),
+            "sigmoid" => Ok(Some(Box::new(Sigmoid))),
+            "tanh" => Ok(Some(Box::new(Tanh))),
+            "softmax" => Ok(Some(Box::new(SoftMax::default()))),
+            other => Err(KerasError::Unsupported(format!("activation {}", other))),
+        };
+    }
+
+    fn check_last_axis(name: &str, axis: &Value, rank: usize) -> Result<(), KerasError> {
+        let axis = axis.as_i64().unwrap_or(-1);
+        if axis != -1 && axis != rank as i64 - 1 {
+            return Err(KerasError::Unsupported(format!(
+                "{} on axis {}, only the feature axis is supported",
+                name, axis
+            )));
+        }
+        return Ok(());
+    }
+
+    /**
+     * Names of the layers feeding the layer, keras 2 lists [name, node, tensor, kwargs]
+     * entries and keras 3 the keras_history of the tensors in the call arguments
+     */
+    fn inbound(layer: &Value) -> Result<Vec<String>, KerasError> {
+        let nodes = layer["inbound_nodes"].as_array().map_or(&[][..], |n| n);
+        let node = match nodes {
+            [] => return Ok(vec![]),
+            [node] => node,
+            _ => {
+                return Err(KerasError::Unsupported(format!(
+                    "shared layer {}",
+                    Self::layer_name(layer)?
+                )))
+            }
+        };
+        let mut names: Vec<String> = vec![];
+        match node.as_array() {
+            Some(entries) => entries
+                .iter()
+                .filter_map(|e| e[0].as_str())
+                .for_each(|n| names.push(n.to_string())),
+            None => Self::keras_history(&node["args"], &mut names),
+        }
+        return Ok(names);
+    }
+
+    fn keras_history(value: &Value, names: &mut Vec<String>) {
+        if let Some(name) = value["config"]["keras_history"][0].as_str() {
+            names.push(name.to_string());
+        } else if let Some(values) = value.as_array() {
+            values.iter().for_each(|v| Self::keras_history(v, names));
+        }
+    }
+
+    /**
+     * [[name, node, tensor], ...], or a single [name, node, tensor] in keras 3
+     */
+    fn io_layers(value: &Value) -> Result<Vec<String>, KerasError> {
+        let entries = value
+            .as_array()
+            .ok_or_else(|| KerasError::Invalid("Model config without io layers".to_string()))?;
+        if let Some(name) = entries.first().and_then(|e| e.as_str()) {
+            return Ok(vec![name.to_string()]);
+        }
+        return Ok(entries
+            .iter()
+            .filter_map(|e| e[0].as_str())
+            .map(|n| n.to_string())
+            .collect());
+    }
+
+    fn weight(&self, layer: &str, param: &str) -> Result<&'w NDMatrix, KerasError> {
+        return self
+            .weights
+            .get(&(layer.to_string(), param.to_string()))
+            .copied()
+            .ok_or_else(|| KerasError::Invalid(format!("Missing weight {}/{}", layer, param)));
+    }
+
+    fn push(&mut self, name: &str, kind: ImportedKind, features: Shape, size: Shape, rank: usize) {
+        self.layers.push(ImportedLayer {
+            kind,
+            features,
+            size,
+            output: name.to_string(),
+        });
+        self.ranks.push(rank);
+        self.tensors.insert(name.to_string(), self.layers.len() - 1);
+    }
+
+    fn layer_of(&self, name: &str) -> Result<usize, KerasError> {
+        return self.tensors.get(name).copied().ok_or_else(|| {
+            KerasError::Invalid(format!("Layer {} is used before it is defined", name))
+        });
+    }
+
+    fn layer_name(layer: &Value) -> Result<String, KerasError> {
+        return layer["config"]["name"]
+            .as_str()
+            .or(layer["name"].as_str())
+            .map(|n| n.to_string())
+            .ok_or_else(|| KerasError::Invalid("Layer without a name".to_string()));
+    }
+
+    fn class_name(layer: &Value) -> &str {
+        return layer["class_name"].as_str().unwrap_or("");
+    }
+}
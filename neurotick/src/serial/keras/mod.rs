@@ -0,0 +1,2 @@
+pub mod keras_error;
+pub mod keras_import;
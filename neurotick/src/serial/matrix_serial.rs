@@ -1,15 +1,346 @@
+use std::{
+    cell::Cell,
+    fmt::Display,
+    io::{Read, Write},
+};
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MatrixPack {
     pub width: usize,
     pub height: usize,
-    pub data: String,
+    pub data: PackData,
+    /**
+     * crc32 of the packed bytes, missing in packs written before checksums and in text packs
+     */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<u32>,
+    /**
+     * Storage of the values in data, f32 when missing
+     */
+    #[serde(default, skip_serializing_if = "MatrixEncoding::is_f32")]
+    pub encoding: MatrixEncoding,
+}
+
+/**
+ * Packed values, base64 of the encoded bytes or rows of numbers for MatrixEncoding::Text
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum PackData {
+    Encoded(String),
+    Rows(#[serde(with = "text_rows")] Vec<Vec<f64>>),
+}
+
+/**
+ * Json numbers can't be NaN or infinite, text rows write those as "NaN", "inf" and "-inf"
+ */
+mod text_rows {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum TextNumber {
+        Number(f64),
+        Token(String),
+    }
+
+    impl TextNumber {
+        fn from_value(value: f64) -> TextNumber {
+            if value.is_finite() {
+                return TextNumber::Number(value);
+            }
+            let token = match value {
+                v if v.is_nan() => "NaN",
+                v if v > 0.0 => "inf",
+                _ => "-inf",
+            };
+            return TextNumber::Token(token.to_string());
+        }
+
+        fn to_value(&self) -> Result<f64, String> {
+            return match self {
+                TextNumber::Number(value) => Ok(*value),
+                TextNumber::Token(token) => match token.as_str() {
+                    "NaN" => Ok(f64::NAN),
+                    "inf" => Ok(f64::INFINITY),
+                    "-inf" => Ok(f64::NEG_INFINITY),
+                    _ => Err(format!("Unknown text value {}", token)),
+                },
+            };
+        }
+    }
+
+    #[allow(clippy::ptr_arg)]
+    pub fn serialize<S: Serializer>(
+        rows: &Vec<Vec<f64>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        return serializer.collect_seq(rows.iter().map(|row| {
+            return row
+                .iter()
+                .map(|v| TextNumber::from_value(*v))
+                .collect::<Vec<_>>();
+        }));
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<f64>>, D::Error> {
+        let rows: Vec<Vec<TextNumber>> = Vec::deserialize(deserializer)?;
+        return rows
+            .iter()
+            .map(|row| {
+                return row
+                    .iter()
+                    .map(|number| number.to_value().map_err(D::Error::custom))
+                    .collect();
+            })
+            .collect();
+    }
+}
+
+/**
+ * Storage encodings of packed values, all big-endian. The reduced precisions are lossy and
+ * the compressed ones store f32, values are f32 again once unpacked.
+ *
+ * Text writes the values as nested json arrays, one per row, so changes between two
+ * documents show up in a diff. It is rounded to the decimals of EncodingScope::text, and
+ * written in full f32 precision otherwise. NaN and infinities are the strings "NaN", "inf"
+ * and "-inf".
+ */
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MatrixEncoding {
+    #[default]
+    F32,
+    F16,
+    Bf16,
+    Zstd,
+    Deflate,
+    Text,
+}
+
+#[derive(Debug)]
+pub enum MatrixPackError {
+    Encoding(String),
+    Length { expected: usize, found: usize },
+    Checksum { expected: u32, found: u32 },
 }
 
 pub trait MatrixSerial<T: Sized> {
-    fn pack(&self) -> MatrixPack;
-    fn unpack(pack: &MatrixPack) -> T
+    /**
+     * Packs with the encoding of the current EncodingScope, f32 outside of one
+     */
+    fn pack(&self) -> MatrixPack {
+        return self.pack_as(MatrixEncoding::current());
+    }
+    fn pack_as(&self, encoding: MatrixEncoding) -> MatrixPack;
+    fn unpack(pack: &MatrixPack) -> Result<T, MatrixPackError>
     where
         Self: Sized;
 }
+
+thread_local! {
+    static PACK_ENCODING: Cell<(MatrixEncoding, Option<u32>)> =
+        const { Cell::new((MatrixEncoding::F32, None)) };
+}
+
+/**
+ * Sets the encoding of MatrixSerial::pack for the current thread, e.g. while a model is
+ * serialized, until the scope is dropped
+ */
+pub struct EncodingScope {
+    previous: (MatrixEncoding, Option<u32>),
+}
+
+impl EncodingScope {
+    pub fn open(encoding: MatrixEncoding) -> EncodingScope {
+        return EncodingScope {
+            previous: PACK_ENCODING.with(|current| current.replace((encoding, None))),
+        };
+    }
+
+    /**
+     * MatrixEncoding::Text with values rounded to the given decimals
+     */
+    pub fn text(decimals: u32) -> EncodingScope {
+        return EncodingScope {
+            previous: PACK_ENCODING
+                .with(|current| current.replace((MatrixEncoding::Text, Some(decimals)))),
+        };
+    }
+}
+
+impl Drop for EncodingScope {
+    fn drop(&mut self) {
+        PACK_ENCODING.with(|current| current.set(self.previous));
+    }
+}
+
+impl MatrixEncoding {
+    const ZSTD_LEVEL: i32 = 3;
+
+    pub fn current() -> MatrixEncoding {
+        return PACK_ENCODING.with(|current| current.get().0);
+    }
+
+    /**
+     * Decimals of MatrixEncoding::Text in the current EncodingScope, None for full precision
+     */
+    pub fn text_decimals() -> Option<u32> {
+        return PACK_ENCODING.with(|current| current.get().1);
+    }
+
+    pub fn is_f32(&self) -> bool {
+        return *self == MatrixEncoding::F32;
+    }
+
+    /**
+     * Bytes of the binary encodings, Text packs rows of numbers instead
+     */
+    pub fn encode(&self, values: &[f32]) -> Vec<u8> {
+        return match self {
+            MatrixEncoding::F16 => values
+                .iter()
+                .flat_map(|f| half::f16::from_f32(*f).to_be_bytes())
+                .collect(),
+            MatrixEncoding::Bf16 => values
+                .iter()
+                .flat_map(|f| half::bf16::from_f32(*f).to_be_bytes())
+                .collect(),
+            MatrixEncoding::F32 | MatrixEncoding::Text => Self::f32_bytes(values),
+            MatrixEncoding::Zstd => {
+                zstd::bulk::compress(&Self::f32_bytes(values), Self::ZSTD_LEVEL).unwrap()
+            }
+            MatrixEncoding::Deflate => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&Self::f32_bytes(values)).unwrap();
+                encoder.finish().unwrap()
+            }
+        };
+    }
+
+    /**
+     * Decodes exactly len values, compressed data may not inflate beyond them
+     */
+    pub fn decode(&self, bytes: &[u8], len: usize) -> Result<Vec<f32>, MatrixPackError> {
+        let size = self.value_size();
+        let expected = len
+            .checked_mul(size)
+            .ok_or_else(|| MatrixPackError::Encoding(format!("{} values overflow", len)))?;
+        let raw = match self {
+            MatrixEncoding::F32
+            | MatrixEncoding::F16
+            | MatrixEncoding::Bf16
+            | MatrixEncoding::Text => bytes.to_vec(),
+            MatrixEncoding::Zstd => {
+                let decoder = zstd::stream::read::Decoder::with_buffer(bytes)
+                    .map_err(|e| MatrixPackError::Encoding(e.to_string()))?;
+                Self::inflate(decoder, expected)?
+            }
+            MatrixEncoding::Deflate => {
+                Self::inflate(flate2::read::DeflateDecoder::new(bytes), expected)?
+            }
+        };
+        if raw.len() != expected {
+            return Err(MatrixPackError::Length {
+                expected,
+                found: raw.len(),
+            });
+        }
+        let values = raw.chunks_exact(size).map(|be| match self {
+            MatrixEncoding::F16 => half::f16::from_be_bytes([be[0], be[1]]).to_f32(),
+            MatrixEncoding::Bf16 => half::bf16::from_be_bytes([be[0], be[1]]).to_f32(),
+            _ => f32::from_be_bytes(be.try_into().unwrap()),
+        });
+        return Ok(values.collect());
+    }
+
+    /**
+     * A value as written by MatrixEncoding::Text. Without decimals it goes through its
+     * shortest decimal form, which reads back as the same f32, so the json doesn't show the
+     * digits of widening it to f64.
+     */
+    pub fn text_value(value: f32, decimals: Option<u32>) -> f64 {
+        return match decimals {
+            Some(decimals) => {
+                let scale = 10f64.powi(decimals as i32);
+                (value as f64 * scale).round() / scale
+            }
+            None => value.to_string().parse().unwrap_or(value as f64),
+        };
+    }
+
+    /**
+     * Values of text rows, which must be height rows of width values
+     */
+    pub fn from_rows(
+        rows: &[Vec<f64>],
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<f32>, MatrixPackError> {
+        if rows.len() != height {
+            return Err(MatrixPackError::Encoding(format!(
+                "{} rows, expected {}",
+                rows.len(),
+                height
+            )));
+        }
+        if let Some(row) = rows.iter().find(|row| row.len() != width) {
+            return Err(MatrixPackError::Encoding(format!(
+                "row of {} values, expected {}",
+                row.len(),
+                width
+            )));
+        }
+        return Ok(rows.iter().flatten().map(|v| *v as f32).collect());
+    }
+
+    /**
+     * Reads at most one byte past the expected length, growing with the data read instead
+     * of allocating the length the pack claims
+     */
+    fn inflate<R: Read>(decoder: R, expected: usize) -> Result<Vec<u8>, MatrixPackError> {
+        let mut raw = vec![];
+        decoder
+            .take(expected as u64 + 1)
+            .read_to_end(&mut raw)
+            .map_err(|e| MatrixPackError::Encoding(e.to_string()))?;
+        return Ok(raw);
+    }
+
+    /**
+     * Bytes per value once decompressed
+     */
+    fn value_size(&self) -> usize {
+        return match self {
+            MatrixEncoding::F16 | MatrixEncoding::Bf16 => 2,
+            _ => 4,
+        };
+    }
+
+    fn f32_bytes(values: &[f32]) -> Vec<u8> {
+        return values.iter().flat_map(|f| f.to_be_bytes()).collect();
+    }
+}
+
+impl Display for MatrixPackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatrixPackError::Encoding(e) => write!(f, "Unreadable matrix data: {}", e),
+            MatrixPackError::Length { expected, found } => {
+                write!(f, "Matrix data has {} bytes, expected {}", found, expected)
+            }
+            MatrixPackError::Checksum { expected, found } => write!(
+                f,
+                "Matrix checksum {:08x} does not match {:08x}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MatrixPackError {}
@@ -1,5 +1,16 @@
+pub mod binary_serial;
+mod imported_graph;
+pub mod integrity;
+pub mod keras;
 pub mod matrix_serial;
 pub mod model_reader;
 pub mod model_serial;
+pub mod model_stream;
+pub mod model_version;
+pub mod model_weights;
+pub mod npy_serial;
+pub mod onnx;
+pub mod registry;
+pub mod safetensors_serial;
 mod tests;
 pub mod weight_serial;
@@ -1,25 +1,57 @@
+use std::{fmt::Display, io::Read};
+
+use serde_json::Value;
+
 use crate::{
-    activation::{
-        abs::{Activation, ActivationVirtual},
-        lerelu::LeakyReLu,
-        none::NoneAct,
-        relu::ReLu,
-        sigmoid::Sigmoid,
-        softmax::SoftMax,
-        tanh::Tanh,
-    },
-    layer::{
-        abs::{LayerBase, LayerPropagateEnum},
-        concat::{Concat, ConcatImpl},
-        dense::{Dense, DenseImpl},
-        input::{Input, InputImpl},
-    },
+    activation::abs::Activation,
+    layer::abs::LayerPropagateEnum,
+    model::model::Model,
     utils::{injector::GenericInjector, json_wrap::JsonWrap},
 };
 
+use super::{
+    integrity::{IntegrityError, ModelIntegrity},
+    matrix_serial::MatrixPackError,
+    model_serial::ModelSerialized,
+    model_stream::ModelStream,
+    model_version::ModelMigrations,
+    registry::{ActivationRegistration, LayerRegistration},
+};
+
 pub struct ModelReader {
     activation_injector: GenericInjector<dyn Activation, JsonWrap, ModelReader>,
     layer_injector: GenericInjector<LayerPropagateEnum, JsonWrap, ModelReader>,
+    migrations: ModelMigrations,
+    signing_key: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub enum ModelReadError {
+    /**
+     * The document was written by a newer library
+     */
+    Newer {
+        found: u32,
+        supported: u32,
+    },
+    MissingMigration(u32),
+    /**
+     * The version field isn't an integer that fits a u32
+     */
+    InvalidVersion(String),
+    Migration {
+        from: u32,
+        message: String,
+    },
+    Integrity(IntegrityError),
+    /**
+     * Matrix of a layer meta that doesn't unpack, named "{layer}.{field}"
+     */
+    Tensor {
+        name: String,
+        error: MatrixPackError,
+    },
+    Json(serde_json::Error),
 }
 
 impl ModelReader {
@@ -27,6 +59,8 @@ impl ModelReader {
         ModelReader {
             activation_injector: GenericInjector::default_activation(),
             layer_injector: GenericInjector::default_layer(),
+            migrations: ModelMigrations::default(),
+            signing_key: None,
         }
     }
 
@@ -37,39 +71,164 @@ impl ModelReader {
     pub fn get_layer_di(&self) -> &GenericInjector<LayerPropagateEnum, JsonWrap, ModelReader> {
         return &self.layer_injector;
     }
+
+    /**
+     * Registers or overrides an activation by name
+     */
+    pub fn register_activation<F: 'static>(&mut self, name: &str, call: F)
+    where
+        F: Fn(&JsonWrap, &ModelReader) -> Box<dyn Activation>,
+    {
+        self.activation_injector.register(name, call);
+    }
+
+    /**
+     * Registers or overrides a layer implementation by name
+     */
+    pub fn register_layer<F: 'static>(&mut self, name: &str, call: F)
+    where
+        F: Fn(&JsonWrap, &ModelReader) -> Box<LayerPropagateEnum>,
+    {
+        self.layer_injector.register(name, call);
+    }
+
+    /**
+     * Registers or overrides the upgrade of documents from the given format version to the
+     * next one, e.g. to rename a field of a layer meta
+     */
+    pub fn register_migration<F: 'static>(&mut self, from_version: u32, step: F)
+    where
+        F: Fn(Value) -> Result<Value, String>,
+    {
+        self.migrations.register(from_version, step);
+    }
+
+    /**
+     * Registers a format version whose documents read as the next one, see
+     * ModelMigrations::register_unchanged
+     */
+    pub fn register_unchanged(&mut self, from_version: u32) {
+        self.migrations.register_unchanged(from_version);
+    }
+
+    pub fn migrations(&self) -> &ModelMigrations {
+        return &self.migrations;
+    }
+
+    pub fn signing_key(&self) -> Option<&[u8]> {
+        return self.signing_key.as_deref();
+    }
+
+    /**
+     * Refuses documents that aren't signed with this key, see ModelSerialized::to_json_signed
+     */
+    pub fn require_signature(&mut self, key: &[u8]) {
+        self.signing_key = Some(key.to_vec());
+    }
+
+    /**
+     * Parses a model document, checks its integrity and upgrades it to
+     * ModelSerialized::FORMAT_VERSION
+     */
+    pub fn read_json(&self, json: &str) -> Result<ModelSerialized, ModelReadError> {
+        return self.read_value(serde_json::from_str(json)?);
+    }
+
+    pub fn read_value(&self, mut document: Value) -> Result<ModelSerialized, ModelReadError> {
+        ModelIntegrity::verify(&mut document, self.signing_key.as_deref())
+            .map_err(ModelReadError::Integrity)?;
+        return self.read_verified(document);
+    }
+
+    /**
+     * Upgrades a document whose integrity was checked by its container, see BinaryModel
+     */
+    pub(crate) fn read_verified(&self, document: Value) -> Result<ModelSerialized, ModelReadError> {
+        let migrated = self.migrations.migrate(document)?;
+        let serialized: ModelSerialized = serde_json::from_value(migrated)?;
+        serialized.verify_tensors()?;
+        return Ok(serialized);
+    }
+
+    /**
+     * Builds the model while parsing, without holding the document in memory, see
+     * ModelStream for the documents it accepts
+     */
+    pub fn read_stream<R: Read>(&self, read: R) -> Result<Model, ModelReadError> {
+        return ModelStream::read(read, self);
+    }
+}
+
+impl Display for ModelReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelReadError::Newer { found, supported } => write!(
+                f,
+                "Model format version {} is newer than the supported version {}",
+                found, supported
+            ),
+            ModelReadError::MissingMigration(from) => {
+                write!(f, "No migration from model format version {}", from)
+            }
+            ModelReadError::InvalidVersion(found) => {
+                write!(f, "Invalid model format version: {}", found)
+            }
+            ModelReadError::Migration { from, message } => write!(
+                f,
+                "Migration from model format version {} failed: {}",
+                from, message
+            ),
+            ModelReadError::Integrity(e) => write!(f, "{}", e),
+            ModelReadError::Tensor { name, error } => write!(f, "Tensor {}: {}", name, error),
+            ModelReadError::Json(e) => write!(f, "Model json error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ModelReadError {}
+
+impl From<serde_json::Error> for ModelReadError {
+    fn from(value: serde_json::Error) -> Self {
+        return ModelReadError::Json(value);
+    }
+}
+
+impl From<ModelReadError> for std::io::Error {
+    fn from(value: ModelReadError) -> Self {
+        return std::io::Error::new(std::io::ErrorKind::InvalidData, value);
+    }
 }
 
 impl GenericInjector<dyn Activation, JsonWrap, ModelReader> {
+    /**
+     * Every activation submitted as an ActivationRegistration
+     */
     pub fn default_activation() -> GenericInjector<dyn Activation, JsonWrap, ModelReader> {
         let mut injector: GenericInjector<dyn Activation, JsonWrap, ModelReader> =
             GenericInjector::new();
 
-        injector.register(NoneAct::NAME, |_, _| Box::new(NoneAct::default()));
-        injector.register(ReLu::NAME, |json, _| ReLu::from_json(&json));
-        injector.register(LeakyReLu::NAME, |json, _| LeakyReLu::from_json(&json));
-        injector.register(SoftMax::NAME, |_, _| Box::new(SoftMax::default()));
-        injector.register(Sigmoid::NAME, |_, _| Box::new(Sigmoid::default()));
-        injector.register(Tanh::NAME, |_, _| Box::new(Tanh::default()));
+        for registration in inventory::iter::<ActivationRegistration> {
+            let create = registration.create;
+            injector.register(registration.name, move |json, _| create(json));
+        }
         return injector;
     }
 }
 
 impl GenericInjector<LayerPropagateEnum, JsonWrap, ModelReader> {
+    /**
+     * Every layer submitted as a LayerRegistration
+     */
     pub fn default_layer() -> GenericInjector<LayerPropagateEnum, JsonWrap, ModelReader> {
         let mut injector: GenericInjector<LayerPropagateEnum, JsonWrap, ModelReader> =
             GenericInjector::new();
 
-        injector.register(Input::NAME, |json, reader| {
-            Box::new(InputImpl::create_from_ser(json, reader))
-        });
-
-        injector.register(Dense::NAME, |json, reader| {
-            Box::new(DenseImpl::create_from_ser(json, reader))
-        });
-
-        injector.register(Concat::NAME, |json, reader| {
-            Box::new(ConcatImpl::create_from_ser(json, reader))
-        });
+        for registration in inventory::iter::<LayerRegistration> {
+            let create = registration.create;
+            injector.register(registration.name, move |json, reader| {
+                Box::new(create(json, reader))
+            });
+        }
         return injector;
     }
 }
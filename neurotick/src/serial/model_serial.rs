@@ -1,14 +1,21 @@
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::{
     builder::graph_elements::{BuilderNode, ModelPropagationNode},
-    layer::abs::LayerPropagateEnum,
+    layer::abs::{LayerPropagateEnum, Mode},
+    matrix::nmatrix::NDMatrix,
     model::model::Model,
     utils::json_wrap::JsonWrap,
 };
 
-use super::model_reader::ModelReader;
+use super::{
+    integrity::ModelIntegrity,
+    matrix_serial::{MatrixPack, MatrixSerial},
+    model_reader::{ModelReadError, ModelReader},
+    model_weights::ModelWeights,
+};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct ModelGraph {
@@ -29,6 +36,11 @@ pub struct ModelMeta {
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct ModelSerialized {
+    /**
+     * Format version of the document, missing in files written before versioning
+     */
+    #[serde(default)]
+    pub version: u32,
     pub io: ModelIO,
     pub graph: ModelGraph,
     #[serde(flatten)]
@@ -36,11 +48,57 @@ pub struct ModelSerialized {
 }
 
 impl ModelSerialized {
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /**
+     * Document with its checksum, see ModelIntegrity
+     */
     pub fn to_json(&self) -> String {
-        return serde_json::to_string(self).unwrap();
+        return self.sealed(None).to_string();
     }
     pub fn to_json_pretty(&self) -> String {
-        return serde_json::to_string_pretty(self).unwrap();
+        return serde_json::to_string_pretty(&self.sealed(None)).unwrap();
+    }
+
+    /**
+     * Document with its checksum and a hmac-sha256 signature, for readers with
+     * ModelReader::require_signature
+     */
+    pub fn to_json_signed(&self, key: &[u8]) -> String {
+        return self.sealed(Some(key)).to_string();
+    }
+
+    pub fn sealed(&self, key: Option<&[u8]>) -> Value {
+        let mut document = serde_json::to_value(self).unwrap();
+        ModelIntegrity::seal(&mut document, key);
+        return document;
+    }
+
+    /**
+     * Unpacks every matrix of the layer metas, so damaged tensors are reported before the
+     * layers are created
+     */
+    pub fn verify_tensors(&self) -> Result<(), ModelReadError> {
+        for (layer, meta) in self.meta.meta.iter() {
+            Self::verify_layer_tensors(layer, meta)?;
+        }
+        return Ok(());
+    }
+
+    pub fn verify_layer_tensors(layer: &str, meta: &JsonWrap) -> Result<(), ModelReadError> {
+        let Some(fields) = meta.as_value().as_object() else {
+            return Ok(());
+        };
+        for (field, value) in fields.iter() {
+            let Ok(pack) = MatrixPack::deserialize(value) else {
+                continue;
+            };
+            NDMatrix::unpack(&pack).map_err(|error| ModelReadError::Tensor {
+                name: ModelWeights::name(layer, field),
+                error,
+            })?;
+        }
+        return Ok(());
     }
 
     pub fn build_model(&self, reader: &ModelReader) -> Model {
@@ -57,32 +115,8 @@ impl ModelSerialized {
                 let prop_enum: LayerPropagateEnum =
                     *reader.get_layer_di().create(&type_name, meta.1, reader);
 
-                let node_num: ModelPropagationNode = match parent_type_descriptor {
-                    BuilderNode::DeadEnd(_) => {
-                        let cast = if let LayerPropagateEnum::SingleInput(single) = prop_enum {
-                            single
-                        } else {
-                            panic!("Not a dead end builder node")
-                        };
-                        ModelPropagationNode::DeadEnd(cast)
-                    }
-                    BuilderNode::SingleParent(c) => {
-                        let cast = if let LayerPropagateEnum::SingleInput(single) = prop_enum {
-                            single
-                        } else {
-                            panic!("Not a single input builder node")
-                        };
-                        ModelPropagationNode::SingleInput(c.parent_name.clone(), cast)
-                    }
-                    BuilderNode::MultipleParent(c) => {
-                        let cast = if let LayerPropagateEnum::MultipleInput(single) = prop_enum {
-                            single
-                        } else {
-                            panic!("Not a multi input builder node")
-                        };
-                        ModelPropagationNode::MultipleInput(c.parent_names.clone(), cast)
-                    }
-                };
+                let node_num =
+                    ModelPropagationNode::from_builder(parent_type_descriptor, prop_enum);
 
                 return (layer_name, node_num);
             })
@@ -93,6 +127,7 @@ impl ModelSerialized {
             output_layer_to_data_name: self.io.outputs.clone(),
             sequential_prop: node_meta_graph,
             builder_ref: self.graph.graph.clone(),
+            mode: Mode::default(),
         }
     }
 }
@@ -0,0 +1,258 @@
+use std::{fmt, io::Read};
+
+use indexmap::IndexMap;
+use serde::de::{DeserializeSeed, Deserializer, Error, MapAccess, Visitor};
+use serde_json::Value;
+
+use crate::{
+    builder::graph_elements::ModelPropagationNode, layer::abs::Mode, model::model::Model,
+    utils::json_wrap::JsonWrap,
+};
+
+use super::{
+    integrity::{IntegrityHasher, ModelIntegrity},
+    model_reader::{ModelReadError, ModelReader},
+    model_serial::{ModelGraph, ModelIO, ModelSerialized},
+    model_version::ModelMigrations,
+};
+
+/**
+ * Reads a json model from a byte stream without holding the whole document: every layer is
+ * created as soon as its meta entry is parsed and only that entry is kept as json meanwhile.
+ * The checksum is computed along the way and the layers are dropped if it or the signature
+ * don't match, so the result is the same as ModelReader::read_json.
+ *
+ * Migrations work on whole documents, so only documents at the current format version or
+ * registered as unchanged since (see ModelMigrations::register_unchanged) can be streamed.
+ */
+pub struct ModelStream<'a> {
+    reader: &'a ModelReader,
+    hasher: IntegrityHasher,
+    entries: usize,
+    version: Option<u32>,
+    io: Option<ModelIO>,
+    graph: Option<ModelGraph>,
+    layers: IndexMap<String, ModelPropagationNode>,
+    /**
+     * Metas that came before the graph, created once it is known
+     */
+    pending: Vec<(String, JsonWrap)>,
+    integrity: Option<Value>,
+    /**
+     * Failure behind the serde error that stopped the parser
+     */
+    error: Option<ModelReadError>,
+}
+
+struct DocumentVisitor<'s, 'a>(&'s mut ModelStream<'a>);
+
+struct MetaSeed<'s, 'a>(&'s mut ModelStream<'a>);
+
+impl<'a> ModelStream<'a> {
+    pub fn read<R: Read>(read: R, reader: &'a ModelReader) -> Result<Model, ModelReadError> {
+        let mut stream = ModelStream {
+            reader,
+            hasher: IntegrityHasher::new(reader.signing_key()),
+            entries: 0,
+            version: None,
+            io: None,
+            graph: None,
+            layers: IndexMap::new(),
+            pending: vec![],
+            integrity: None,
+            error: None,
+        };
+        let mut deserializer = serde_json::Deserializer::from_reader(read);
+        let parsed = deserializer
+            .deserialize_map(DocumentVisitor(&mut stream))
+            .and_then(|_| deserializer.end());
+        if let Err(e) = parsed {
+            return Err(stream.error.take().unwrap_or(ModelReadError::Json(e)));
+        }
+        return stream.finish();
+    }
+
+    fn finish(self) -> Result<Model, ModelReadError> {
+        // documents before versioning have no version field
+        self.check_version(self.version.unwrap_or(0))?;
+        let io = self
+            .io
+            .ok_or_else(|| serde_json::Error::missing_field("io"))?;
+        let graph = self
+            .graph
+            .ok_or_else(|| serde_json::Error::missing_field("graph"))?;
+        self.hasher
+            .check(self.integrity)
+            .map_err(ModelReadError::Integrity)?;
+        return Ok(Model {
+            input_layer_to_data_name: io.inputs,
+            output_layer_to_data_name: io.outputs,
+            sequential_prop: self.layers,
+            builder_ref: graph.graph,
+            mode: Mode::default(),
+        });
+    }
+
+    /**
+     * Creates the layer of a meta once the graph is known, bad metas are an error instead of
+     * a panic in the layer
+     */
+    fn add_layer(&mut self, name: String, meta: JsonWrap) -> Result<(), ModelReadError> {
+        let Some(graph) = self.graph.as_ref() else {
+            self.pending.push((name, meta));
+            return Ok(());
+        };
+        ModelSerialized::verify_layer_tensors(&name, &meta)?;
+        let node = graph.graph.get(&name).ok_or_else(|| {
+            serde_json::Error::custom(format!("Layer {} is not in the graph", name))
+        })?;
+        let type_name = node.type_name();
+        if !self.reader.get_layer_di().contains(&type_name) {
+            return Err(serde_json::Error::custom(format!(
+                "Layer {} has the unregistered type {}",
+                name, type_name
+            ))
+            .into());
+        }
+        let layer = *self
+            .reader
+            .get_layer_di()
+            .create(&type_name, &meta, self.reader);
+        self.layers
+            .insert(name, ModelPropagationNode::from_builder(node, layer));
+        return Ok(());
+    }
+
+    fn check_version(&self, version: u32) -> Result<(), ModelReadError> {
+        let supported = ModelSerialized::FORMAT_VERSION;
+        if version > supported {
+            return Err(ModelReadError::Newer {
+                found: version,
+                supported,
+            });
+        }
+        if !self.reader.migrations().is_unchanged(version) {
+            return Err(ModelReadError::Migration {
+                from: version,
+                message: "the document needs migrating, read it with read_json".to_string(),
+            });
+        }
+        return Ok(());
+    }
+
+    /**
+     * Writes the separator and key of a top level entry to the checksum
+     */
+    fn hash_key(&mut self, key: &str) {
+        if self.entries > 0 {
+            self.hash_raw(b",");
+        }
+        self.entries += 1;
+        self.hash_value(&key);
+        self.hash_raw(b":");
+    }
+
+    fn hash_value<T: serde::Serialize + ?Sized>(&mut self, value: &T) {
+        serde_json::to_writer(&mut self.hasher, value).unwrap();
+    }
+
+    fn hash_raw(&mut self, bytes: &[u8]) {
+        std::io::Write::write_all(&mut self.hasher, bytes).unwrap();
+    }
+
+    /**
+     * Keeps the failure and stops the parser
+     */
+    fn fail<E: Error>(&mut self, error: ModelReadError) -> E {
+        let message = error.to_string();
+        self.error = Some(error);
+        return E::custom(message);
+    }
+}
+
+impl<'de, 's, 'a> Visitor<'de> for DocumentVisitor<'s, 'a> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a model document")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let stream = self.0;
+        stream.hash_raw(b"{");
+        while let Some(key) = map.next_key::<String>()? {
+            if key == ModelIntegrity::INTEGRITY_KEY {
+                stream.integrity = Some(map.next_value()?);
+                continue;
+            }
+            stream.hash_key(&key);
+            if key == "meta" {
+                map.next_value_seed(MetaSeed(stream))?;
+                continue;
+            }
+            let value: Value = map.next_value()?;
+            stream.hash_value(&value);
+            match key.as_str() {
+                ModelMigrations::VERSION_KEY => {
+                    let version = match ModelMigrations::parse_version(&value) {
+                        Ok(version) => version,
+                        Err(e) => return Err(stream.fail(e)),
+                    };
+                    if let Err(e) = stream.check_version(version) {
+                        return Err(stream.fail(e));
+                    }
+                    stream.version = Some(version);
+                }
+                "io" => stream.io = Some(serde_json::from_value(value).map_err(A::Error::custom)?),
+                "graph" => {
+                    stream.graph = Some(serde_json::from_value(value).map_err(A::Error::custom)?);
+                    for (name, meta) in std::mem::take(&mut stream.pending) {
+                        if let Err(e) = stream.add_layer(name, meta) {
+                            return Err(stream.fail(e));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        stream.hash_raw(b"}");
+        return Ok(());
+    }
+}
+
+impl<'de, 's, 'a> DeserializeSeed<'de> for MetaSeed<'s, 'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        return deserializer.deserialize_map(self);
+    }
+}
+
+impl<'de, 's, 'a> Visitor<'de> for MetaSeed<'s, 'a> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("layer metas by name")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let stream = self.0;
+        stream.hash_raw(b"{");
+        let mut first = true;
+        while let Some(name) = map.next_key::<String>()? {
+            if !first {
+                stream.hash_raw(b",");
+            }
+            first = false;
+            let meta: Value = map.next_value()?;
+            stream.hash_value(&name);
+            stream.hash_raw(b":");
+            stream.hash_value(&meta);
+            if let Err(e) = stream.add_layer(name, JsonWrap::from_value(meta)) {
+                return Err(stream.fail(e));
+            }
+        }
+        stream.hash_raw(b"}");
+        return Ok(());
+    }
+}
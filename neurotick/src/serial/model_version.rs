@@ -0,0 +1,115 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use super::{model_reader::ModelReadError, model_serial::ModelSerialized};
+
+/**
+ * Upgrades of serialized model documents, one step per format version. A step receives the
+ * raw json of a document at its version and returns it at the next version, the version
+ * field itself is updated by ModelMigrations.
+ */
+pub struct ModelMigrations {
+    steps: HashMap<u32, Box<dyn Fn(Value) -> Result<Value, String>>>,
+    /**
+     * Versions with the same layout as the next one, see register_unchanged
+     */
+    unchanged: HashSet<u32>,
+}
+
+impl ModelMigrations {
+    pub const VERSION_KEY: &str = "version";
+
+    pub fn new() -> ModelMigrations {
+        return ModelMigrations {
+            steps: HashMap::new(),
+            unchanged: HashSet::new(),
+        };
+    }
+
+    /**
+     * Built-in steps up to ModelSerialized::FORMAT_VERSION
+     */
+    pub fn default() -> ModelMigrations {
+        let mut migrations = ModelMigrations::new();
+        // documents before versioning have the same layout as version 1
+        migrations.register_unchanged(0);
+        return migrations;
+    }
+
+    /**
+     * Registers or overrides the step from the given version to the next one
+     */
+    pub fn register<F: 'static>(&mut self, from_version: u32, step: F)
+    where
+        F: Fn(Value) -> Result<Value, String>,
+    {
+        self.steps.insert(from_version, Box::new(step));
+        self.unchanged.remove(&from_version);
+    }
+
+    /**
+     * Registers a version whose documents read as the next one without changes, these can
+     * also be streamed, see ModelReader::read_stream
+     */
+    pub fn register_unchanged(&mut self, from_version: u32) {
+        self.register(from_version, Ok);
+        self.unchanged.insert(from_version);
+    }
+
+    /**
+     * Whether documents of the version read as the current one without migrating
+     */
+    pub fn is_unchanged(&self, version: u32) -> bool {
+        return (version..ModelSerialized::FORMAT_VERSION).all(|v| self.unchanged.contains(&v));
+    }
+
+    /**
+     * Format version of a document, 0 for documents written before versioning
+     */
+    pub fn version_of(document: &Value) -> Result<u32, ModelReadError> {
+        return match document.get(Self::VERSION_KEY) {
+            Some(value) => Self::parse_version(value),
+            None => Ok(0),
+        };
+    }
+
+    /**
+     * Value of the version field, which has to be an integer that fits a u32
+     */
+    pub fn parse_version(value: &Value) -> Result<u32, ModelReadError> {
+        return value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| ModelReadError::InvalidVersion(value.to_string()));
+    }
+
+    /**
+     * Applies every step from the document version up to the current one
+     */
+    pub fn migrate(&self, mut document: Value) -> Result<Value, ModelReadError> {
+        let supported = ModelSerialized::FORMAT_VERSION;
+        let mut version = Self::version_of(&document)?;
+        if version > supported {
+            return Err(ModelReadError::Newer {
+                found: version,
+                supported,
+            });
+        }
+        while version < supported {
+            let step = self
+                .steps
+                .get(&version)
+                .ok_or(ModelReadError::MissingMigration(version))?;
+            document = step(document).map_err(|message| ModelReadError::Migration {
+                from: version,
+                message,
+            })?;
+            version += 1;
+            if let Some(object) = document.as_object_mut() {
+                object.insert(Self::VERSION_KEY.to_string(), Value::from(version));
+            }
+        }
+        return Ok(document);
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    fmt::Display,
+    io::{Error, ErrorKind},
+};
+
+use indexmap::IndexMap;
+use serde_json::{Map, Value};
+
+use crate::{
+    builder::graph_elements::ModelPropagationNode, matrix::nmatrix::NDMatrix, model::model::Model,
+    utils::json_wrap::JsonWrap,
+};
+
+use super::model_reader::ModelReader;
+
+/**
+ * Parameter matrices of a model by name. Every matrix field of a layer's serialized meta is
+ * a parameter named "{layer}.{field}", e.g. "Dense_1.weight" and "Dense_1.bias", in
+ * propagation order. Custom layers take part as long as their meta keeps matrices as fields.
+ */
+pub struct ModelWeights;
+
+impl ModelWeights {
+    pub fn collect(model: &Model) -> IndexMap<String, NDMatrix> {
+        let mut weights: IndexMap<String, NDMatrix> = IndexMap::new();
+        for (layer, node) in model.sequential_prop.iter() {
+            for (field, value) in Self::fields(&node.to_json()) {
+                if let Some(matrix) = Self::as_matrix(&value) {
+                    weights.insert(Self::name(layer, &field), matrix);
+                }
+            }
+        }
+        return weights;
+    }
+
+    /**
+     * Parameters that propagation updates instead of training, see
+     * LayerBase::non_trainable_fields
+     */
+    pub fn non_trainable(model: &Model) -> Vec<String> {
+        return model
+            .sequential_prop
+            .iter()
+            .flat_map(|(layer, node)| {
+                return node
+                    .non_trainable_fields()
+                    .iter()
+                    .map(|field| Self::name(layer, field));
+            })
+            .collect();
+    }
+
+    /**
+     * Matches the given weights against the model by name and shape, nothing is changed
+     */
+    pub fn compare(model: &Model, weights: &IndexMap<String, NDMatrix>) -> WeightReport {
+        let current = Self::collect(model);
+        let mut report = WeightReport::default();
+        for (name, matrix) in current.iter() {
+            match weights.get(name) {
+                None => report.missing.push(name.clone()),
+                Some(update) if update.width != matrix.width || update.height != matrix.height => {
+                    report.mismatched.push(ShapeMismatch {
+                        name: name.clone(),
+                        expected: (matrix.width, matrix.height),
+                        found: (update.width, update.height),
+                    })
+                }
+                Some(_) => report.loaded.push(name.clone()),
+            }
+        }
+        report.unexpected = weights
+            .keys()
+            .filter(|name| !current.contains_key(*name))
+            .cloned()
+            .collect();
+        return report;
+    }
+
+    /**
+     * Replaces every parameter of the model, the layers are recreated through the reader.
+     * Missing, unknown or differently shaped matrices are rejected before anything changes.
+     */
+    pub fn apply(
+        model: &mut Model,
+        weights: &IndexMap<String, NDMatrix>,
+        reader: &ModelReader,
+    ) -> Result<WeightReport, Error> {
+        let report = Self::compare(model, weights);
+        if !report.is_complete() {
+            return Err(Error::new(ErrorKind::InvalidData, report.to_string()));
+        }
+        Self::replace(model, weights, &report.loaded, reader)?;
+        return Ok(report);
+    }
+
+    /**
+     * Replaces only the parameters that match by name and shape, the rest of the model keeps
+     * its weights. The report lists what was loaded and what was skipped.
+     */
+    pub fn apply_partial(
+        model: &mut Model,
+        weights: &IndexMap<String, NDMatrix>,
+        reader: &ModelReader,
+    ) -> Result<WeightReport, Error> {
+        let report = Self::compare(model, weights);
+        Self::replace(model, weights, &report.loaded, reader)?;
+        return Ok(report);
+    }
+
+    fn replace(
+        model: &mut Model,
+        weights: &IndexMap<String, NDMatrix>,
+        selected: &[String],
+        reader: &ModelReader,
+    ) -> Result<(), Error> {
+        let mode = model.mode();
+        for (layer, node) in model.sequential_prop.iter_mut() {
+            let mut fields = Self::fields(&node.to_json());
+            let mut changed = false;
+            for (field, value) in fields.iter_mut() {
+                let name = Self::name(layer, field);
+                if !selected.contains(&name) {
+                    continue;
+                }
+                if let Some(matrix) = weights.get(&name) {
+                    *value = serde_json::to_value(matrix)?;
+                    changed = true;
+                }
+            }
+            if !changed {
+                continue;
+            }
+            let builder_node = model.builder_ref.get(layer).unwrap();
+            let json = JsonWrap::from(&fields)?;
+            let created = *reader
+                .get_layer_di()
+                .create(&builder_node.type_name(), &json, reader);
+            *node = ModelPropagationNode::from_builder(builder_node, created);
+            node.set_mode(mode);
+        }
+        return Ok(());
+    }
+
+    pub fn name(layer: &str, field: &str) -> String {
+        return format!("{}.{}", layer, field);
+    }
+
+    fn fields(json: &JsonWrap) -> Map<String, Value> {
+        return json.to::<Map<String, Value>>().unwrap_or_default();
+    }
+
+    fn as_matrix(value: &Value) -> Option<NDMatrix> {
+        if !value.is_object() {
+            return None;
+        }
+        return serde_json::from_value(value.clone()).ok();
+    }
+}
+
+/**
+ * Outcome of matching weights against a model, shapes are (width, height)
+ */
+#[derive(Debug, Default)]
+pub struct WeightReport {
+    pub loaded: Vec<String>,
+    /**
+     * Parameters of the model without a matching name, left unchanged
+     */
+    pub missing: Vec<String>,
+    /**
+     * Names that are not a parameter of the model
+     */
+    pub unexpected: Vec<String>,
+    pub mismatched: Vec<ShapeMismatch>,
+}
+
+#[derive(Debug)]
+pub struct ShapeMismatch {
+    pub name: String,
+    pub expected: (usize, usize),
+    pub found: (usize, usize),
+}
+
+impl WeightReport {
+    pub fn is_complete(&self) -> bool {
+        return self.missing.is_empty() && self.unexpected.is_empty() && self.mismatched.is_empty();
+    }
+}
+
+impl Display for WeightReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Loaded {} weights", self.loaded.len())?;
+        if !self.missing.is_empty() {
+            write!(f, ", missing: {}", self.missing.join(", "))?;
+        }
+        if !self.unexpected.is_empty() {
+            write!(f, ", unexpected: {}", self.unexpected.join(", "))?;
+        }
+        for m in self.mismatched.iter() {
+            write!(
+                f,
+                ", {} is {}x{} instead of {}x{}",
+                m.name, m.found.0, m.found.1, m.expected.0, m.expected.1
+            )?;
+        }
+        return Ok(());
+    }
+}
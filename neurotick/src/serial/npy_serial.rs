@@ -0,0 +1,190 @@
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Read, Write},
+    path::Path,
+};
+
+use indexmap::IndexMap;
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::matrix::nmatrix::NDMatrix;
+
+/**
+ * NumPy .npy arrays and .npz archives (https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html)
+ *
+ * Matrices are written as little-endian f32 with shape (height, width), the layout of
+ * NDMatrix::values. Reading also accepts f64 and big-endian data, fortran order and
+ * 0d/1d arrays, a 1d array of length n becomes a single row of width n.
+ * An .npz is a zip of "{name}.npy" entries, as written by numpy.savez.
+ */
+pub struct Npy;
+
+impl Npy {
+    pub const MAGIC: &[u8; 6] = b"\x93NUMPY";
+    pub const HEADER_ALIGNMENT: usize = 64;
+
+    pub fn encode(matrix: &NDMatrix) -> Vec<u8> {
+        let dict = format!(
+            "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}), }}",
+            matrix.height, matrix.width
+        );
+        // magic, version and header length precede the dict, which ends with a newline
+        let unpadded = Self::MAGIC.len() + 2 + 2 + dict.len() + 1;
+        let padding =
+            (Self::HEADER_ALIGNMENT - unpadded % Self::HEADER_ALIGNMENT) % Self::HEADER_ALIGNMENT;
+        let header = format!("{}{}\n", dict, " ".repeat(padding));
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(unpadded + padding + matrix.values.len() * 4);
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        matrix
+            .iter_all()
+            .for_each(|f| bytes.extend_from_slice(&f.to_le_bytes()));
+        return bytes;
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<NDMatrix, Error> {
+        if bytes.len() < 10 || &bytes[0..6] != Self::MAGIC {
+            return Err(Self::invalid("Not an npy array".to_string()));
+        }
+        let (header_start, header_len) = match bytes[6] {
+            1 => (10, u16::from_le_bytes([bytes[8], bytes[9]]) as usize),
+            2 | 3 if bytes.len() >= 12 => (
+                12,
+                u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize,
+            ),
+            version => {
+                return Err(Self::invalid(format!(
+                    "Unsupported npy version: {}",
+                    version
+                )))
+            }
+        };
+        let header_end = header_start + header_len;
+        let header = bytes
+            .get(header_start..header_end)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .ok_or_else(|| Self::invalid("Unreadable npy header".to_string()))?;
+
+        let descr = Self::header_value(header, "descr")?;
+        let descr = descr
+            .get(1..)
+            .and_then(|d| d.split(|c| c == '\'' || c == '"').next())
+            .unwrap_or("");
+        let fortran_order = Self::header_value(header, "fortran_order")?.starts_with("True");
+        let shape = Self::header_value(header, "shape")?;
+        let dims = shape
+            .trim_start_matches('(')
+            .split(')')
+            .next()
+            .unwrap_or("")
+            .split(',')
+            .map(|d| d.trim())
+            .filter(|d| !d.is_empty())
+            .map(|d| d.parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| Self::invalid(format!("Unreadable npy shape: {}", shape)))?;
+        let (width, height) = match dims.as_slice() {
+            [] => (1, 1),
+            [w] => (*w, 1),
+            [h, w] => (*w, *h),
+            _ => {
+                return Err(Self::invalid(format!(
+                    "Only 2d npy arrays are supported: {:?}",
+                    dims
+                )))
+            }
+        };
+
+        let data = &bytes[header_end..];
+        let count = width
+            .checked_mul(height)
+            .ok_or_else(|| Self::invalid(format!("Npy shape overflow: {:?}", dims)))?;
+        let values: Vec<f32> = match descr {
+            "<f4" | "=f4" => Self::values(data, count, 4, |b| {
+                f32::from_le_bytes(b.try_into().unwrap())
+            }),
+            ">f4" => Self::values(data, count, 4, |b| {
+                f32::from_be_bytes(b.try_into().unwrap())
+            }),
+            "<f8" | "=f8" => Self::values(data, count, 8, |b| {
+                f64::from_le_bytes(b.try_into().unwrap()) as f32
+            }),
+            ">f8" => Self::values(data, count, 8, |b| {
+                f64::from_be_bytes(b.try_into().unwrap()) as f32
+            }),
+            other => return Err(Self::invalid(format!("Unsupported npy dtype: {}", other))),
+        }?;
+
+        if fortran_order {
+            // column-major data, read it as the transposed matrix and swap back
+            let transposed = NDMatrix::from_raw_vec(height, width, values);
+            return Ok(NDMatrix::with(
+                width,
+                height,
+                transposed.values.t().to_owned(),
+            ));
+        }
+        return Ok(NDMatrix::from_raw_vec(width, height, values));
+    }
+
+    pub fn write_npz<P: AsRef<Path>>(
+        path: P,
+        arrays: &IndexMap<String, NDMatrix>,
+    ) -> Result<(), Error> {
+        let mut zip = ZipWriter::new(File::create(path)?);
+        let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+        for (name, matrix) in arrays.iter() {
+            zip.start_file(format!("{}.npy", name), options)?;
+            zip.write_all(&Self::encode(matrix))?;
+        }
+        zip.finish()?;
+        return Ok(());
+    }
+
+    pub fn read_npz<P: AsRef<Path>>(path: P) -> Result<IndexMap<String, NDMatrix>, Error> {
+        let mut zip = ZipArchive::new(File::open(path)?)?;
+        let mut arrays: IndexMap<String, NDMatrix> = IndexMap::new();
+        for index in 0..zip.len() {
+            let mut entry = zip.by_index(index)?;
+            let name = entry.name().to_string();
+            // the size in the zip header is not trusted for an allocation
+            let mut bytes: Vec<u8> = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            let matrix = Self::decode(&bytes)
+                .map_err(|e| Self::invalid(format!("Array {}: {}", name, e)))?;
+            arrays.insert(name.trim_end_matches(".npy").to_string(), matrix);
+        }
+        return Ok(arrays);
+    }
+
+    /**
+     * Raw value of a key in the python dict literal of the header
+     */
+    fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str, Error> {
+        let position = header
+            .find(&format!("'{}'", key))
+            .or_else(|| header.find(&format!("\"{}\"", key)))
+            .ok_or_else(|| Self::invalid(format!("Npy header without {}", key)))?;
+        let rest = &header[position + key.len() + 2..];
+        let rest = rest.trim_start().trim_start_matches(':').trim_start();
+        return Ok(rest);
+    }
+
+    fn values<F>(data: &[u8], count: usize, size: usize, read: F) -> Result<Vec<f32>, Error>
+    where
+        F: Fn(&[u8]) -> f32,
+    {
+        let data = count
+            .checked_mul(size)
+            .and_then(|len| data.get(..len))
+            .ok_or_else(|| Self::invalid("Truncated npy data".to_string()))?;
+        return Ok(data.chunks_exact(size).map(read).collect());
+    }
+
+    fn invalid(message: String) -> Error {
+        return Error::new(ErrorKind::InvalidData, message);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod onnx_error;
+pub mod onnx_export;
+pub mod onnx_import;
+pub mod proto;
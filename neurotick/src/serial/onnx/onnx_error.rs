@@ -0,0 +1,40 @@
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum OnnxError {
+    /**
+     * A layer, activation or op that has no onnx mapping
+     */
+    Unsupported(String),
+    /**
+     * The model or graph is malformed or inconsistent
+     */
+    Invalid(String),
+    Io(std::io::Error),
+    Decode(prost::DecodeError),
+}
+
+impl Display for OnnxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OnnxError::Unsupported(m) => write!(f, "Unsupported by onnx conversion: {}", m),
+            OnnxError::Invalid(m) => write!(f, "Invalid onnx conversion: {}", m),
+            OnnxError::Io(e) => write!(f, "Onnx io error: {}", e),
+            OnnxError::Decode(e) => write!(f, "Onnx decode error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for OnnxError {}
+
+impl From<std::io::Error> for OnnxError {
+    fn from(value: std::io::Error) -> Self {
+        return OnnxError::Io(value);
+    }
+}
+
+impl From<prost::DecodeError> for OnnxError {
+    fn from(value: prost::DecodeError) -> Self {
+        return OnnxError::Decode(value);
+    }
+}
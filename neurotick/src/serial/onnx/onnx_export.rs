@@ -0,0 +1,337 @@
+use std::collections::HashSet;
+
+use prost::Message;
+use serde::Deserialize;
+
+use crate::{
+    activation::{
+        abs::ActivationSerialised, lerelu::LeakyReLu, none::NoneAct, relu::ReLu, sigmoid::Sigmoid,
+        softmax::SoftMax, tanh::Tanh,
+    },
+    builder::graph_elements::BuilderNode,
+    layer::{concat::Concat, dense::Dense, direct::Direct, flatten::Flatten, input::Input},
+    matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+    serial::model_serial::ModelSerialized,
+    utils::json_wrap::JsonWrap,
+};
+
+use super::{
+    onnx_error::OnnxError,
+    proto::{
+        AttributeProto, AttributeType, DataType, Dimension, DimensionValue, GraphProto, ModelProto,
+        NodeProto, OperatorSetIdProto, TensorProto, TensorShapeProto, TensorTypeProto, TypeProto,
+        TypeValue, ValueInfoProto,
+    },
+};
+
+/**
+ * Maps a serialized model onto an onnx graph. Every layer output is a tensor named after the
+ * layer, graph inputs and outputs are named after their data names. An output data name
+ * that is also used by an input gets the OUTPUT_SUFFIX appended.
+ *
+ * Input -> Identity, Dense -> MatMul + Add, Direct -> Mul + Add, Concat -> Concat,
+ * Flatten -> Reshape, activations -> Relu + Clip, LeakyRelu, Sigmoid, Tanh, Softmax
+ */
+pub struct OnnxExport {
+    nodes: Vec<NodeProto>,
+    initializers: Vec<TensorProto>,
+}
+
+#[derive(Deserialize)]
+struct InputMeta {
+    features: Shape,
+    size: Shape,
+}
+
+#[derive(Deserialize)]
+struct WeightedMeta {
+    weight: NDMatrix,
+    bias: NDMatrix,
+    activation: ActivationSerialised,
+}
+
+impl OnnxExport {
+    pub const IR_VERSION: i64 = 8;
+    pub const OPSET_VERSION: i64 = 13;
+    pub const OUTPUT_SUFFIX: &str = ":output";
+
+    pub fn to_bytes(serialized: &ModelSerialized) -> Result<Vec<u8>, OnnxError> {
+        return Ok(Self::export(serialized)?.encode_to_vec());
+    }
+
+    pub fn export(serialized: &ModelSerialized) -> Result<ModelProto, OnnxError> {
+        let mut export = OnnxExport {
+            nodes: vec![],
+            initializers: vec![],
+        };
+        let mut inputs: Vec<ValueInfoProto> = vec![];
+
+        for (layer, meta) in serialized.meta.meta.iter() {
+            let node = serialized.graph.graph.get(layer).ok_or_else(|| {
+                OnnxError::Invalid(format!("Missing graph node for layer: {}", layer))
+            })?;
+
+            match node.type_name().as_str() {
+                Input::NAME => {
+                    let data_name = serialized.io.inputs.get(layer).ok_or_else(|| {
+                        OnnxError::Invalid(format!("Input layer without data name: {}", layer))
+                    })?;
+                    let input_meta: InputMeta = Self::parse(layer, meta)?;
+                    inputs.push(Self::value_info(
+                        data_name,
+                        Some(vec![
+                            Self::dimension(&input_meta.size, "size"),
+                            Self::dimension(&input_meta.features, "features"),
+                        ]),
+                    ));
+                    export.node("Identity", layer, vec![data_name.clone()], layer, vec![]);
+                }
+                Dense::NAME => {
+                    let parent = Self::single_parent(node)?;
+                    let dense: WeightedMeta = Self::parse(layer, meta)?;
+                    let weight = export.matrix_initializer(layer, "weight", &dense.weight);
+                    let bias = export.matrix_initializer(layer, "bias", &dense.bias);
+                    let matmul = format!("{}/matmul", layer);
+                    export.node("MatMul", layer, vec![parent, weight], &matmul, vec![]);
+                    export.weighted_output(layer, matmul, bias, &dense.activation)?;
+                }
+                Direct::NAME => {
+                    let parent = Self::single_parent(node)?;
+                    let direct: WeightedMeta = Self::parse(layer, meta)?;
+                    let weight = export.matrix_initializer(layer, "weight", &direct.weight);
+                    let bias = export.matrix_initializer(layer, "bias", &direct.bias);
+                    let mul = format!("{}/mul", layer);
+                    export.node("Mul", layer, vec![parent, weight], &mul, vec![]);
+                    export.weighted_output(layer, mul, bias, &direct.activation)?;
+                }
+                Concat::NAME => {
+                    let parents = match node {
+                        BuilderNode::MultipleParent(s) => s.parent_names.clone(),
+                        _ => return Err(OnnxError::Invalid(format!("{} needs parents", layer))),
+                    };
+                    let axis = Self::int_attribute("axis", 1);
+                    export.node("Concat", layer, parents, layer, vec![axis]);
+                }
+                Flatten::NAME => {
+                    let parent = Self::single_parent(node)?;
+                    let shape = format!("{}.shape", layer);
+                    export.initializers.push(TensorProto {
+                        name: shape.clone(),
+                        dims: vec![2],
+                        data_type: DataType::INT64,
+                        int64_data: vec![1, -1],
+                        ..Default::default()
+                    });
+                    export.node("Reshape", layer, vec![parent, shape], layer, vec![]);
+                }
+                other => {
+                    return Err(OnnxError::Unsupported(format!(
+                        "layer {} of type {}",
+                        layer, other
+                    )))
+                }
+            }
+        }
+
+        let input_names: HashSet<&String> = serialized.io.inputs.values().collect();
+        let mut outputs: Vec<ValueInfoProto> = vec![];
+        for (layer, data_name) in serialized.io.outputs.iter() {
+            let output_name = if input_names.contains(data_name) {
+                format!("{}{}", data_name, Self::OUTPUT_SUFFIX)
+            } else {
+                data_name.clone()
+            };
+            let node_name = format!("{}/output", layer);
+            export.node(
+                "Identity",
+                &node_name,
+                vec![layer.clone()],
+                &output_name,
+                vec![],
+            );
+            outputs.push(Self::value_info(&output_name, None));
+        }
+
+        let graph = GraphProto {
+            name: "neurotick".to_string(),
+            node: export.nodes,
+            initializer: export.initializers,
+            input: inputs,
+            output: outputs,
+            ..Default::default()
+        };
+        return Ok(ModelProto {
+            ir_version: Self::IR_VERSION,
+            opset_import: vec![OperatorSetIdProto {
+                domain: "".to_string(),
+                version: Self::OPSET_VERSION,
+            }],
+            producer_name: "neurotick".to_string(),
+            producer_version: env!("CARGO_PKG_VERSION").to_string(),
+            graph: Some(graph),
+            ..Default::default()
+        });
+    }
+
+    /**
+     * Adds the bias and the activation, the last node outputs the layer tensor
+     */
+    fn weighted_output(
+        &mut self,
+        layer: &String,
+        input: String,
+        bias: String,
+        activation: &ActivationSerialised,
+    ) -> Result<(), OnnxError> {
+        if activation.name == NoneAct::NAME {
+            self.node("Add", layer, vec![input, bias], layer, vec![]);
+            return Ok(());
+        }
+        let add = format!("{}/add", layer);
+        self.node("Add", layer, vec![input, bias], &add, vec![]);
+        return self.activation(layer, add, activation);
+    }
+
+    fn activation(
+        &mut self,
+        layer: &String,
+        input: String,
+        activation: &ActivationSerialised,
+    ) -> Result<(), OnnxError> {
+        match activation.name.as_str() {
+            ReLu::NAME => {
+                let relu: ReLu = Self::parse(layer, &activation.json)?;
+                let relu_out = format!("{}/relu", layer);
+                let cap = format!("{}.cap", layer);
+                self.initializers.push(TensorProto {
+                    name: cap.clone(),
+                    dims: vec![],
+                    data_type: DataType::FLOAT,
+                    float_data: vec![relu.cap],
+                    ..Default::default()
+                });
+                self.node("Relu", layer, vec![input], &relu_out, vec![]);
+                // empty name skips the optional min input
+                self.node(
+                    "Clip",
+                    layer,
+                    vec![relu_out, "".to_string(), cap],
+                    layer,
+                    vec![],
+                );
+            }
+            LeakyReLu::NAME => {
+                let lerelu: LeakyReLu = Self::parse(layer, &activation.json)?;
+                let alpha = AttributeProto {
+                    name: "alpha".to_string(),
+                    r#type: AttributeType::FLOAT,
+                    f: lerelu.beta,
+                    ..Default::default()
+                };
+                self.node("LeakyRelu", layer, vec![input], layer, vec![alpha]);
+            }
+            Sigmoid::NAME => self.node("Sigmoid", layer, vec![input], layer, vec![]),
+            Tanh::NAME => self.node("Tanh", layer, vec![input], layer, vec![]),
+            SoftMax::NAME => {
+                let axis = Self::int_attribute("axis", 1);
+                self.node("Softmax", layer, vec![input], layer, vec![axis]);
+            }
+            other => {
+                return Err(OnnxError::Unsupported(format!(
+                    "activation {} of layer {}",
+                    other, layer
+                )))
+            }
+        }
+        return Ok(());
+    }
+
+    fn node(
+        &mut self,
+        op_type: &str,
+        layer: &str,
+        inputs: Vec<String>,
+        output: &str,
+        attributes: Vec<AttributeProto>,
+    ) {
+        self.nodes.push(NodeProto {
+            name: format!("{}/{}", layer, op_type),
+            op_type: op_type.to_string(),
+            input: inputs,
+            output: vec![output.to_string()],
+            attribute: attributes,
+            ..Default::default()
+        });
+    }
+
+    /**
+     * Stored as [height, width] raw little-endian floats, same as NDMatrix::values
+     */
+    fn matrix_initializer(&mut self, layer: &str, suffix: &str, matrix: &NDMatrix) -> String {
+        let name = format!("{}.{}", layer, suffix);
+        let raw_data = matrix
+            .iter_all()
+            .flat_map(|f| f.to_le_bytes())
+            .collect::<Vec<u8>>();
+        self.initializers.push(TensorProto {
+            name: name.clone(),
+            dims: vec![matrix.height as i64, matrix.width as i64],
+            data_type: DataType::FLOAT,
+            raw_data,
+            ..Default::default()
+        });
+        return name;
+    }
+
+    fn int_attribute(name: &str, value: i64) -> AttributeProto {
+        return AttributeProto {
+            name: name.to_string(),
+            r#type: AttributeType::INT,
+            i: value,
+            ..Default::default()
+        };
+    }
+
+    fn value_info(name: &str, dims: Option<Vec<Dimension>>) -> ValueInfoProto {
+        let tensor_type = TensorTypeProto {
+            elem_type: DataType::FLOAT,
+            shape: dims.map(|dim| TensorShapeProto { dim }),
+        };
+        return ValueInfoProto {
+            name: name.to_string(),
+            r#type: Some(TypeProto {
+                value: Some(TypeValue::TensorType(tensor_type)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+    }
+
+    fn dimension(shape: &Shape, param: &str) -> Dimension {
+        let value = match shape {
+            Shape::Const(c) => DimensionValue::DimValue(*c as i64),
+            Shape::Grid(height, width) => DimensionValue::DimValue((height * width) as i64),
+            Shape::Repeat | Shape::Variable => DimensionValue::DimParam(param.to_string()),
+        };
+        return Dimension {
+            value: Some(value),
+            ..Default::default()
+        };
+    }
+
+    fn single_parent(node: &BuilderNode) -> Result<String, OnnxError> {
+        return match node {
+            BuilderNode::SingleParent(s) => Ok(s.parent_name.clone()),
+            _ => Err(OnnxError::Invalid(format!(
+                "{} should have a single parent",
+                node.layer_name()
+            ))),
+        };
+    }
+
+    fn parse<T: serde::de::DeserializeOwned>(layer: &str, json: &JsonWrap) -> Result<T, OnnxError> {
+        return json
+            .to::<T>()
+            .map_err(|e| OnnxError::Invalid(format!("Unreadable meta of {}: {}", layer, e)));
+    }
+}
@@ -0,0 +1,654 @@
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+use prost::Message;
+
+use crate::{
+    activation::{
+        abs::Activation, lerelu::LeakyReLu, relu::ReLu, sigmoid::Sigmoid, softmax::SoftMax,
+        tanh::Tanh,
+    },
+    matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+    model::model::Model,
+    serial::imported_graph::{ImportedKind, ImportedLayer, Weighted},
+};
+
+use super::{
+    onnx_error::OnnxError,
+    onnx_export::OnnxExport,
+    proto::{
+        AttributeProto, DataType, DimensionValue, ModelProto, NodeProto, TensorProto, TypeValue,
+        ValueInfoProto,
+    },
+};
+
+/**
+ * Maps a feed-forward onnx graph onto Neurotick layers, ops are fused into the layer that
+ * produces their input when that tensor has no other consumer:
+ *
+ * MatMul/Gemm -> Dense, Mul -> Direct, Add -> bias, Relu (+ Clip) -> ReLu,
+ * LeakyRelu, Sigmoid, Tanh, Softmax -> activation, Concat -> Concat,
+ * Flatten (axis 0) / Reshape [1, -1] -> Flatten, Identity / Flatten (axis 1) -> passthrough
+ *
+ * An Add or an activation that can't be fused becomes a Direct layer with unit weights.
+ * Graph inputs and outputs become data names, OnnxExport::OUTPUT_SUFFIX is dropped again
+ * when it was added to an input name.
+ */
+pub struct OnnxImport {
+    constants: HashMap<String, TensorProto>,
+    consumers: HashMap<String, usize>,
+    tensors: HashMap<String, usize>,
+    layers: Vec<ImportedLayer>,
+}
+
+impl OnnxImport {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Model, OnnxError> {
+        return Self::import(&ModelProto::decode(bytes)?);
+    }
+
+    pub fn import(proto: &ModelProto) -> Result<Model, OnnxError> {
+        let graph = proto
+            .graph
+            .as_ref()
+            .ok_or_else(|| OnnxError::Invalid("Model without a graph".to_string()))?;
+
+        let mut import = OnnxImport {
+            constants: HashMap::new(),
+            consumers: HashMap::new(),
+            tensors: HashMap::new(),
+            layers: vec![],
+        };
+        graph.initializer.iter().for_each(|t| {
+            import.constants.insert(t.name.clone(), t.clone());
+        });
+        graph
+            .node
+            .iter()
+            .flat_map(|n| n.input.iter())
+            .chain(graph.output.iter().map(|o| &o.name))
+            .for_each(|name| *import.consumers.entry(name.clone()).or_insert(0) += 1);
+
+        let mut input_names: Vec<String> = vec![];
+        for input in graph.input.iter() {
+            // older exporters list initializers as graph inputs
+            if import.constants.contains_key(&input.name) {
+                continue;
+            }
+            let (features, size) = Self::input_shape(input)?;
+            let index = import.push(ImportedKind::Input, features, size, &input.name);
+            import.tensors.insert(input.name.clone(), index);
+            input_names.push(input.name.clone());
+        }
+
+        for node in graph.node.iter() {
+            if !node.domain.is_empty() && node.domain != "ai.onnx" {
+                return Err(OnnxError::Unsupported(format!(
+                    "operator {} of domain {}",
+                    node.op_type, node.domain
+                )));
+            }
+            import.node(node)?;
+        }
+
+        let names = ImportedLayer::names(&import.layers);
+
+        let mut input_layer_to_data_name: IndexMap<String, String> = IndexMap::new();
+        for data_name in input_names.iter() {
+            let index = import.tensors[data_name];
+            input_layer_to_data_name.insert(names[index].clone(), data_name.clone());
+        }
+
+        let mut output_layer_to_data_name: IndexMap<String, String> = IndexMap::new();
+        for output in graph.output.iter() {
+            let index = import.layer_of(&output.name)?;
+            let data_name = match output.name.strip_suffix(OnnxExport::OUTPUT_SUFFIX) {
+                Some(stripped) if input_names.iter().any(|i| i == stripped) => stripped.to_string(),
+                _ => output.name.clone(),
+            };
+            if let Some(other) = output_layer_to_data_name.insert(names[index].clone(), data_name) {
+                return Err(OnnxError::Unsupported(format!(
+                    "outputs {} and {} from the same layer",
+                    other, output.name
+                )));
+            }
+        }
+
+        return Ok(ImportedLayer::into_model(
+            import.layers,
+            &names,
+            input_layer_to_data_name,
+            output_layer_to_data_name,
+        ));
+    }
+
+    fn node(&mut self, node: &NodeProto) -> Result<(), OnnxError> {
+        let output = node
+            .output
+            .first()
+            .ok_or_else(|| OnnxError::Invalid(format!("Node {} without output", node.name)))?
+            .clone();
+
+        match node.op_type.as_str() {
+            "Constant" => {
+                let value = Self::attribute(node, "value")
+                    .and_then(|a| a.t.clone())
+                    .ok_or_else(|| {
+                        OnnxError::Unsupported(format!("Constant {} without a tensor", node.name))
+                    })?;
+                self.constants.insert(output, value);
+            }
+            "Identity" => {
+                let input = self.input(node, 0)?;
+                if let Some(constant) = self.constants.get(&input).cloned() {
+                    self.constants.insert(output, constant);
+                } else {
+                    self.alias(&input, output)?;
+                }
+            }
+            "MatMul" => {
+                let parent = self.layer_of(&self.input(node, 0)?)?;
+                let weight = self.matrix(&self.input(node, 1)?)?;
+                self.check_features(node, parent, weight.height)?;
+                self.dense(parent, weight, None, output);
+            }
+            "Gemm" => {
+                if Self::int(node, "transA", 0) != 0 {
+                    return Err(OnnxError::Unsupported(format!(
+                        "Gemm {} with transA",
+                        node.name
+                    )));
+                }
+                let parent = self.layer_of(&self.input(node, 0)?)?;
+                let mut weight = self.matrix(&self.input(node, 1)?)?;
+                if Self::int(node, "transB", 0) != 0 {
+                    weight =
+                        NDMatrix::with(weight.height, weight.width, weight.values.t().to_owned());
+                }
+                let alpha = Self::float(node, "alpha", 1.0);
+                weight.values.mapv_inplace(|f| f * alpha);
+                self.check_features(node, parent, weight.height)?;
+
+                let bias = match node.input.get(2).filter(|c| !c.is_empty()) {
+                    Some(c) => {
+                        let beta = Self::float(node, "beta", 1.0);
+                        let mut bias = self.row(c, weight.width)?;
+                        bias.values.mapv_inplace(|f| f * beta);
+                        Some(bias)
+                    }
+                    None => None,
+                };
+                self.dense(parent, weight, bias, output);
+            }
+            "Mul" => {
+                let (tensor, constant) = self.tensor_and_constant(node)?;
+                let parent = self.layer_of(&tensor)?;
+                let features = self.const_features(node, parent)?;
+                let weight = self.row(&constant, features)?;
+                let weighted = Weighted {
+                    weight,
+                    bias: None,
+                    activation: None,
+                };
+                self.derive(parent, ImportedKind::Direct(parent, weighted), output);
+            }
+            "Add" => {
+                let (tensor, constant) = self.tensor_and_constant(node)?;
+                let parent = self.layer_of(&tensor)?;
+                let features = self.const_features(node, parent)?;
+                let bias = self.row(&constant, features)?;
+                match self.fusable(&tensor) {
+                    Some(w) if w.bias.is_none() && w.activation.is_none() => {
+                        w.bias = Some(bias);
+                        self.layers[parent].output = output.clone();
+                        self.tensors.insert(output, parent);
+                    }
+                    _ => {
+                        let weighted = Weighted {
+                            weight: NDMatrix::constant(features, 1, 1.0),
+                            bias: Some(bias),
+                            activation: None,
+                        };
+                        self.derive(parent, ImportedKind::Direct(parent, weighted), output);
+                    }
+                }
+            }
+            "Relu" => self.activation(node, Box::new(ReLu { cap: f32::MAX }), output)?,
+            "LeakyRelu" => {
+                let beta = Self::float(node, "alpha", 0.01);
+                self.activation(node, Box::new(LeakyReLu { beta }), output)?;
+            }
+            "Sigmoid" => self.activation(node, Box::new(Sigmoid::default()), output)?,
+            "Tanh" => self.activation(node, Box::new(Tanh::default()), output)?,
+            "Softmax" => {
+                let axis = Self::int(node, "axis", -1);
+                if axis != 1 && axis != -1 {
+                    return Err(OnnxError::Unsupported(format!(
+                        "Softmax {} over axis {}",
+                        node.name, axis
+                    )));
+                }
+                self.activation(node, Box::new(SoftMax::default()), output)?;
+            }
+            "Clip" => self.clip(node, output)?,
+            "Concat" => {
+                let axis = Self::int(node, "axis", 1);
+                if axis != 1 && axis != -1 {
+                    return Err(OnnxError::Unsupported(format!(
+                        "Concat {} over axis {}",
+                        node.name, axis
+                    )));
+                }
+                let parents = node
+                    .input
+                    .iter()
+                    .map(|i| self.layer_of(i))
+                    .collect::<Result<Vec<usize>, OnnxError>>()?;
+                let mut features = 0;
+                for parent in parents.iter() {
+                    features += self.const_features(node, *parent)?;
+                }
+                let sizes: Vec<&Shape> = parents.iter().map(|p| &self.layers[*p].size).collect();
+                let size = match sizes.first() {
+                    Some(Shape::Const(first))
+                        if sizes
+                            .iter()
+                            .all(|s| matches!(s, Shape::Const(c) if c == first)) =>
+                    {
+                        Shape::Const(*first)
+                    }
+                    _ => Shape::Variable,
+                };
+                let index = self.push(
+                    ImportedKind::Concat(parents),
+                    Shape::Const(features),
+                    size,
+                    &output,
+                );
+                self.tensors.insert(output, index);
+            }
+            "Flatten" => {
+                let input = self.input(node, 0)?;
+                match Self::int(node, "axis", 1) {
+                    // rows stay rows for the 2d tensors between layers
+                    1 => self.alias(&input, output)?,
+                    0 => self.flatten(&input, output)?,
+                    axis => {
+                        return Err(OnnxError::Unsupported(format!(
+                            "Flatten {} over axis {}",
+                            node.name, axis
+                        )))
+                    }
+                }
+            }
+            "Reshape" => {
+                let shape = self.int64_values(&self.input(node, 1)?)?;
+                if shape != vec![1, -1] {
+                    return Err(OnnxError::Unsupported(format!(
+                        "Reshape {} to {:?}, only [1, -1] is supported",
+                        node.name, shape
+                    )));
+                }
+                self.flatten(&self.input(node, 0)?, output)?;
+            }
+            other => {
+                return Err(OnnxError::Unsupported(format!(
+                    "operator {} of node {}",
+                    other, node.name
+                )))
+            }
+        }
+        return Ok(());
+    }
+
+    fn dense(&mut self, parent: usize, weight: NDMatrix, bias: Option<NDMatrix>, output: String) {
+        let features = Shape::Const(weight.width);
+        let size = self.layers[parent].size.clone();
+        let weighted = Weighted {
+            weight,
+            bias,
+            activation: None,
+        };
+        let index = self.push(
+            ImportedKind::Dense(parent, weighted),
+            features,
+            size,
+            &output,
+        );
+        self.tensors.insert(output, index);
+    }
+
+    /**
+     * New layer with the shape of its parent
+     */
+    fn derive(&mut self, parent: usize, kind: ImportedKind, output: String) {
+        let features = self.layers[parent].features.clone();
+        let size = self.layers[parent].size.clone();
+        let index = self.push(kind, features, size, &output);
+        self.tensors.insert(output, index);
+    }
+
+    fn flatten(&mut self, input: &str, output: String) -> Result<(), OnnxError> {
+        let parent = self.layer_of(input)?;
+        let features = match (&self.layers[parent].features, &self.layers[parent].size) {
+            (Shape::Const(x), Shape::Const(y)) => Shape::Const(x * y),
+            _ => Shape::Variable,
+        };
+        let index = self.push(
+            ImportedKind::Flatten(parent),
+            features,
+            Shape::Const(1),
+            &output,
+        );
+        self.tensors.insert(output, index);
+        return Ok(());
+    }
+
+    fn activation(
+        &mut self,
+        node: &NodeProto,
+        activation: Box<dyn Activation>,
+        output: String,
+    ) -> Result<(), OnnxError> {
+        let input = self.input(node, 0)?;
+        let parent = self.layer_of(&input)?;
+        match self.fusable(&input) {
+            Some(w) if w.activation.is_none() => {
+                w.activation = Some(activation);
+                self.layers[parent].output = output.clone();
+                self.tensors.insert(output, parent);
+            }
+            _ => {
+                let features = self.const_features(node, parent)?;
+                let weighted = Weighted {
+                    weight: NDMatrix::constant(features, 1, 1.0),
+                    bias: None,
+                    activation: Some(activation),
+                };
+                self.derive(parent, ImportedKind::Direct(parent, weighted), output);
+            }
+        }
+        return Ok(());
+    }
+
+    /**
+     * Only as the cap of a preceding Relu, min is either omitted or not above zero
+     */
+    fn clip(&mut self, node: &NodeProto, output: String) -> Result<(), OnnxError> {
+        let input = self.input(node, 0)?;
+        let parent = self.layer_of(&input)?;
+        let bound = |import: &OnnxImport,
+                     index: usize,
+                     attribute: &str|
+         -> Result<Option<f32>, OnnxError> {
+            return match node.input.get(index).filter(|n| !n.is_empty()) {
+                Some(name) => Ok(Some(import.scalar(name)?)),
+                None => Ok(Self::attribute(node, attribute).map(|a| a.f)),
+            };
+        };
+        let min = bound(self, 1, "min")?;
+        let max = bound(self, 2, "max")?;
+
+        let capped = match (self.fusable(&input), min, max) {
+            (Some(w), min, Some(max)) if !min.is_some_and(|m| m > 0.0) => {
+                match w.activation.as_ref().map(|a| a.as_serialized()) {
+                    Some(serialized) if serialized.name == ReLu::NAME => {
+                        let relu: ReLu = serialized.json.to().unwrap();
+                        w.activation = Some(Box::new(ReLu {
+                            cap: relu.cap.min(max),
+                        }));
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+        if !capped {
+            return Err(OnnxError::Unsupported(format!(
+                "Clip {} outside of a capped Relu",
+                node.name
+            )));
+        }
+        self.layers[parent].output = output.clone();
+        self.tensors.insert(output, parent);
+        return Ok(());
+    }
+
+    /**
+     * Weights of the layer producing the tensor, if nothing else reads that tensor
+     */
+    fn fusable(&mut self, tensor: &str) -> Option<&mut Weighted> {
+        let index = *self.tensors.get(tensor)?;
+        if self.consumers.get(tensor).copied().unwrap_or(0) != 1 {
+            return None;
+        }
+        let layer = &mut self.layers[index];
+        if layer.output != tensor {
+            return None;
+        }
+        return match &mut layer.kind {
+            ImportedKind::Dense(_, w) | ImportedKind::Direct(_, w) => Some(w),
+            _ => None,
+        };
+    }
+
+    fn alias(&mut self, input: &str, output: String) -> Result<(), OnnxError> {
+        let index = self.layer_of(input)?;
+        if self.layers[index].output == input && self.consumers.get(input) == Some(&1) {
+            self.layers[index].output = output.clone();
+        }
+        self.tensors.insert(output, index);
+        return Ok(());
+    }
+
+    fn push(&mut self, kind: ImportedKind, features: Shape, size: Shape, output: &str) -> usize {
+        self.layers.push(ImportedLayer {
+            kind,
+            features,
+            size,
+            output: output.to_string(),
+        });
+        return self.layers.len() - 1;
+    }
+
+    fn layer_of(&self, tensor: &str) -> Result<usize, OnnxError> {
+        return self.tensors.get(tensor).copied().ok_or_else(|| {
+            OnnxError::Invalid(format!("Tensor {} is not produced by a layer", tensor))
+        });
+    }
+
+    fn input(&self, node: &NodeProto, index: usize) -> Result<String, OnnxError> {
+        return node
+            .input
+            .get(index)
+            .filter(|n| !n.is_empty())
+            .cloned()
+            .ok_or_else(|| {
+                OnnxError::Invalid(format!("Node {} misses input {}", node.name, index))
+            });
+    }
+
+    /**
+     * Binary op of a graph tensor and a constant, in any order
+     */
+    fn tensor_and_constant(&self, node: &NodeProto) -> Result<(String, String), OnnxError> {
+        let a = self.input(node, 0)?;
+        let b = self.input(node, 1)?;
+        return match (
+            self.constants.contains_key(&a),
+            self.constants.contains_key(&b),
+        ) {
+            (false, true) => Ok((a, b)),
+            (true, false) => Ok((b, a)),
+            _ => Err(OnnxError::Unsupported(format!(
+                "{} {} needs one graph tensor and one constant",
+                node.op_type, node.name
+            ))),
+        };
+    }
+
+    fn const_features(&self, node: &NodeProto, layer: usize) -> Result<usize, OnnxError> {
+        return match self.layers[layer].features {
+            Shape::Const(c) => Ok(c),
+            _ => Err(OnnxError::Unsupported(format!(
+                "{} {} on a variable feature count",
+                node.op_type, node.name
+            ))),
+        };
+    }
+
+    fn check_features(&self, node: &NodeProto, layer: usize, rows: usize) -> Result<(), OnnxError> {
+        let features = self.const_features(node, layer)?;
+        if features != rows {
+            return Err(OnnxError::Invalid(format!(
+                "{} {} expects {} features, got {}",
+                node.op_type, node.name, rows, features
+            )));
+        }
+        return Ok(());
+    }
+
+    /**
+     * Constant as a matrix, [height, width] for 2d tensors and a single row otherwise
+     */
+    fn matrix(&self, name: &str) -> Result<NDMatrix, OnnxError> {
+        let tensor = self.constant(name)?;
+        if tensor.data_type != DataType::FLOAT {
+            return Err(OnnxError::Unsupported(format!(
+                "tensor {} of data type {}",
+                name, tensor.data_type
+            )));
+        }
+        let values: Vec<f32> = if tensor.raw_data.is_empty() {
+            tensor.float_data.clone()
+        } else {
+            tensor
+                .raw_data
+                .chunks_exact(4)
+                .map(|le| f32::from_le_bytes(le.try_into().unwrap()))
+                .collect()
+        };
+        let (width, height) = match tensor.dims.as_slice() {
+            [] => (1, 1),
+            [w] => (Self::dim(*w, name)?, 1),
+            [h, w] => (Self::dim(*w, name)?, Self::dim(*h, name)?),
+            dims => {
+                return Err(OnnxError::Unsupported(format!(
+                    "tensor {} with dims {:?}",
+                    name, dims
+                )))
+            }
+        };
+        if width.checked_mul(height) != Some(values.len()) {
+            return Err(OnnxError::Invalid(format!(
+                "Tensor {} has {} values for dims {:?}",
+                name,
+                values.len(),
+                tensor.dims
+            )));
+        }
+        return Ok(NDMatrix::from_raw_vec(width, height, values));
+    }
+
+    /**
+     * Constant broadcast to a single row of the given width
+     */
+    fn row(&self, name: &str, width: usize) -> Result<NDMatrix, OnnxError> {
+        let matrix = self.matrix(name)?;
+        if matrix.height != 1 {
+            return Err(OnnxError::Unsupported(format!(
+                "tensor {} with {} rows, expected a single row",
+                name, matrix.height
+            )));
+        }
+        if matrix.width == 1 {
+            return Ok(NDMatrix::constant(width, 1, matrix.get(0, 0)));
+        }
+        if matrix.width != width {
+            return Err(OnnxError::Invalid(format!(
+                "Tensor {} has width {}, expected {}",
+                name, matrix.width, width
+            )));
+        }
+        return Ok(matrix);
+    }
+
+    fn scalar(&self, name: &str) -> Result<f32, OnnxError> {
+        let matrix = self.matrix(name)?;
+        if matrix.width * matrix.height != 1 {
+            return Err(OnnxError::Invalid(format!(
+                "Tensor {} is not a scalar",
+                name
+            )));
+        }
+        return Ok(matrix.get(0, 0));
+    }
+
+    fn int64_values(&self, name: &str) -> Result<Vec<i64>, OnnxError> {
+        let tensor = self.constant(name)?;
+        if tensor.data_type != DataType::INT64 {
+            return Err(OnnxError::Invalid(format!("Tensor {} is not int64", name)));
+        }
+        if tensor.raw_data.is_empty() {
+            return Ok(tensor.int64_data.clone());
+        }
+        return Ok(tensor
+            .raw_data
+            .chunks_exact(8)
+            .map(|le| i64::from_le_bytes(le.try_into().unwrap()))
+            .collect());
+    }
+
+    fn constant(&self, name: &str) -> Result<&TensorProto, OnnxError> {
+        return self
+            .constants
+            .get(name)
+            .ok_or_else(|| OnnxError::Unsupported(format!("non constant tensor {}", name)));
+    }
+
+    /**
+     * [size, features], a named or missing size dimension is repeated
+     */
+    fn input_shape(input: &ValueInfoProto) -> Result<(Shape, Shape), OnnxError> {
+        let dims = input
+            .r#type
+            .as_ref()
+            .and_then(|t| t.value.as_ref())
+            .and_then(|TypeValue::TensorType(t)| t.shape.as_ref())
+            .map(|s| s.dim.iter().map(|d| d.value.clone()).collect::<Vec<_>>())
+            .ok_or_else(|| OnnxError::Invalid(format!("Input {} without a shape", input.name)))?;
+        return match dims.as_slice() {
+            [size, Some(DimensionValue::DimValue(features))] => {
+                let size = match size {
+                    Some(DimensionValue::DimValue(s)) => Shape::Const(Self::dim(*s, &input.name)?),
+                    _ => Shape::Repeat,
+                };
+                Ok((Shape::Const(Self::dim(*features, &input.name)?), size))
+            }
+            _ => Err(OnnxError::Unsupported(format!(
+                "input {} with dims {:?}, expected [size, features]",
+                input.name, dims
+            ))),
+        };
+    }
+
+    /**
+     * Onnx dims are i64, negative ones or ones beyond usize are invalid
+     */
+    fn dim(value: i64, name: &str) -> Result<usize, OnnxError> {
+        return usize::try_from(value)
+            .map_err(|_| OnnxError::Invalid(format!("Tensor {} has dim {}", name, value)));
+    }
+
+    fn attribute<'a>(node: &'a NodeProto, name: &str) -> Option<&'a AttributeProto> {
+        return node.attribute.iter().find(|a| a.name == name);
+    }
+
+    fn int(node: &NodeProto, name: &str, default: i64) -> i64 {
+        return Self::attribute(node, name).map_or(default, |a| a.i);
+    }
+
+    fn float(node: &NodeProto, name: &str, default: f32) -> f32 {
+        return Self::attribute(node, name).map_or(default, |a| a.f);
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{
+    activation::abs::Activation, layer::abs::LayerPropagateEnum, utils::json_wrap::JsonWrap,
+};
+
+use super::model_reader::ModelReader;
+
+/**
+ * Self registration of a layer implementation into the default ModelReader.
+ * Submit one per layer type with inventory::submit!, the name should match Layer::type_name
+ */
+pub struct LayerRegistration {
+    pub name: &'static str,
+    pub create: fn(&JsonWrap, &ModelReader) -> LayerPropagateEnum,
+}
+
+/**
+ * Self registration of an activation implementation into the default ModelReader.
+ * Submit one per activation type with inventory::submit!
+ */
+pub struct ActivationRegistration {
+    pub name: &'static str,
+    pub create: fn(&JsonWrap) -> Box<dyn Activation>,
+}
+
+inventory::collect!(LayerRegistration);
+inventory::collect!(ActivationRegistration);
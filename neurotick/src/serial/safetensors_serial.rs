@@ -0,0 +1,139 @@
+use std::io::{Error, ErrorKind};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::matrix::nmatrix::NDMatrix;
+
+/**
+ * safetensors layout (https://github.com/huggingface/safetensors):
+ *
+ * | header length u64 | json header | tensor data |
+ *
+ * The header maps every tensor name to its dtype, shape and [begin, end) byte offsets into
+ * the data section, an optional "__metadata__" entry holds string pairs. Matrices are
+ * written as F32 with shape [height, width], the row-major layout of NDMatrix::values.
+ * Reading accepts F32 and F64, 0d and 1d tensors become a single row.
+ */
+pub struct SafeTensors;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct TensorInfo {
+    dtype: String,
+    shape: Vec<usize>,
+    data_offsets: (usize, usize),
+}
+
+impl SafeTensors {
+    pub const METADATA: &str = "__metadata__";
+    pub const HEADER_ALIGNMENT: usize = 8;
+
+    pub fn encode(tensors: &IndexMap<String, NDMatrix>) -> Vec<u8> {
+        let mut header: IndexMap<String, Value> = IndexMap::new();
+        let mut data_len = 0usize;
+        for (name, matrix) in tensors.iter() {
+            let len = matrix.width * matrix.height * 4;
+            let info = TensorInfo {
+                dtype: "F32".to_string(),
+                shape: vec![matrix.height, matrix.width],
+                data_offsets: (data_len, data_len + len),
+            };
+            header.insert(name.clone(), serde_json::to_value(info).unwrap());
+            data_len += len;
+        }
+        let mut metadata: IndexMap<String, String> = IndexMap::new();
+        metadata.insert("format".to_string(), "neurotick".to_string());
+        header.insert(
+            Self::METADATA.to_string(),
+            serde_json::to_value(metadata).unwrap(),
+        );
+
+        let mut header_json = serde_json::to_vec(&header).unwrap();
+        // padded with spaces so the data section starts aligned
+        while header_json.len() % Self::HEADER_ALIGNMENT != 0 {
+            header_json.push(b' ');
+        }
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(8 + header_json.len() + data_len);
+        bytes.extend_from_slice(&(header_json.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&header_json);
+        tensors.values().for_each(|m| {
+            m.iter_all()
+                .for_each(|f| bytes.extend_from_slice(&f.to_le_bytes()))
+        });
+        return bytes;
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<IndexMap<String, NDMatrix>, Error> {
+        let header_len = bytes
+            .get(0..8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| Self::invalid("Not a safetensors file".to_string()))?;
+        let header_end = usize::try_from(header_len)
+            .ok()
+            .and_then(|len| len.checked_add(8))
+            .ok_or_else(|| Self::invalid("Safetensors header length overflow".to_string()))?;
+        let header_json = bytes
+            .get(8..header_end)
+            .ok_or_else(|| Self::invalid("Truncated safetensors header".to_string()))?;
+        let header: IndexMap<String, Value> = serde_json::from_slice(header_json)?;
+        let data = &bytes[header_end..];
+
+        let mut tensors: IndexMap<String, NDMatrix> = IndexMap::new();
+        for (name, value) in header.into_iter() {
+            if name == Self::METADATA {
+                continue;
+            }
+            let info: TensorInfo = serde_json::from_value(value)?;
+            let (width, height) = match info.shape.as_slice() {
+                [] => (1, 1),
+                [w] => (*w, 1),
+                [h, w] => (*w, *h),
+                shape => {
+                    return Err(Self::invalid(format!(
+                        "Tensor {} has shape {:?}, only up to 2d is supported",
+                        name, shape
+                    )))
+                }
+            };
+            let size = match info.dtype.as_str() {
+                "F32" => 4,
+                "F64" => 8,
+                other => {
+                    return Err(Self::invalid(format!(
+                        "Tensor {} has unsupported dtype {}",
+                        name, other
+                    )))
+                }
+            };
+            let (begin, end) = info.data_offsets;
+            let len = width.checked_mul(height).and_then(|c| c.checked_mul(size));
+            if end < begin || Some(end - begin) != len {
+                return Err(Self::invalid(format!(
+                    "Tensor {} has offsets {:?} for shape {:?}",
+                    name, info.data_offsets, info.shape
+                )));
+            }
+            let raw = data
+                .get(begin..end)
+                .ok_or_else(|| Self::invalid(format!("Truncated tensor {}", name)))?;
+            let values: Vec<f32> = match size {
+                4 => raw
+                    .chunks_exact(4)
+                    .map(|le| f32::from_le_bytes(le.try_into().unwrap()))
+                    .collect(),
+                _ => raw
+                    .chunks_exact(8)
+                    .map(|le| f64::from_le_bytes(le.try_into().unwrap()) as f32)
+                    .collect(),
+            };
+            tensors.insert(name, NDMatrix::from_raw_vec(width, height, values));
+        }
+        return Ok(tensors);
+    }
+
+    fn invalid(message: String) -> Error {
+        return Error::new(ErrorKind::InvalidData, message);
+    }
+}
@@ -0,0 +1,167 @@
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, io::ErrorKind};
+
+    use crate::{
+        activation::relu::ReLu,
+        builder::builder::ModelBuilder,
+        layer::{concat::Concat, dense::Dense, direct::Direct, input::Input},
+        map,
+        matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        model::model::Model,
+        serial::{
+            binary_serial::BinaryModel, integrity::ModelIntegrity, model_reader::ModelReader,
+        },
+        suppliers::suppliers::RandomUniformSupplier,
+    };
+
+    fn build_model() -> Model {
+        let input = Input::new(Shape::Const(30), Shape::Repeat);
+        let d1 = Dense::builder(50, || &input)
+            .with_activation(ReLu::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let d2 = Direct::builder(|| &input)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let concat = Concat::new(|| vec![&d1, &d2]);
+        let output = Dense::new(7, || &concat);
+        return ModelBuilder::from_straight(input, output).build();
+    }
+
+    fn assert_same_output(a: &Model, b: &Model) {
+        let input_data: HashMap<String, NDMatrix> = map! {
+            ModelBuilder::SINGLE_IO.to_owned() =>
+                NDMatrix::from_supply(30, 4, RandomUniformSupplier::new(1.0, -1.0)),
+        };
+        let output_a = a.propagate(&input_data);
+        let output_b = b.propagate(&input_data);
+        assert_eq!(
+            output_a.get(ModelBuilder::SINGLE_IO).unwrap().values,
+            output_b.get(ModelBuilder::SINGLE_IO).unwrap().values
+        );
+    }
+
+    #[test]
+    fn binary_roundtrip() {
+        let model = build_model();
+        let bytes = model.to_binary();
+        let restored = Model::from_binary(&bytes, &ModelReader::default()).unwrap();
+        assert_same_output(&model, &restored);
+    }
+
+    #[test]
+    fn binary_layout() {
+        let model = build_model();
+        let bytes = model.to_binary();
+
+        assert_eq!(&bytes[0..4], BinaryModel::MAGIC);
+        assert_eq!(
+            u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+            BinaryModel::VERSION
+        );
+        let json_len = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+        let header: serde_json::Value = serde_json::from_slice(&bytes[16..16 + json_len]).unwrap();
+        let data_start = BinaryModel::data_start(json_len);
+        assert_eq!(data_start % BinaryModel::TENSOR_ALIGNMENT, 0);
+
+        // 3 weights + 3 biases
+        let tensors = header["tensors"].as_array().unwrap();
+        assert_eq!(tensors.len(), 6);
+        tensors.iter().for_each(|t| {
+            let offset = t["offset"].as_u64().unwrap() as usize;
+            assert_eq!(offset % BinaryModel::TENSOR_ALIGNMENT, 0);
+        });
+
+        // first tensor is the first dense weight, raw little-endian and row-major
+        let first = &tensors[0];
+        let width = first["width"].as_u64().unwrap() as usize;
+        let offset = data_start + first["offset"].as_u64().unwrap() as usize;
+        let value = f32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().unwrap());
+        let json_model = model.to_serialized_model();
+        let meta: serde_json::Value = json_model.meta.meta.get("Dense_1").unwrap().to().unwrap();
+        let weight: NDMatrix = serde_json::from_value(meta["weight"].clone()).unwrap();
+        assert_eq!(weight.width, width);
+        assert_eq!(weight.get(0, 1), value);
+
+        assert!(bytes.len() < model.to_json().len());
+    }
+
+    #[test]
+    fn binary_file_roundtrip() {
+        let model = build_model();
+        let path = std::env::temp_dir().join("neurotick_binary_file_roundtrip.ntck");
+        model.save_binary(&path).unwrap();
+        let restored = Model::load_binary(&path, &ModelReader::default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_same_output(&model, &restored);
+    }
+
+    #[test]
+    fn binary_rejects_bad_input() {
+        let reader = ModelReader::default();
+        assert!(Model::from_binary(b"not a model at all", &reader).is_err());
+
+        let mut bytes = build_model().to_binary();
+        bytes[4] = 99;
+        assert!(Model::from_binary(&bytes, &reader).is_err());
+
+        let bytes = build_model().to_binary();
+        assert!(Model::from_binary(&bytes[..bytes.len() - 64], &reader).is_err());
+    }
+
+    #[test]
+    fn binary_rejects_corrupted_header() {
+        let reader = ModelReader::default();
+        let mut bytes = build_model().to_binary();
+        bytes[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(Model::from_binary(&bytes, &reader).is_err());
+
+        // a tensor entry pointing past the end of the address space
+        let corrupted = rewrite_header(&build_model().to_binary(), |header| {
+            header["tensors"][0]["offset"] = serde_json::json!(usize::MAX - 8);
+            header["tensors"][1]["width"] = serde_json::json!(usize::MAX);
+        });
+        let error = Model::from_binary(&corrupted, &reader).err().unwrap();
+        assert_eq!(error.to_string(), "Tensor offset or size overflow");
+    }
+
+    #[test]
+    fn binary_rejects_unresolved_refs() {
+        let reader = ModelReader::default();
+        let bytes = build_model().to_binary();
+        let edits: Vec<fn(&mut serde_json::Value)> = vec![
+            |header| header["model"]["meta"]["Dense_1"]["weight"]["tensor"] = 99.into(),
+            |header| header["model"]["meta"]["Dense_1"]["weight"]["width"] = 3.into(),
+            |header| header["model"]["meta"]["Dense_1"]["bias"]["tensor"] = 0.into(),
+        ];
+        for edit in edits {
+            let corrupted = rewrite_header(&bytes, edit);
+            let error = Model::from_binary(&corrupted, &reader).err().unwrap();
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+            assert!(error.to_string().contains("doesn't match the tensor table"));
+        }
+    }
+
+    /**
+     * Same tensor data under an edited json header, without its integrity entry as after an
+     * edit by hand
+     */
+    fn rewrite_header<F: Fn(&mut serde_json::Value)>(bytes: &[u8], edit: F) -> Vec<u8> {
+        let json_len = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+        let mut header: serde_json::Value =
+            serde_json::from_slice(&bytes[16..16 + json_len]).unwrap();
+        header
+            .as_object_mut()
+            .unwrap()
+            .remove(ModelIntegrity::INTEGRITY_KEY);
+        edit(&mut header);
+        let header_json = serde_json::to_vec(&header).unwrap();
+        let mut rewritten = bytes[0..8].to_vec();
+        rewritten.extend_from_slice(&(header_json.len() as u64).to_le_bytes());
+        rewritten.extend_from_slice(&header_json);
+        rewritten.resize(BinaryModel::data_start(header_json.len()), 0);
+        rewritten.extend_from_slice(&bytes[BinaryModel::data_start(json_len)..]);
+        return rewritten;
+    }
+}
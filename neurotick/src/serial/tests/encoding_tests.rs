@@ -0,0 +1,264 @@
+#[cfg(test)]
+mod test {
+    use std::io::ErrorKind;
+
+    use serde_json::Value;
+
+    use crate::{
+        activation::relu::ReLu,
+        builder::builder::ModelBuilder,
+        layer::{dense::Dense, input::Input},
+        matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        model::model::Model,
+        serial::{
+            integrity::ModelIntegrity,
+            matrix_serial::{
+                EncodingScope, MatrixEncoding, MatrixPack, MatrixPackError, MatrixSerial, PackData,
+            },
+            model_reader::{ModelReadError, ModelReader},
+        },
+        suppliers::suppliers::RandomUniformSupplier,
+    };
+
+    fn build_model() -> Model {
+        let input = Input::new(Shape::Const(64), Shape::Repeat);
+        let output = Dense::builder(32, || &input)
+            .with_activation(ReLu::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        return ModelBuilder::from_straight(input, output).build();
+    }
+
+    fn max_difference(a: &NDMatrix, b: &NDMatrix) -> f32 {
+        return a
+            .iter_all()
+            .zip(b.iter_all())
+            .map(|(x, y)| (x - y).abs())
+            .fold(0.0, f32::max);
+    }
+
+    #[test]
+    fn encoding_matrix_roundtrip() {
+        let matrix = NDMatrix::from_supply(7, 5, RandomUniformSupplier::new(1.0, -1.0));
+        let cases = [
+            (MatrixEncoding::F32, 0.0),
+            (MatrixEncoding::Zstd, 0.0),
+            (MatrixEncoding::Deflate, 0.0),
+            (MatrixEncoding::F16, 1e-3),
+            (MatrixEncoding::Bf16, 1e-2),
+        ];
+        for (encoding, tolerance) in cases {
+            let pack = matrix.pack_as(encoding);
+            let json = serde_json::to_value(&pack).unwrap();
+            assert_eq!(json.get("encoding").is_none(), encoding.is_f32());
+
+            let unpacked = NDMatrix::unpack(&serde_json::from_value(json).unwrap()).unwrap();
+            assert_eq!((unpacked.width, unpacked.height), (7, 5));
+            assert!(
+                max_difference(&matrix, &unpacked) <= tolerance,
+                "{:?}",
+                encoding
+            );
+        }
+
+        // the scope applies to pack and to the serde form of matrices
+        let scope = EncodingScope::open(MatrixEncoding::Bf16);
+        let json = serde_json::to_value(&matrix).unwrap();
+        drop(scope);
+        assert_eq!(json["encoding"], "bf16");
+        assert!(matrix.pack().encoding.is_f32());
+    }
+
+    #[test]
+    fn encoding_invalid_compressed() {
+        let matrix = NDMatrix::from_supply(4, 4, RandomUniformSupplier::new(1.0, -1.0));
+        let mut pack = NDMatrix::constant(8, 4, 0.5).pack_as(MatrixEncoding::Zstd);
+        // a stream that inflates beyond width x height
+        pack.width = 4;
+        pack.checksum = None;
+        assert!(NDMatrix::unpack(&pack).is_err());
+
+        let mut pack = matrix.pack_as(MatrixEncoding::Deflate);
+        pack.encoding = MatrixEncoding::F16;
+        pack.checksum = None;
+        assert!(matches!(
+            NDMatrix::unpack(&pack),
+            Err(MatrixPackError::Length { expected: 32, .. })
+        ));
+    }
+
+    #[test]
+    fn encoding_rejects_overflow() {
+        let mut pack = NDMatrix::constant(2, 2, 0.5).pack_as(MatrixEncoding::F16);
+        pack.width = usize::MAX;
+        pack.checksum = None;
+        assert!(matches!(
+            NDMatrix::unpack(&pack),
+            Err(MatrixPackError::Encoding(_))
+        ));
+        pack.width = usize::MAX / 2 + 1;
+        pack.height = 1;
+        assert!(matches!(
+            NDMatrix::unpack(&pack),
+            Err(MatrixPackError::Encoding(_))
+        ));
+    }
+
+    #[test]
+    fn encoding_rejects_oversized_shape() {
+        // the claimed shape is not allocated before the data is inflated
+        let huge = 1usize << 36;
+        for encoding in [MatrixEncoding::Zstd, MatrixEncoding::Deflate] {
+            let mut pack = NDMatrix::constant(2, 2, 0.5).pack_as(encoding);
+            pack.width = huge;
+            pack.height = 1;
+            assert!(matches!(
+                NDMatrix::unpack(&pack),
+                Err(MatrixPackError::Length { expected, found: 16 }) if expected == huge * 4
+            ));
+        }
+
+        let mut document: Value =
+            serde_json::from_str(&build_model().to_json_with(MatrixEncoding::Zstd)).unwrap();
+        document
+            .as_object_mut()
+            .unwrap()
+            .remove(ModelIntegrity::INTEGRITY_KEY);
+        document["meta"]["Dense_1"]["weight"]["width"] = Value::from(huge);
+        let error = ModelReader::default()
+            .read_json(&document.to_string())
+            .err()
+            .unwrap();
+        assert!(matches!(error, ModelReadError::Tensor { .. }));
+        assert_eq!(std::io::Error::from(error).kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn encoding_model_json() {
+        let model = build_model();
+        let reader = ModelReader::default();
+        let input = NDMatrix::from_supply(64, 3, RandomUniformSupplier::new(1.0, -1.0));
+        let expected = model.propagate_single(input.clone());
+
+        let full = model.to_json();
+        let half = model.to_json_with(MatrixEncoding::F16);
+        assert!(half.len() < full.len() * 2 / 3);
+        let document: Value = serde_json::from_str(&half).unwrap();
+        assert_eq!(document["meta"]["Dense_1"]["weight"]["encoding"], "f16");
+        let restored = Model::from_json(&half, &reader).unwrap();
+        assert!(max_difference(&expected, &restored.propagate_single(input.clone())) < 0.05);
+
+        let compressed = model.to_json_with(MatrixEncoding::Zstd);
+        let restored = Model::from_json(&compressed, &reader).unwrap();
+        assert_eq!(expected.values, restored.propagate_single(input).values);
+
+        // the encoding only lasts for the call
+        assert_eq!(model.to_json(), full);
+    }
+
+    #[test]
+    fn encoding_text_matrix() {
+        let matrix = NDMatrix::from_supply(3, 2, RandomUniformSupplier::new(1.0, -1.0));
+        let pack = matrix.pack_as(MatrixEncoding::Text);
+        let json = serde_json::to_value(&pack).unwrap();
+        assert_eq!(json["encoding"], "text");
+        assert!(json.get("checksum").is_none());
+        let rows = json["data"].as_array().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1][2].as_f64().unwrap() as f32, matrix.values[[1, 2]]);
+        // full precision reads back exactly
+        let unpacked = NDMatrix::unpack(&serde_json::from_value(json).unwrap()).unwrap();
+        assert_eq!(unpacked.values, matrix.values);
+
+        let scope = EncodingScope::text(2);
+        let rounded = matrix.pack();
+        drop(scope);
+        let PackData::Rows(rows) = &rounded.data else {
+            panic!("text packs are rows");
+        };
+        assert!(rows
+            .iter()
+            .flatten()
+            .all(|v| (v * 100.0 - (v * 100.0).round()).abs() < 1e-9));
+        let unpacked = NDMatrix::unpack(&rounded).unwrap();
+        assert!(max_difference(&matrix, &unpacked) <= 0.005 + 1e-6);
+
+        let mut pack = matrix.pack_as(MatrixEncoding::Text);
+        pack.data = PackData::Rows(vec![vec![0.5; 3]]);
+        assert!(matches!(
+            NDMatrix::unpack(&pack),
+            Err(MatrixPackError::Encoding(_))
+        ));
+        pack.data = PackData::Rows(vec![vec![0.5; 3], vec![0.5; 2]]);
+        assert!(matches!(
+            NDMatrix::unpack(&pack),
+            Err(MatrixPackError::Encoding(_))
+        ));
+        // base64 data is not text
+        pack.data = matrix.pack().data;
+        assert!(NDMatrix::unpack(&pack).is_err());
+    }
+
+    #[test]
+    fn encoding_text_non_finite() {
+        let values = vec![f32::NAN, f32::INFINITY, f32::NEG_INFINITY, 0.25];
+        let matrix = NDMatrix::from_raw_vec(2, 2, values);
+        for decimals in [None, Some(2)] {
+            let scope = decimals.map(EncodingScope::text);
+            let pack = matrix.pack_as(MatrixEncoding::Text);
+            drop(scope);
+            let json = serde_json::to_string(&pack).unwrap();
+            assert!(
+                json.contains(r#"[["NaN","inf"],["-inf",0.25]]"#),
+                "{}",
+                json
+            );
+
+            let unpacked = NDMatrix::unpack(&serde_json::from_str(&json).unwrap()).unwrap();
+            assert!(unpacked.values[[0, 0]].is_nan());
+            assert_eq!(unpacked.values[[0, 1]], f32::INFINITY);
+            assert_eq!(unpacked.values[[1, 0]], f32::NEG_INFINITY);
+            assert_eq!(unpacked.values[[1, 1]], 0.25);
+        }
+
+        let pack = matrix.pack_as(MatrixEncoding::Text);
+        let json = serde_json::to_string(&pack)
+            .unwrap()
+            .replace("-inf", "-infinity");
+        assert!(serde_json::from_str::<MatrixPack>(&json).is_err());
+    }
+
+    #[test]
+    fn encoding_text_model() {
+        let model = build_model();
+        let reader = ModelReader::default();
+        let input = NDMatrix::from_supply(64, 3, RandomUniformSupplier::new(1.0, -1.0));
+        let expected = model.propagate_single(input.clone());
+
+        let text = model.to_json_pretty_with(MatrixEncoding::Text);
+        let restored = Model::from_json(&text, &reader).unwrap();
+        assert_eq!(
+            expected.values,
+            restored.propagate_single(input.clone()).values
+        );
+        let streamed = reader.read_stream(text.as_bytes()).unwrap();
+        assert_eq!(
+            expected.values,
+            streamed.propagate_single(input.clone()).values
+        );
+
+        let rounded = model.to_json_pretty_text(3);
+        let document: Value = serde_json::from_str(&rounded).unwrap();
+        let weight = &document["meta"]["Dense_1"]["weight"];
+        assert_eq!(weight["data"].as_array().unwrap().len(), 64);
+        // one short number per line
+        assert!(rounded
+            .lines()
+            .filter(|line| !line.contains("\"checksum\""))
+            .all(|line| line.len() < 40));
+        let restored = Model::from_json(&rounded, &reader).unwrap();
+        assert!(max_difference(&expected, &restored.propagate_single(input)) < 0.05);
+
+        assert!(!model.to_json_pretty().contains("\"text\""));
+    }
+}
@@ -0,0 +1,228 @@
+#[cfg(test)]
+mod test {
+    use std::io::ErrorKind;
+
+    use serde_json::Value;
+
+    use crate::{
+        activation::relu::ReLu,
+        builder::builder::ModelBuilder,
+        layer::{dense::Dense, input::Input},
+        matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        model::model::Model,
+        serial::{
+            binary_serial::BinaryModel,
+            integrity::{IntegrityError, ModelIntegrity},
+            matrix_serial::{MatrixPackError, MatrixSerial, PackData},
+            model_reader::{ModelReadError, ModelReader},
+        },
+        suppliers::suppliers::RandomUniformSupplier,
+    };
+
+    fn build_model() -> Model {
+        let input = Input::new(Shape::Const(3), Shape::Repeat);
+        let output = Dense::builder(4, || &input)
+            .with_activation(ReLu::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        return ModelBuilder::from_straight(input, output).build();
+    }
+
+    #[test]
+    fn integrity_matrix_pack() {
+        let matrix = NDMatrix::from_supply(3, 2, RandomUniformSupplier::new(1.0, -1.0));
+        let mut pack = matrix.pack();
+        assert_eq!(NDMatrix::unpack(&pack).unwrap().values, matrix.values);
+
+        let PackData::Encoded(data) = pack.data.clone() else {
+            panic!("f32 packs are base64");
+        };
+        pack.data = PackData::Encoded(data[..data.len() - 4].to_string());
+        assert!(matches!(
+            NDMatrix::unpack(&pack),
+            Err(MatrixPackError::Checksum { .. })
+        ));
+        let checksum = pack.checksum.take();
+        assert!(matches!(
+            NDMatrix::unpack(&pack),
+            Err(MatrixPackError::Length { expected: 24, .. })
+        ));
+        pack.checksum = checksum;
+
+        // same length, other values
+        pack.data = PackData::Encoded(data.replacen(
+            &data[0..1],
+            if &data[0..1] == "A" { "B" } else { "A" },
+            1,
+        ));
+        assert!(matches!(
+            NDMatrix::unpack(&pack),
+            Err(MatrixPackError::Checksum { .. })
+        ));
+
+        pack.data = PackData::Encoded("not base64!".to_string());
+        assert!(matches!(
+            NDMatrix::unpack(&pack),
+            Err(MatrixPackError::Encoding(_))
+        ));
+    }
+
+    #[test]
+    fn integrity_document_checksum() {
+        let model = build_model();
+        let reader = ModelReader::default();
+        let json = model.to_json();
+        let document: Value = serde_json::from_str(&json).unwrap();
+        assert!(document[ModelIntegrity::INTEGRITY_KEY]["checksum"].is_string());
+        assert!(Model::from_json(&model.to_json_pretty(), &reader).is_ok());
+
+        let mut tampered = document.clone();
+        tampered["meta"]["Dense_1"]["activation"]["cap"] = Value::from(1.0);
+        let result = Model::from_json(&tampered.to_string(), &reader);
+        assert!(matches!(
+            result,
+            Err(ModelReadError::Integrity(IntegrityError::Checksum))
+        ));
+
+        // without a checksum, e.g. an older file, a truncated tensor is still an error
+        let mut truncated = document;
+        truncated
+            .as_object_mut()
+            .unwrap()
+            .remove(ModelIntegrity::INTEGRITY_KEY);
+        let data = &mut truncated["meta"]["Dense_1"]["weight"]["data"];
+        let text = data.as_str().unwrap().to_string();
+        *data = Value::from(&text[..text.len() - 4]);
+        assert!(matches!(
+            Model::from_json(&truncated.to_string(), &reader),
+            Err(ModelReadError::Tensor { ref name, .. }) if name == "Dense_1.weight"
+        ));
+    }
+
+    #[test]
+    fn integrity_signature() {
+        let model = build_model();
+        let key = b"inference-service-key";
+        let signed = model.to_json_signed(key);
+
+        let mut reader = ModelReader::default();
+        reader.require_signature(key);
+        let restored = Model::from_json(&signed, &reader).unwrap();
+        let input = NDMatrix::from_supply(3, 2, RandomUniformSupplier::new(1.0, -1.0));
+        assert_eq!(
+            model.propagate_single(input.clone()).values,
+            restored.propagate_single(input).values
+        );
+        // readers without a key still check the checksum
+        assert!(Model::from_json(&signed, &ModelReader::default()).is_ok());
+
+        assert!(matches!(
+            Model::from_json(&model.to_json(), &reader),
+            Err(ModelReadError::Integrity(IntegrityError::Unsigned))
+        ));
+        let mut other = ModelReader::default();
+        other.require_signature(b"other-key");
+        assert!(matches!(
+            Model::from_json(&signed, &other),
+            Err(ModelReadError::Integrity(IntegrityError::Signature))
+        ));
+
+        // resealing an edited document without the key doesn't restore the signature
+        let mut forged: Value = serde_json::from_str(&signed).unwrap();
+        forged["meta"]["Dense_1"]["activation"]["cap"] = Value::from(1.0);
+        ModelIntegrity::seal(&mut forged, None);
+        assert!(matches!(
+            Model::from_json(&forged.to_string(), &reader),
+            Err(ModelReadError::Integrity(IntegrityError::Unsigned))
+        ));
+    }
+
+    #[test]
+    fn integrity_binary_tensor() {
+        let model = build_model();
+        let mut bytes = model.to_binary();
+        let reader = ModelReader::default();
+        assert!(Model::from_binary(&bytes, &reader).is_ok());
+
+        let json_len = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+        let first = BinaryModel::data_start(json_len);
+        bytes[first] ^= 0xff;
+        let result = Model::from_binary(&bytes, &reader);
+        assert_eq!(result.err().unwrap().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn integrity_binary_signature() {
+        let model = build_model();
+        let key = b"inference-service-key";
+        let signed = model.to_binary_signed(key);
+
+        let mut reader = ModelReader::default();
+        reader.require_signature(key);
+        let restored = Model::from_binary(&signed, &reader).unwrap();
+        let input = NDMatrix::from_supply(3, 2, RandomUniformSupplier::new(1.0, -1.0));
+        assert_eq!(
+            model.propagate_single(input.clone()).values,
+            restored.propagate_single(input).values
+        );
+        assert!(Model::from_binary(&signed, &ModelReader::default()).is_ok());
+
+        let error = Model::from_binary(&model.to_binary(), &reader)
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), IntegrityError::Unsigned.to_string());
+        let mut other = ModelReader::default();
+        other.require_signature(b"other-key");
+        let error = Model::from_binary(&signed, &other).err().unwrap();
+        assert_eq!(error.to_string(), IntegrityError::Signature.to_string());
+    }
+
+    #[test]
+    fn integrity_binary_tensor_table() {
+        let reader = ModelReader::default();
+        let bytes = build_model().to_binary();
+
+        // the table points the weight at the bias bytes
+        let tampered = rewrite_header(&bytes, |header| {
+            let bias = header["tensors"][1].clone();
+            header["tensors"][0] = bias;
+            header["model"]["meta"]["Dense_1"]["weight"]["width"] =
+                header["tensors"][0]["width"].clone();
+            header["model"]["meta"]["Dense_1"]["weight"]["height"] =
+                header["tensors"][0]["height"].clone();
+            header["model"]["meta"]["Dense_1"]["bias"]["tensor"] = Value::from(0);
+        });
+        let error = Model::from_binary(&tampered, &reader).err().unwrap();
+        assert_eq!(error.to_string(), IntegrityError::Checksum.to_string());
+
+        // tensor bytes changed along with their crc32 are still caught by the checksum
+        let json_len = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+        let data_start = BinaryModel::data_start(json_len);
+        let mut flipped = bytes.clone();
+        flipped[data_start] ^= 0xff;
+        let tampered = rewrite_header(&flipped, |header| {
+            let width = header["tensors"][0]["width"].as_u64().unwrap() as usize;
+            let height = header["tensors"][0]["height"].as_u64().unwrap() as usize;
+            let raw = &flipped[data_start..data_start + width * height * 4];
+            header["tensors"][0]["checksum"] = Value::from(crc32fast::hash(raw));
+        });
+        let error = Model::from_binary(&tampered, &reader).err().unwrap();
+        assert_eq!(error.to_string(), IntegrityError::Checksum.to_string());
+    }
+
+    /**
+     * Same tensor data under an edited json header, keeping its integrity entry
+     */
+    fn rewrite_header<F: Fn(&mut Value)>(bytes: &[u8], edit: F) -> Vec<u8> {
+        let json_len = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+        let mut header: Value = serde_json::from_slice(&bytes[16..16 + json_len]).unwrap();
+        edit(&mut header);
+        let header_json = serde_json::to_vec(&header).unwrap();
+        let mut rewritten = bytes[0..8].to_vec();
+        rewritten.extend_from_slice(&(header_json.len() as u64).to_le_bytes());
+        rewritten.extend_from_slice(&header_json);
+        rewritten.resize(BinaryModel::data_start(header_json.len()), 0);
+        rewritten.extend_from_slice(&bytes[BinaryModel::data_start(json_len)..]);
+        return rewritten;
+    }
+}
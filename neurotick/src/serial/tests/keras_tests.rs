@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use indexmap::IndexMap;
+    use serde_json::{json, Value};
+
+    use crate::{
+        activation::{
+            abs::Activation, lerelu::LeakyReLu, relu::ReLu, sigmoid::Sigmoid, softmax::SoftMax,
+            tanh::Tanh,
+        },
+        matrix::nmatrix::NDMatrix,
+        model::model::Model,
+        serial::{keras::keras_error::KerasError, npy_serial::Npy},
+        suppliers::suppliers::RandomUniformSupplier,
+    };
+
+    fn random(width: usize, height: usize) -> NDMatrix {
+        return NDMatrix::from_supply(width, height, RandomUniformSupplier::new(1.0, -1.0));
+    }
+
+    /**
+     * x . kernel + bias, then the activation
+     */
+    fn dense(
+        x: &NDMatrix,
+        kernel: &NDMatrix,
+        bias: Option<&NDMatrix>,
+        act: &dyn Activation,
+    ) -> NDMatrix {
+        let mut values = x.values.dot(&kernel.values);
+        if let Some(bias) = bias {
+            values = values + &bias.values;
+        }
+        return act.apply(&NDMatrix::with(kernel.width, x.height, values));
+    }
+
+    fn assert_close(a: &NDMatrix, b: &NDMatrix) {
+        assert_eq!((a.width, a.height), (b.width, b.height));
+        a.iter_all()
+            .zip(b.iter_all())
+            .for_each(|(x, y)| assert!((x - y).abs() < 1e-5, "{} != {}", x, y));
+    }
+
+    fn sequential_config() -> Value {
+        return json!({
+            "class_name": "Sequential",
+            "config": {
+                "name": "sequential",
+                "layers": [
+                    {"class_name": "InputLayer", "config": {"batch_input_shape": [null, 4], "dtype": "float32", "name": "dense_input"}},
+                    {"class_name": "Dense", "config": {"name": "dense", "units": 5, "activation": "relu", "use_bias": true}},
+                    {"class_name": "Dense", "config": {"name": "dense_1", "units": 3, "activation": "softmax", "use_bias": false}}
+                ]
+            },
+            "keras_version": "2.12.0",
+            "backend": "tensorflow"
+        });
+    }
+
+    /**
+     * keras 3 call arguments
+     */
+    fn inbound(names: &[&str]) -> Value {
+        let tensors: Vec<Value> = names
+            .iter()
+            .map(|n| json!({"class_name": "__keras_tensor__", "config": {"dtype": "float32", "keras_history": [n, 0, 0]}}))
+            .collect();
+        let args = match tensors.len() {
+            1 => tensors[0].clone(),
+            _ => Value::from(tensors),
+        };
+        return json!([{"args": [args], "kwargs": {}}]);
+    }
+
+    #[test]
+    fn keras_sequential() {
+        let mut weights: IndexMap<String, NDMatrix> = IndexMap::new();
+        weights.insert("dense/kernel:0".to_string(), random(5, 4));
+        weights.insert("dense/bias:0".to_string(), random(5, 1));
+        weights.insert("dense_1/kernel:0".to_string(), random(3, 5));
+        weights.insert("optimizer/iter:0".to_string(), random(1, 1));
+
+        let json_path = std::env::temp_dir().join("neurotick_keras_sequential.json");
+        let npz_path = std::env::temp_dir().join("neurotick_keras_sequential.npz");
+        std::fs::write(&json_path, sequential_config().to_string()).unwrap();
+        Npy::write_npz(&npz_path, &weights).unwrap();
+        let model = Model::load_keras(&json_path, &npz_path).unwrap();
+        std::fs::remove_file(&json_path).unwrap();
+        std::fs::remove_file(&npz_path).unwrap();
+
+        let x = random(4, 3);
+        let hidden = dense(
+            &x,
+            &weights["dense/kernel:0"],
+            Some(&weights["dense/bias:0"]),
+            &ReLu { cap: f32::MAX },
+        );
+        let expected = dense(
+            &hidden,
+            &weights["dense_1/kernel:0"],
+            None,
+            &SoftMax::default(),
+        );
+        assert_close(&model.propagate_single(x), &expected);
+    }
+
+    #[test]
+    fn keras_functional() {
+        let config = json!({
+            "module": "keras",
+            "class_name": "Functional",
+            "config": {
+                "name": "functional",
+                "layers": [
+                    {"class_name": "InputLayer", "config": {"batch_shape": [null, 3], "name": "left"}, "name": "left", "inbound_nodes": []},
+                    {"class_name": "InputLayer", "config": {"batch_shape": [null, 2], "name": "right"}, "name": "right", "inbound_nodes": []},
+                    {"class_name": "Concatenate", "config": {"name": "concatenate", "axis": -1}, "name": "concatenate", "inbound_nodes": inbound(&["left", "right"])},
+                    {"class_name": "Flatten", "config": {"name": "flatten"}, "name": "flatten", "inbound_nodes": inbound(&["concatenate"])},
+                    {"class_name": "Dense", "config": {"name": "dense", "units": 4, "use_bias": true,
+                        "activation": {"module": "keras.activations", "class_name": "function", "config": "tanh", "registered_name": "function"}},
+                        "name": "dense", "inbound_nodes": inbound(&["flatten"])},
+                    {"class_name": "LeakyReLU", "config": {"name": "leaky", "negative_slope": 0.1}, "name": "leaky", "inbound_nodes": inbound(&["dense"])},
+                    {"class_name": "Activation", "config": {"name": "act", "activation": "sigmoid"}, "name": "act", "inbound_nodes": inbound(&["dense"])}
+                ],
+                "input_layers": [["left", 0, 0], ["right", 0, 0]],
+                "output_layers": [["leaky", 0, 0], ["act", 0, 0]]
+            }
+        });
+        let mut weights: IndexMap<String, NDMatrix> = IndexMap::new();
+        weights.insert("dense/kernel".to_string(), random(4, 5));
+        weights.insert("dense/bias".to_string(), random(4, 1));
+        let model = Model::from_keras(&config.to_string(), &weights).unwrap();
+
+        let mut inputs: Vec<&String> = model.input_layer_to_data_name.values().collect();
+        inputs.sort();
+        assert_eq!(inputs, vec!["left", "right"]);
+        // flatten of a 2d tensor is a passthrough
+        assert!(!model.builder_ref.keys().any(|k| k.starts_with("Flatten")));
+
+        let left = random(3, 2);
+        let right = random(2, 2);
+        let outputs = model.propagate(&HashMap::from([
+            ("left".to_string(), left.clone()),
+            ("right".to_string(), right.clone()),
+        ]));
+        let x = NDMatrix::with(
+            5,
+            2,
+            ndarray::concatenate![ndarray::Axis(1), left.values, right.values],
+        );
+        let hidden = dense(
+            &x,
+            &weights["dense/kernel"],
+            Some(&weights["dense/bias"]),
+            &Tanh,
+        );
+        assert_close(&outputs["leaky"], &LeakyReLu { beta: 0.1 }.apply(&hidden));
+        assert_close(&outputs["act"], &Sigmoid.apply(&hidden));
+    }
+
+    #[test]
+    fn keras_errors() {
+        let mut weights: IndexMap<String, NDMatrix> = IndexMap::new();
+        weights.insert("dense/kernel".to_string(), random(5, 4));
+        weights.insert("dense/bias".to_string(), random(5, 1));
+        let result = Model::from_keras(&sequential_config().to_string(), &weights);
+        assert!(matches!(result, Err(KerasError::Invalid(m)) if m.contains("dense_1/kernel")));
+
+        // wrong kernel shape
+        weights.insert("dense_1/kernel".to_string(), random(3, 4));
+        let result = Model::from_keras(&sequential_config().to_string(), &weights);
+        assert!(matches!(result, Err(KerasError::Invalid(_))));
+
+        let mut config = sequential_config();
+        config["config"]["layers"][2] = json!({"class_name": "Conv2D", "config": {"name": "conv"}});
+        let result = Model::from_keras(&config.to_string(), &weights);
+        assert!(matches!(result, Err(KerasError::Unsupported(m)) if m.contains("Conv2D")));
+    }
+}
@@ -1 +1,13 @@
+mod binary_tests;
+mod encoding_tests;
+mod integrity_tests;
+mod keras_tests;
+mod npy_tests;
+mod onnx_import_tests;
+mod onnx_tests;
+mod roundtrip_tests;
+mod safetensors_tests;
+mod stream_tests;
 mod test;
+mod version_tests;
+mod weight_tests;
@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod test {
+    use std::io::ErrorKind;
+
+    use crate::{
+        activation::relu::ReLu,
+        builder::builder::ModelBuilder,
+        layer::{concat::Concat, dense::Dense, direct::Direct, input::Input},
+        matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        model::model::Model,
+        serial::{model_reader::ModelReader, model_weights::ModelWeights, npy_serial::Npy},
+        suppliers::suppliers::RandomUniformSupplier,
+    };
+
+    fn build_model(dense_features: usize) -> Model {
+        let input = Input::new(Shape::Const(4), Shape::Repeat);
+        let d1 = Dense::builder(dense_features, || &input)
+            .with_activation(ReLu::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let d2 = Direct::builder(|| &input)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let concat = Concat::new(|| vec![&d1, &d2]);
+        let output = Dense::new(2, || &concat);
+        return ModelBuilder::from_straight(input, output).build();
+    }
+
+    fn npy_bytes(header: &str, data: Vec<u8>) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Npy::MAGIC.to_vec();
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend(data);
+        return bytes;
+    }
+
+    #[test]
+    fn npy_layout() {
+        let matrix = NDMatrix::from_raw_vec(3, 2, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let bytes = Npy::encode(&matrix);
+
+        assert_eq!(&bytes[0..6], Npy::MAGIC);
+        assert_eq!(&bytes[6..8], &[1, 0]);
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        let data_start = 10 + header_len;
+        assert_eq!(data_start % Npy::HEADER_ALIGNMENT, 0);
+        let header = std::str::from_utf8(&bytes[10..data_start]).unwrap();
+        assert!(header.contains("'descr': '<f4'"));
+        assert!(header.contains("'shape': (2, 3)"));
+        assert!(header.ends_with('\n'));
+
+        // row-major (height, width), same as NDMatrix::values
+        let second = f32::from_le_bytes(bytes[data_start + 4..data_start + 8].try_into().unwrap());
+        assert_eq!(second, 2.0);
+        assert_eq!(bytes.len(), data_start + 6 * 4);
+
+        let decoded = Npy::decode(&bytes).unwrap();
+        assert_eq!(decoded.values, matrix.values);
+    }
+
+    #[test]
+    fn npy_numpy_variants() {
+        // np.asfortranarray(np.array([[1, 2, 3], [4, 5, 6]], dtype=np.float64))
+        let column_major: Vec<u8> = [1.0f64, 4.0, 2.0, 5.0, 3.0, 6.0]
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+        let header = "{'descr': '<f8', 'fortran_order': True, 'shape': (2, 3), }\n";
+        let decoded = Npy::decode(&npy_bytes(header, column_major)).unwrap();
+        let expected = NDMatrix::from_raw_vec(3, 2, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(decoded.values, expected.values);
+
+        let vector: Vec<u8> = [0.5f32, -1.5]
+            .iter()
+            .flat_map(|f| f.to_be_bytes())
+            .collect();
+        let header = "{'descr': '>f4', 'fortran_order': False, 'shape': (2,), }\n";
+        let decoded = Npy::decode(&npy_bytes(header, vector)).unwrap();
+        assert_eq!((decoded.width, decoded.height), (2, 1));
+        assert_eq!(decoded.get(0, 1), -1.5);
+
+        let header = "{'descr': '<i8', 'fortran_order': False, 'shape': (1,), }\n";
+        let result = Npy::decode(&npy_bytes(header, vec![0; 8]));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn npy_file() {
+        let matrix = NDMatrix::from_supply(5, 3, RandomUniformSupplier::new(1.0, -1.0));
+        let path = std::env::temp_dir().join("neurotick_npy_file.npy");
+        matrix.write_npy(&path).unwrap();
+        let read = NDMatrix::read_npy(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.values, matrix.values);
+    }
+
+    #[test]
+    fn npz_weights_roundtrip() {
+        let source = build_model(6);
+        let mut target = build_model(6);
+        let path = std::env::temp_dir().join("neurotick_npz_weights_roundtrip.npz");
+        source.export_weights_npz(&path).unwrap();
+
+        let arrays = Npy::read_npz(&path).unwrap();
+        assert!(arrays.contains_key("Dense_1.weight"));
+        assert!(arrays.contains_key("Direct_2.bias"));
+        assert_eq!(arrays.len(), ModelWeights::collect(&source).len());
+
+        target
+            .import_weights_npz(&path, &ModelReader::default())
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let input = NDMatrix::from_supply(4, 3, RandomUniformSupplier::new(1.0, -1.0));
+        assert_eq!(
+            source.propagate_single(input.clone()).values,
+            target.propagate_single(input).values
+        );
+    }
+
+    #[test]
+    fn npz_weights_shape_mismatch() {
+        let source = build_model(6);
+        let mut target = build_model(5);
+        let path = std::env::temp_dir().join("neurotick_npz_weights_shape_mismatch.npz");
+        source.export_weights_npz(&path).unwrap();
+
+        let before = ModelWeights::collect(&target);
+        let result = target.import_weights_npz(&path, &ModelReader::default());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // nothing is applied when a weight doesn't fit
+        let after = ModelWeights::collect(&target);
+        before
+            .iter()
+            .for_each(|(name, matrix)| assert_eq!(matrix.values, after[name].values));
+    }
+
+    #[test]
+    fn npy_rejects_overflow() {
+        let header = format!(
+            "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, 2), }}\n",
+            usize::MAX
+        );
+        let result = Npy::decode(&npy_bytes(&header, vec![0; 8]));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        let header = format!(
+            "{{'descr': '<f8', 'fortran_order': False, 'shape': ({},), }}\n",
+            usize::MAX / 4
+        );
+        let result = Npy::decode(&npy_bytes(&header, vec![0; 8]));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,216 @@
+#[cfg(test)]
+mod test {
+    use prost::Message;
+
+    use crate::{
+        activation::{
+            abs::Activation, lerelu::LeakyReLu, relu::ReLu, sigmoid::Sigmoid, softmax::SoftMax,
+            tanh::Tanh,
+        },
+        builder::builder::ModelBuilder,
+        layer::{concat::Concat, dense::Dense, direct::Direct, input::Input},
+        matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        model::model::Model,
+        serial::onnx::{
+            onnx_error::OnnxError,
+            proto::{
+                AttributeProto, AttributeType, DataType, Dimension, DimensionValue, GraphProto,
+                ModelProto, NodeProto, TensorProto, TensorShapeProto, TensorTypeProto, TypeProto,
+                TypeValue, ValueInfoProto,
+            },
+        },
+        suppliers::suppliers::RandomUniformSupplier,
+    };
+
+    fn build_model() -> Model {
+        let input = Input::new(Shape::Const(6), Shape::Repeat);
+        let d1 = Dense::builder(8, || &input)
+            .with_activation(ReLu { cap: 0.5 })
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let d2 = Direct::builder(|| &input)
+            .with_activation(LeakyReLu { beta: 0.2 })
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let concat = Concat::new(|| vec![&d1, &d2]);
+        let d3 = Dense::builder(5, || &concat)
+            .with_activation(Sigmoid::default())
+            .build();
+        let output = Dense::builder(3, || &d3)
+            .with_activation(SoftMax::default())
+            .build();
+        return ModelBuilder::from_straight(input, output).build();
+    }
+
+    fn node(
+        op_type: &str,
+        inputs: &[&str],
+        output: &str,
+        attribute: Vec<AttributeProto>,
+    ) -> NodeProto {
+        return NodeProto {
+            name: output.to_string(),
+            op_type: op_type.to_string(),
+            input: inputs.iter().map(|i| i.to_string()).collect(),
+            output: vec![output.to_string()],
+            attribute,
+            ..Default::default()
+        };
+    }
+
+    fn tensor(name: &str, dims: Vec<i64>, float_data: Vec<f32>) -> TensorProto {
+        return TensorProto {
+            name: name.to_string(),
+            dims,
+            data_type: DataType::FLOAT,
+            float_data,
+            ..Default::default()
+        };
+    }
+
+    fn value_info(name: &str, dims: Vec<DimensionValue>) -> ValueInfoProto {
+        let dim = dims
+            .into_iter()
+            .map(|d| Dimension {
+                value: Some(d),
+                ..Default::default()
+            })
+            .collect();
+        return ValueInfoProto {
+            name: name.to_string(),
+            r#type: Some(TypeProto {
+                value: Some(TypeValue::TensorType(TensorTypeProto {
+                    elem_type: DataType::FLOAT,
+                    shape: Some(TensorShapeProto { dim }),
+                })),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+    }
+
+    fn proto(nodes: Vec<NodeProto>, initializer: Vec<TensorProto>, features: i64) -> ModelProto {
+        let graph = GraphProto {
+            node: nodes,
+            initializer,
+            input: vec![value_info(
+                "obs",
+                vec![
+                    DimensionValue::DimParam("batch".to_string()),
+                    DimensionValue::DimValue(features),
+                ],
+            )],
+            output: vec![value_info("action", vec![])],
+            ..Default::default()
+        };
+        return ModelProto {
+            ir_version: 8,
+            graph: Some(graph),
+            ..Default::default()
+        };
+    }
+
+    #[test]
+    fn onnx_import_roundtrip() {
+        let model = build_model();
+        let imported = Model::from_onnx(&model.to_onnx().unwrap()).unwrap();
+
+        let types =
+            |m: &Model| -> Vec<String> { m.builder_ref.values().map(|n| n.type_name()).collect() };
+        assert_eq!(types(&model), types(&imported));
+        assert_eq!(
+            imported
+                .input_layer_to_data_name
+                .values()
+                .collect::<Vec<_>>(),
+            vec![ModelBuilder::SINGLE_IO]
+        );
+        assert_eq!(
+            imported
+                .output_layer_to_data_name
+                .values()
+                .collect::<Vec<_>>(),
+            vec![ModelBuilder::SINGLE_IO]
+        );
+
+        let input = NDMatrix::from_supply(6, 4, RandomUniformSupplier::new(1.0, -1.0));
+        let expected = model.propagate_single(input.clone());
+        let actual = imported.propagate_single(input);
+        assert_eq!(expected.values, actual.values);
+    }
+
+    #[test]
+    fn onnx_import_gemm_policy() {
+        let transposed = AttributeProto {
+            name: "transB".to_string(),
+            r#type: AttributeType::INT,
+            i: 1,
+            ..Default::default()
+        };
+        let nodes = vec![
+            node("Gemm", &["obs", "w", "b"], "hidden", vec![transposed]),
+            node("Relu", &["hidden"], "relu", vec![]),
+            node("Flatten", &["relu"], "flat", vec![]),
+            node("Add", &["flat", "shift"], "shifted", vec![]),
+            node("Tanh", &["shifted"], "action", vec![]),
+        ];
+        // transB: [out, in] = [3, 2]
+        let initializer = vec![
+            tensor("w", vec![3, 2], vec![1.0, 0.0, 0.0, 1.0, 1.0, -1.0]),
+            tensor("b", vec![3], vec![0.0, 0.5, -10.0]),
+            tensor("shift", vec![], vec![0.25]),
+        ];
+        let model = Model::from_onnx(&proto(nodes, initializer, 2).encode_to_vec()).unwrap();
+
+        // Flatten on the 2d tensor keeps rows, the Dense already has a bias so Add becomes a Direct
+        let types: Vec<String> = model.builder_ref.values().map(|n| n.type_name()).collect();
+        assert_eq!(types, vec![Input::NAME, Dense::NAME, Direct::NAME]);
+        assert_eq!(
+            model.input_layer_to_data_name.get("Input_0").unwrap(),
+            "obs"
+        );
+        assert_eq!(
+            model.output_layer_to_data_name.get("Direct_2").unwrap(),
+            "action"
+        );
+
+        let input = NDMatrix::from_raw_vec(2, 1, vec![2.0, 3.0]);
+        let output = model
+            .propagate(&[("obs".to_string(), input)].into_iter().collect())
+            .remove("action")
+            .unwrap();
+        let expected = NDMatrix::from_raw_vec(3, 1, vec![2.0 + 0.25, 3.5 + 0.25, 0.25]);
+        assert_eq!(output.values, Tanh::default().apply(&expected).values);
+    }
+
+    #[test]
+    fn onnx_import_unsupported_op() {
+        let nodes = vec![node("Conv", &["obs", "w"], "action", vec![])];
+        let initializer = vec![tensor("w", vec![2, 2], vec![1.0; 4])];
+        let result = Model::from_onnx(&proto(nodes, initializer, 2).encode_to_vec());
+        match result {
+            Err(OnnxError::Unsupported(message)) => assert!(message.contains("Conv")),
+            _ => panic!("Conv should not be imported"),
+        }
+    }
+
+    #[test]
+    fn onnx_import_invalid_dims() {
+        let gemm = || vec![node("Gemm", &["obs", "w"], "action", vec![])];
+        let cases = [
+            (vec![2, 3], 2, -3),
+            (vec![-1, -6], 6, 2),
+            (vec![i64::MAX, 4], 4, 2),
+        ];
+        for (dims, values, features) in cases {
+            let initializer = vec![tensor("w", dims.clone(), vec![1.0; values])];
+            let result = Model::from_onnx(&proto(gemm(), initializer, features).encode_to_vec());
+            assert!(
+                matches!(result, Err(OnnxError::Invalid(_))),
+                "dims {:?} with {} features",
+                dims,
+                features
+            );
+        }
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod test {
+    use prost::Message;
+
+    use crate::{
+        activation::{lerelu::LeakyReLu, relu::ReLu, softmax::SoftMax, tanh::Tanh},
+        builder::{builder::ModelBuilder, graph_elements::BuilderNode},
+        layer::{concat::Concat, dense::Dense, direct::Direct, flatten::Flatten, input::Input},
+        matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        model::model::Model,
+        serial::onnx::{
+            onnx_error::OnnxError,
+            onnx_export::OnnxExport,
+            proto::{DataType, DimensionValue, ModelProto, TypeValue},
+        },
+    };
+
+    fn build_model() -> Model {
+        let input = Input::new(Shape::Const(4), Shape::Repeat);
+        let d1 = Dense::builder(5, || &input)
+            .with_activation(ReLu { cap: 3.0 })
+            .build();
+        let d2 = Direct::builder(|| &input)
+            .with_activation(LeakyReLu { beta: 0.1 })
+            .build();
+        let concat = Concat::new(|| vec![&d1, &d2]);
+        let d3 = Dense::builder(3, || &concat)
+            .with_activation(Tanh::default())
+            .build();
+        let output = Dense::builder(2, || &d3)
+            .with_activation(SoftMax::default())
+            .build();
+        return ModelBuilder::from_straight(input, output).build();
+    }
+
+    #[test]
+    fn onnx_export_decode() {
+        let model = build_model();
+        let bytes = model.to_onnx().unwrap();
+        let decoded = ModelProto::decode(bytes.as_slice()).unwrap();
+
+        assert_eq!(decoded.ir_version, OnnxExport::IR_VERSION);
+        assert_eq!(decoded.opset_import[0].version, OnnxExport::OPSET_VERSION);
+        let graph = decoded.graph.unwrap();
+
+        let ops: Vec<&str> = graph.node.iter().map(|n| n.op_type.as_str()).collect();
+        assert_eq!(
+            ops,
+            vec![
+                "Identity",
+                "MatMul",
+                "Add",
+                "Relu",
+                "Clip",
+                "Mul",
+                "Add",
+                "LeakyRelu",
+                "Concat",
+                "MatMul",
+                "Add",
+                "Tanh",
+                "MatMul",
+                "Add",
+                "Softmax",
+                "Identity",
+            ]
+        );
+
+        // single io model uses the same data name on both ends
+        assert_eq!(graph.input.len(), 1);
+        assert_eq!(graph.input[0].name, ModelBuilder::SINGLE_IO);
+        assert_eq!(
+            graph.output[0].name,
+            format!("{}{}", ModelBuilder::SINGLE_IO, OnnxExport::OUTPUT_SUFFIX)
+        );
+        let input_type = graph.input[0].r#type.clone().unwrap().value.unwrap();
+        let TypeValue::TensorType(tensor_type) = input_type;
+        let dims: Vec<DimensionValue> = tensor_type
+            .shape
+            .unwrap()
+            .dim
+            .into_iter()
+            .map(|d| d.value.unwrap())
+            .collect();
+        assert_eq!(
+            dims,
+            vec![
+                DimensionValue::DimParam("size".to_string()),
+                DimensionValue::DimValue(4),
+            ]
+        );
+
+        let serialized = model.to_serialized_model();
+        let meta: serde_json::Value = serialized.meta.meta.get("Dense_1").unwrap().to().unwrap();
+        let weight: NDMatrix = serde_json::from_value(meta["weight"].clone()).unwrap();
+        let initializer = graph
+            .initializer
+            .iter()
+            .find(|t| t.name == "Dense_1.weight")
+            .unwrap();
+        assert_eq!(initializer.data_type, DataType::FLOAT);
+        assert_eq!(initializer.dims, vec![4, 5]);
+        let values: Vec<f32> = initializer
+            .raw_data
+            .chunks_exact(4)
+            .map(|le| f32::from_le_bytes(le.try_into().unwrap()))
+            .collect();
+        assert_eq!(values, weight.iter_all().cloned().collect::<Vec<f32>>());
+
+        let leaky = graph
+            .node
+            .iter()
+            .find(|n| n.op_type == "LeakyRelu")
+            .unwrap();
+        assert_eq!(leaky.attribute[0].name, "alpha");
+        assert_eq!(leaky.attribute[0].f, 0.1);
+    }
+
+    #[test]
+    fn onnx_export_flatten_file() {
+        let input = Input::new(Shape::Const(3), Shape::Const(2));
+        let flatten = Flatten::new(|| &input);
+        let model = ModelBuilder::from_straight(input, flatten).build();
+
+        let path = std::env::temp_dir().join("neurotick_onnx_export_flatten.onnx");
+        model.save_onnx(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let graph = ModelProto::decode(bytes.as_slice()).unwrap().graph.unwrap();
+        let reshape = graph.node.iter().find(|n| n.op_type == "Reshape").unwrap();
+        assert_eq!(reshape.output[0], "Flatten_1");
+        let shape = graph
+            .initializer
+            .iter()
+            .find(|t| t.name == reshape.input[1])
+            .unwrap();
+        assert_eq!(shape.data_type, DataType::INT64);
+        assert_eq!(shape.int64_data, vec![1, -1]);
+    }
+
+    #[test]
+    fn onnx_export_unsupported_layer() {
+        let mut serialized = build_model().to_serialized_model();
+        if let Some(BuilderNode::SingleParent(node)) = serialized.graph.graph.get_mut("Dense_1") {
+            node.type_name = "Custom".to_string();
+        }
+        let result = OnnxExport::export(&serialized);
+        assert!(matches!(result, Err(OnnxError::Unsupported(_))));
+    }
+}
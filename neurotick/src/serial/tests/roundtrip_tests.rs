@@ -0,0 +1,340 @@
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use indexmap::IndexMap;
+
+    use crate::{
+        activation::{
+            abs::Activation, lerelu::LeakyReLu, none::NoneAct, relu::ReLu, sigmoid::Sigmoid,
+            softmax::SoftMax, tanh::Tanh,
+        },
+        builder::builder::ModelBuilder,
+        layer::{
+            abs::{LayerRef, Mode},
+            attention::{AttentionMask, MultiHeadAttention},
+            batch_norm::BatchNorm,
+            concat::Concat,
+            conv1d::{Conv1D, Padding},
+            conv2d::Conv2D,
+            dense::Dense,
+            direct::Direct,
+            embedding::Embedding,
+            flatten::Flatten,
+            gru::Gru,
+            input::Input,
+            layer_norm::LayerNorm,
+            lstm::Lstm,
+            merge::{Add, Average, Maximum, Multiply, Subtract},
+            noise::{AlphaDropout, Dropout, GaussianNoise},
+            pooling::{AvgPool2D, MaxPool2D},
+            reshape::Reshape,
+            slice::{Slice, Split},
+            transformer::TransformerEncoder,
+            transpose::Transpose,
+        },
+        map,
+        matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        model::model::Model,
+        serial::{model_reader::ModelReader, model_serial::ModelSerialized},
+        suppliers::suppliers::RandomUniformSupplier,
+    };
+
+    /**
+     * Serializes to a json string, reads it back and compares outputs on the same input
+     */
+    fn assert_roundtrip(model: &Model, inputs: &HashMap<String, NDMatrix>) {
+        let reader = ModelReader::default();
+        let json = model.to_json();
+        let serialized: ModelSerialized = serde_json::from_str(&json).unwrap();
+        let restored = serialized.build_model(&reader);
+
+        let pre = model.propagate(inputs);
+        let post = restored.propagate(inputs);
+        assert_eq!(pre.len(), post.len());
+        pre.iter().for_each(|(name, data)| {
+            assert_eq!(data.values, post.get(name).unwrap().values);
+        });
+    }
+
+    fn single_input(features: usize, size: usize) -> HashMap<String, NDMatrix> {
+        return map! {
+            ModelBuilder::SINGLE_IO.to_string() =>
+                NDMatrix::from_supply(features, size, RandomUniformSupplier::new(1.0, -1.0))
+        };
+    }
+
+    #[test]
+    fn roundtrip_input() {
+        let input = Input::new(Shape::Const(4), Shape::Repeat);
+        let model = ModelBuilder::from_straight(input.clone(), input).build();
+        assert_roundtrip(&model, &single_input(4, 3));
+    }
+
+    #[test]
+    fn roundtrip_dense() {
+        let input = Input::new(Shape::Const(4), Shape::Repeat);
+        let dense = Dense::builder(6, || &input)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let model = ModelBuilder::from_straight(input, dense).build();
+        assert_roundtrip(&model, &single_input(4, 3));
+    }
+
+    #[test]
+    fn roundtrip_direct() {
+        let input = Input::new(Shape::Const(4), Shape::Repeat);
+        let direct = Direct::builder(|| &input)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let model = ModelBuilder::from_straight(input, direct).build();
+        assert_roundtrip(&model, &single_input(4, 3));
+    }
+
+    #[test]
+    fn roundtrip_flatten() {
+        let input = Input::new(Shape::Const(4), Shape::Const(3));
+        let flatten = Flatten::new(|| &input);
+        let model = ModelBuilder::from_straight(input, flatten).build();
+        assert_roundtrip(&model, &single_input(4, 3));
+    }
+
+    #[test]
+    fn roundtrip_conv1d() {
+        let input = Input::new(Shape::Const(3), Shape::Const(12));
+        let conv = Conv1D::builder(4, 3, || &input)
+            .with_padding(Padding::Causal)
+            .with_dilation(2)
+            .with_stride(2)
+            .with_activation(ReLu::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let model = ModelBuilder::from_straight(input, conv).build();
+        assert_roundtrip(&model, &single_input(3, 12));
+    }
+
+    #[test]
+    fn roundtrip_conv2d_pooling() {
+        let input = Input::new(Shape::Const(2), Shape::Grid(7, 6));
+        let conv = Conv2D::builder(3, (3, 2), || &input)
+            .with_padding(Padding::Same)
+            .with_stride((2, 1))
+            .with_activation(ReLu::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let max = MaxPool2D::new((2, 2), || &conv);
+        let avg = AvgPool2D::builder((3, 3), || &max)
+            .with_padding(Padding::Same)
+            .with_stride((1, 1))
+            .build();
+        let model = ModelBuilder::from_straight(input, avg).build();
+        assert_roundtrip(&model, &single_input(2, 42));
+    }
+
+    #[test]
+    fn roundtrip_recurrent() {
+        let input = Input::new(Shape::Const(3), Shape::Repeat);
+        let lstm = Lstm::builder(5, || &input)
+            .with_return_sequences(true)
+            .with_activation(Tanh::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let gru = Gru::builder(4, || &lstm)
+            .with_recurrent_activation(Sigmoid::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let model = ModelBuilder::from_straight(input, gru).build();
+        assert_roundtrip(&model, &single_input(3, 6));
+    }
+
+    #[test]
+    fn roundtrip_attention() {
+        let input = Input::new(Shape::Const(4), Shape::Variable);
+        let attention = MultiHeadAttention::builder(2, 3, || &input)
+            .with_mask(AttentionMask::Padding(0.0))
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let encoder = TransformerEncoder::builder(2, 2, 6, || &attention)
+            .with_mask(AttentionMask::Causal)
+            .with_activation(Tanh::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let model = ModelBuilder::from_straight(input, encoder).build();
+        assert_roundtrip(&model, &single_input(4, 5));
+    }
+
+    #[test]
+    fn roundtrip_normalization() {
+        let input = Input::new(Shape::Const(4), Shape::Repeat);
+        let dense = Dense::new(6, || &input);
+        let layer_norm = LayerNorm::builder(|| &dense).with_epsilon(1e-5).build();
+        let batch_norm = BatchNorm::builder(|| &layer_norm)
+            .with_momentum(0.9)
+            .build();
+        let mut model = ModelBuilder::from_straight(input, batch_norm).build();
+        model.set_mode(Mode::Training);
+        model.propagate_single(NDMatrix::from_supply(
+            4,
+            5,
+            RandomUniformSupplier::new(1.0, -1.0),
+        ));
+        model.set_mode(Mode::Inference);
+        assert_roundtrip(&model, &single_input(4, 5));
+    }
+
+    #[test]
+    fn roundtrip_noise() {
+        let input = Input::new(Shape::Const(4), Shape::Repeat);
+        let dropout = Dropout::builder(0.3, || &input).with_seed(1).build();
+        let gaussian = GaussianNoise::new(0.1, || &dropout);
+        let alpha = AlphaDropout::builder(0.1, || &gaussian)
+            .with_seed(2)
+            .build();
+        let model = ModelBuilder::from_straight(input, alpha).build();
+        assert_roundtrip(&model, &single_input(4, 3));
+    }
+
+    #[test]
+    fn roundtrip_concat() {
+        let input_1 = Input::new(Shape::Const(4), Shape::Repeat);
+        let input_2 = Input::new(Shape::Const(2), Shape::Repeat);
+        let concat = Concat::new(|| vec![&input_1, &input_2]);
+
+        let inputs: IndexMap<LayerRef, String> = map! {
+            input_1 => "1".to_owned(),
+            input_2 => "2".to_owned(),
+        };
+        let model = ModelBuilder::from_single_o(inputs, concat).build();
+        let data = map! {
+            "1".to_owned() => NDMatrix::from_supply(4, 3, RandomUniformSupplier::new(1.0, -1.0)),
+            "2".to_owned() => NDMatrix::from_supply(2, 3, RandomUniformSupplier::new(1.0, -1.0)),
+        };
+        assert_roundtrip(&model, &data);
+    }
+
+    #[test]
+    fn roundtrip_embedding() {
+        let input = Input::new(Shape::Const(3), Shape::Repeat);
+        let embedding = Embedding::builder(7, 4, || &input)
+            .with_embeddings_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let dense = Dense::new(2, || &embedding);
+        let model = ModelBuilder::from_straight(input, dense).build();
+        let ids = NDMatrix::from_raw_vec(3, 2, vec![0.0, 6.0, 2.0, 5.0, 5.0, 1.0]);
+        assert_roundtrip(&model, &map! { ModelBuilder::SINGLE_IO.to_string() => ids });
+    }
+
+    #[test]
+    fn roundtrip_reshape_slice() {
+        let input = Input::new(Shape::Const(6), Shape::Const(2));
+        let branches = Split::new(vec![("left", 2), ("right", 4)], || &input);
+        let reshape = Reshape::new(1, Shape::Const(4), || &branches["left"]);
+        let transpose = Transpose::new(|| &branches["right"]);
+        let concat = Concat::new(|| vec![&reshape, &transpose]);
+        let dense = Dense::new(3, || &concat);
+        let model = ModelBuilder::from_straight(input, dense).build();
+        let values: Vec<f32> = (0..12).map(|v| v as f32 / 10.0).collect();
+        let data = NDMatrix::from_raw_vec(6, 2, values);
+        assert_roundtrip(
+            &model,
+            &map! { ModelBuilder::SINGLE_IO.to_string() => data },
+        );
+    }
+
+    #[test]
+    fn roundtrip_merge() {
+        let input_1 = Input::new(Shape::Const(3), Shape::Repeat);
+        let input_2 = Input::new(Shape::Const(3), Shape::Repeat);
+        let dense = Dense::builder(3, || &input_1)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let add = Add::new(|| vec![&input_1, &dense]);
+        let subtract = Subtract::new(|| vec![&add, &input_2]);
+        let multiply = Multiply::new(|| vec![&subtract, &dense, &input_2]);
+        let average = Average::new(|| vec![&multiply, &add]);
+        let maximum = Maximum::new(|| vec![&average, &input_1, &subtract]);
+
+        let inputs: IndexMap<LayerRef, String> = map! {
+            input_1 => "1".to_owned(),
+            input_2 => "2".to_owned(),
+        };
+        let model = ModelBuilder::from_single_o(inputs, maximum).build();
+        let data = map! {
+            "1".to_owned() => NDMatrix::from_supply(3, 4, RandomUniformSupplier::new(1.0, -1.0)),
+            "2".to_owned() => NDMatrix::from_supply(3, 4, RandomUniformSupplier::new(1.0, -1.0)),
+        };
+        assert_roundtrip(&model, &data);
+    }
+
+    #[test]
+    fn roundtrip_activations() {
+        let activations: Vec<Box<dyn Fn(Dense) -> Dense>> = vec![
+            Box::new(|d| d.with_activation(NoneAct::default())),
+            Box::new(|d| d.with_activation(ReLu { cap: 0.5 })),
+            Box::new(|d| d.with_activation(LeakyReLu { beta: 0.1 })),
+            Box::new(|d| d.with_activation(Sigmoid::default())),
+            Box::new(|d| d.with_activation(Tanh::default())),
+            Box::new(|d| d.with_activation(SoftMax::default())),
+        ];
+
+        activations.iter().for_each(|with_activation| {
+            let input = Input::new(Shape::Const(4), Shape::Repeat);
+            let dense = with_activation(Dense::builder(5, || &input)).build();
+            let model = ModelBuilder::from_straight(input, dense).build();
+            assert_roundtrip(&model, &single_input(4, 3));
+        });
+    }
+
+    #[test]
+    fn default_reader_registers_builtins() {
+        let reader = ModelReader::default();
+        [
+            Input::NAME,
+            Dense::NAME,
+            Direct::NAME,
+            Concat::NAME,
+            Flatten::NAME,
+            Conv1D::NAME,
+            Conv2D::NAME,
+            MaxPool2D::NAME,
+            AvgPool2D::NAME,
+            Lstm::NAME,
+            Gru::NAME,
+            MultiHeadAttention::NAME,
+            TransformerEncoder::NAME,
+            LayerNorm::NAME,
+            BatchNorm::NAME,
+            Dropout::NAME,
+            GaussianNoise::NAME,
+            AlphaDropout::NAME,
+            Add::NAME,
+            Subtract::NAME,
+            Multiply::NAME,
+            Average::NAME,
+            Maximum::NAME,
+            Embedding::NAME,
+            Reshape::NAME,
+            Transpose::NAME,
+            Slice::NAME,
+        ]
+        .iter()
+        .for_each(|name| assert!(reader.get_layer_di().contains(name), "{}", name));
+
+        [
+            NoneAct::NAME,
+            ReLu::NAME,
+            LeakyReLu::NAME,
+            Sigmoid::NAME,
+            Tanh::NAME,
+            SoftMax::NAME,
+        ]
+        .iter()
+        .for_each(|name| assert!(reader.get_activation_di().contains(name), "{}", name));
+
+        // the serialized name of each activation must match its registration
+        let relu: &dyn Activation = &ReLu::default();
+        assert!(reader
+            .get_activation_di()
+            .contains(&relu.as_serialized().name));
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod test {
+    use std::io::ErrorKind;
+
+    use crate::{
+        activation::relu::ReLu,
+        builder::builder::ModelBuilder,
+        layer::{concat::Concat, dense::Dense, direct::Direct, input::Input},
+        matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        model::model::Model,
+        serial::{
+            model_reader::ModelReader, model_weights::ModelWeights, safetensors_serial::SafeTensors,
+        },
+        suppliers::suppliers::RandomUniformSupplier,
+    };
+
+    fn build_model(dense_features: usize) -> Model {
+        let input = Input::new(Shape::Const(4), Shape::Repeat);
+        let d1 = Dense::builder(dense_features, || &input)
+            .with_activation(ReLu::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let d2 = Direct::builder(|| &input)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let concat = Concat::new(|| vec![&d1, &d2]);
+        let output = Dense::builder(2, || &concat)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        return ModelBuilder::from_straight(input, output).build();
+    }
+
+    #[test]
+    fn safetensors_layout() {
+        let model = build_model(6);
+        let bytes = model.to_safetensors();
+
+        let header_len = u64::from_le_bytes(bytes[0..8].try_into().unwrap()) as usize;
+        assert_eq!(header_len % SafeTensors::HEADER_ALIGNMENT, 0);
+        let header: serde_json::Value = serde_json::from_slice(&bytes[8..8 + header_len]).unwrap();
+        assert_eq!(header[SafeTensors::METADATA]["format"], "neurotick");
+
+        // tensor names follow the builder_ref layer names
+        let weight = &header["Dense_1.weight"];
+        assert_eq!(weight["dtype"], "F32");
+        assert_eq!(weight["shape"], serde_json::json!([4, 6]));
+        let begin = weight["data_offsets"][0].as_u64().unwrap() as usize;
+        let first = f32::from_le_bytes(
+            bytes[8 + header_len + begin..8 + header_len + begin + 4]
+                .try_into()
+                .unwrap(),
+        );
+        let weights = ModelWeights::collect(&model);
+        assert_eq!(first, weights["Dense_1.weight"].get(0, 0));
+        assert!(weights.keys().all(|name| model
+            .builder_ref
+            .contains_key(name.split('.').next().unwrap())));
+
+        let decoded = SafeTensors::decode(&bytes).unwrap();
+        assert_eq!(decoded.len(), weights.len());
+        weights
+            .iter()
+            .for_each(|(name, matrix)| assert_eq!(decoded[name].values, matrix.values));
+    }
+
+    #[test]
+    fn safetensors_load() {
+        let source = build_model(6);
+        let mut target = build_model(6);
+        let path = std::env::temp_dir().join("neurotick_safetensors_load.safetensors");
+        source.save_safetensors(&path).unwrap();
+        target
+            .load_safetensors(&path, &ModelReader::default())
+            .unwrap();
+
+        let mut other = build_model(5);
+        let result = other.load_safetensors(&path, &ModelReader::default());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        let input = NDMatrix::from_supply(4, 3, RandomUniformSupplier::new(1.0, -1.0));
+        assert_eq!(
+            source.propagate_single(input.clone()).values,
+            target.propagate_single(input).values
+        );
+    }
+
+    #[test]
+    fn safetensors_partial_load() {
+        let source = build_model(6);
+        let mut source_weights = ModelWeights::collect(&source);
+        source_weights.insert("Extra.weight".to_string(), NDMatrix::constant(2, 2, 1.0));
+        source_weights.remove("Dense_4.bias");
+        let path = std::env::temp_dir().join("neurotick_safetensors_partial.safetensors");
+        std::fs::write(&path, SafeTensors::encode(&source_weights)).unwrap();
+
+        // narrower first dense layer, its weights and the ones of the last layer don't fit
+        let mut target = build_model(5);
+        let before = ModelWeights::collect(&target);
+        let report = target
+            .load_safetensors_partial(&path, &ModelReader::default())
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!report.is_complete());
+        assert_eq!(report.loaded, vec!["Direct_2.weight", "Direct_2.bias"]);
+        assert_eq!(report.missing, vec!["Dense_4.bias"]);
+        assert_eq!(report.unexpected, vec!["Extra.weight"]);
+        let mismatched: Vec<&str> = report.mismatched.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(
+            mismatched,
+            vec!["Dense_1.weight", "Dense_1.bias", "Dense_4.weight"]
+        );
+        assert_eq!(report.mismatched[0].expected, (5, 4));
+        assert_eq!(report.mismatched[0].found, (6, 4));
+
+        let after = ModelWeights::collect(&target);
+        assert_eq!(
+            after["Direct_2.weight"].values,
+            source_weights["Direct_2.weight"].values
+        );
+        assert_eq!(
+            after["Dense_1.weight"].values,
+            before["Dense_1.weight"].values
+        );
+    }
+
+    #[test]
+    fn safetensors_rejects_overflow() {
+        let mut bytes = u64::MAX.to_le_bytes().to_vec();
+        bytes.extend_from_slice(b"{}");
+        assert!(SafeTensors::decode(&bytes).is_err());
+
+        let header = serde_json::json!({
+            "huge": {"dtype": "F32", "shape": [usize::MAX, 2], "data_offsets": [0, 8]},
+        });
+        let header_json = serde_json::to_vec(&header).unwrap();
+        let mut bytes = (header_json.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&header_json);
+        bytes.extend_from_slice(&[0; 8]);
+        let error = SafeTensors::decode(&bytes).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,191 @@
+#[cfg(test)]
+mod test {
+    use serde_json::{Map, Value};
+
+    use crate::{
+        activation::relu::ReLu,
+        builder::builder::ModelBuilder,
+        layer::{concat::Concat, dense::Dense, direct::Direct, input::Input},
+        matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        model::model::Model,
+        serial::{
+            integrity::{IntegrityError, ModelIntegrity},
+            model_reader::{ModelReadError, ModelReader},
+            model_serial::ModelSerialized,
+            model_version::ModelMigrations,
+        },
+        suppliers::suppliers::RandomUniformSupplier,
+    };
+
+    fn build_model() -> Model {
+        let input = Input::new(Shape::Const(4), Shape::Repeat);
+        let d1 = Dense::builder(6, || &input)
+            .with_activation(ReLu::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let d2 = Direct::builder(|| &input)
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let concat = Concat::new(|| vec![&d1, &d2]);
+        let output = Dense::builder(3, || &concat).build();
+        return ModelBuilder::from_straight(input, output).build();
+    }
+
+    fn assert_same_output(a: &Model, b: &Model) {
+        let input = NDMatrix::from_supply(4, 2, RandomUniformSupplier::new(1.0, -1.0));
+        assert_eq!(
+            a.propagate_single(input.clone()).values,
+            b.propagate_single(input).values
+        );
+    }
+
+    fn document(model: &Model) -> Map<String, Value> {
+        let document: Value = serde_json::from_str(&model.to_json()).unwrap();
+        return document.as_object().unwrap().clone();
+    }
+
+    #[test]
+    fn stream_file_roundtrip() {
+        let model = build_model();
+        let path = std::env::temp_dir().join("neurotick_stream_file_roundtrip.json");
+        model.save_json(&path).unwrap();
+        let loaded = Model::load_json(&path, &ModelReader::default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_same_output(&model, &loaded);
+        assert_eq!(
+            loaded.sequential_prop.keys().collect::<Vec<_>>(),
+            model.sequential_prop.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(loaded.to_json(), model.to_json());
+    }
+
+    #[test]
+    fn stream_integrity() {
+        let model = build_model();
+        let reader = ModelReader::default();
+        let pretty = model.to_json_pretty();
+        assert_same_output(&model, &reader.read_stream(pretty.as_bytes()).unwrap());
+
+        let tampered = pretty.replacen("\"cap\": 10.0", "\"cap\": 1.0", 1);
+        assert_ne!(tampered, pretty);
+        assert!(matches!(
+            reader.read_stream(tampered.as_bytes()),
+            Err(ModelReadError::Integrity(IntegrityError::Checksum))
+        ));
+
+        let key = b"stream-key";
+        let mut signed_reader = ModelReader::default();
+        signed_reader.require_signature(key);
+        let signed = model.to_json_signed(key);
+        assert!(signed_reader.read_stream(signed.as_bytes()).is_ok());
+        assert!(matches!(
+            signed_reader.read_stream(model.to_json().as_bytes()),
+            Err(ModelReadError::Integrity(IntegrityError::Unsigned))
+        ));
+    }
+
+    #[test]
+    fn stream_bad_metas() {
+        let model = build_model();
+        let reader = ModelReader::default();
+        let mut original = document(&model);
+        original.remove(ModelIntegrity::INTEGRITY_KEY);
+        let read = |document: Map<String, Value>| {
+            return reader.read_stream(Value::Object(document).to_string().as_bytes());
+        };
+
+        // layers are created while parsing, bad metas are an error before a layer sees them
+        let mut truncated = original.clone();
+        let data = &mut truncated["meta"]["Dense_1"]["weight"]["data"];
+        let text = data.as_str().unwrap().to_string();
+        *data = Value::from(&text[..text.len() - 4]);
+        assert!(matches!(
+            read(truncated),
+            Err(ModelReadError::Tensor { ref name, .. }) if name == "Dense_1.weight"
+        ));
+
+        let mut unknown = original.clone();
+        unknown["graph"]["Dense_1"]["SingleParent"]["type_name"] = Value::from("Unknown");
+        let error = read(unknown).err().unwrap();
+        assert!(error.to_string().contains("unregistered type Unknown"));
+
+        let mut stray = original.clone();
+        let meta = stray["meta"]["Dense_1"].clone();
+        stray["meta"]
+            .as_object_mut()
+            .unwrap()
+            .insert("Stray".to_string(), meta);
+        let error = read(stray).err().unwrap();
+        assert!(error
+            .to_string()
+            .contains("Layer Stray is not in the graph"));
+    }
+
+    #[test]
+    fn stream_entry_order() {
+        let model = build_model();
+        let mut original = document(&model);
+        original.remove(ModelIntegrity::INTEGRITY_KEY);
+
+        // metas before the graph are kept until the graph is known
+        let mut reordered = Map::new();
+        for key in ["meta", "version", "io", "graph"] {
+            reordered.insert(key.to_string(), original[key].clone());
+        }
+        let mut sealed = Value::Object(reordered);
+        ModelIntegrity::seal(&mut sealed, None);
+        let streamed = ModelReader::default()
+            .read_stream(sealed.to_string().as_bytes())
+            .unwrap();
+        assert_same_output(&model, &streamed);
+
+        let mut missing = original.clone();
+        missing.remove("graph");
+        let result =
+            ModelReader::default().read_stream(Value::Object(missing).to_string().as_bytes());
+        assert!(matches!(result, Err(ModelReadError::Json(_))));
+    }
+
+    #[test]
+    fn stream_versions() {
+        let model = build_model();
+        let mut json = document(&model);
+        json.remove(ModelIntegrity::INTEGRITY_KEY);
+
+        // documents before versioning read as version 1
+        let mut legacy = json.clone();
+        legacy.remove(ModelMigrations::VERSION_KEY);
+        let legacy = Value::Object(legacy).to_string();
+        let reader = ModelReader::default();
+        assert_same_output(&model, &reader.read_stream(legacy.as_bytes()).unwrap());
+
+        let mut migrating = ModelReader::default();
+        migrating.register_migration(0, Ok);
+        assert!(matches!(
+            migrating.read_stream(legacy.as_bytes()),
+            Err(ModelReadError::Migration { from: 0, .. })
+        ));
+        assert!(migrating.read_json(&legacy).is_ok());
+
+        let mut newer = json.clone();
+        newer.insert(
+            ModelMigrations::VERSION_KEY.to_string(),
+            Value::from(ModelSerialized::FORMAT_VERSION + 1),
+        );
+        assert!(matches!(
+            reader.read_stream(Value::Object(newer).to_string().as_bytes()),
+            Err(ModelReadError::Newer { .. })
+        ));
+
+        let mut truncated = json.clone();
+        truncated.insert(
+            ModelMigrations::VERSION_KEY.to_string(),
+            Value::from(u32::MAX as u64 + 2),
+        );
+        assert!(matches!(
+            reader.read_stream(Value::Object(truncated).to_string().as_bytes()),
+            Err(ModelReadError::InvalidVersion(_))
+        ));
+    }
+}
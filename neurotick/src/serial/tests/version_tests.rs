@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use crate::{
+        activation::relu::ReLu,
+        builder::builder::ModelBuilder,
+        layer::{dense::Dense, input::Input},
+        matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        model::model::Model,
+        serial::{
+            integrity::ModelIntegrity,
+            model_reader::{ModelReadError, ModelReader},
+            model_serial::ModelSerialized,
+            model_version::ModelMigrations,
+        },
+        suppliers::suppliers::RandomUniformSupplier,
+    };
+
+    fn build_model() -> Model {
+        let input = Input::new(Shape::Const(3), Shape::Repeat);
+        let output = Dense::builder(4, || &input)
+            .with_activation(ReLu::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        return ModelBuilder::from_straight(input, output).build();
+    }
+
+    fn assert_same_output(a: &Model, b: &Model) {
+        let input = NDMatrix::from_supply(3, 2, RandomUniformSupplier::new(1.0, -1.0));
+        assert_eq!(
+            a.propagate_single(input.clone()).values,
+            b.propagate_single(input).values
+        );
+    }
+
+    /**
+     * Without the checksum, the tests edit the documents
+     */
+    fn document(model: &Model) -> Value {
+        let mut document: Value = serde_json::from_str(&model.to_json()).unwrap();
+        document
+            .as_object_mut()
+            .unwrap()
+            .remove(ModelIntegrity::INTEGRITY_KEY);
+        return document;
+    }
+
+    #[test]
+    fn version_current_and_legacy() {
+        let model = build_model();
+        let mut json = document(&model);
+        assert_eq!(
+            json[ModelMigrations::VERSION_KEY],
+            ModelSerialized::FORMAT_VERSION
+        );
+
+        let reader = ModelReader::default();
+        let restored = Model::from_json(&json.to_string(), &reader).unwrap();
+        assert_same_output(&model, &restored);
+
+        // files written before versioning have no version field
+        json.as_object_mut()
+            .unwrap()
+            .remove(ModelMigrations::VERSION_KEY);
+        let legacy = reader.read_json(&json.to_string()).unwrap();
+        assert_eq!(legacy.version, ModelSerialized::FORMAT_VERSION);
+        assert_same_output(&model, &legacy.build_model(&reader));
+    }
+
+    #[test]
+    fn version_newer_file() {
+        let mut json = document(&build_model());
+        json[ModelMigrations::VERSION_KEY] = Value::from(ModelSerialized::FORMAT_VERSION + 1);
+        let result = Model::from_json(&json.to_string(), &ModelReader::default());
+        match result {
+            Err(ModelReadError::Newer { found, supported }) => {
+                assert_eq!(found, ModelSerialized::FORMAT_VERSION + 1);
+                assert_eq!(supported, ModelSerialized::FORMAT_VERSION);
+            }
+            _ => panic!("Newer files should be rejected"),
+        }
+    }
+
+    #[test]
+    fn version_invalid_field() {
+        let reader = ModelReader::default();
+        let invalid = [
+            Value::from(u32::MAX as u64 + 2),
+            Value::from(-1),
+            Value::from(1.5),
+            Value::from("1"),
+            Value::Null,
+        ];
+        for version in invalid {
+            let mut json = document(&build_model());
+            json[ModelMigrations::VERSION_KEY] = version;
+            assert!(matches!(
+                Model::from_json(&json.to_string(), &reader),
+                Err(ModelReadError::InvalidVersion(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn version_migration_step() {
+        let model = build_model();
+        // an old layout that stored the dense weight as "kernel"
+        let mut json = document(&model);
+        json[ModelMigrations::VERSION_KEY] = Value::from(0);
+        let dense = json["meta"]["Dense_1"].as_object_mut().unwrap();
+        let weight = dense.remove("weight").unwrap();
+        dense.insert("kernel".to_string(), weight);
+
+        let mut reader = ModelReader::default();
+        reader.register_migration(0, |mut document| {
+            let meta = document["meta"]
+                .as_object_mut()
+                .ok_or("Missing meta".to_string())?;
+            for layer in meta.values_mut().filter_map(|l| l.as_object_mut()) {
+                if let Some(kernel) = layer.remove("kernel") {
+                    layer.insert("weight".to_string(), kernel);
+                }
+            }
+            return Ok(document);
+        });
+        let migrated = Model::from_json(&json.to_string(), &reader).unwrap();
+        assert_same_output(&model, &migrated);
+
+        let mut failing = ModelReader::default();
+        failing.register_migration(0, |_| Err("unreadable".to_string()));
+        let result = failing.read_json(&json.to_string());
+        assert!(matches!(
+            result,
+            Err(ModelReadError::Migration { from: 0, .. })
+        ));
+
+        let result = ModelMigrations::new().migrate(json);
+        assert!(matches!(result, Err(ModelReadError::MissingMigration(0))));
+    }
+}
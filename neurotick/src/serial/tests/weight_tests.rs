@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, io::ErrorKind, rc::Rc};
+
+    use crate::{
+        activation::{relu::ReLu, softmax::SoftMax},
+        builder::builder::ModelBuilder,
+        layer::{batch_norm::BatchNorm, dense::Dense, input::Input},
+        matrix::{meta::shape::Shape, nmatrix::NDMatrix},
+        model::model::Model,
+        serial::{
+            model_reader::ModelReader,
+            model_weights::ModelWeights,
+            weight_serial::{WeightLoadMode, WeightsSerialized},
+        },
+        suppliers::suppliers::RandomUniformSupplier,
+    };
+
+    fn build_model(head: usize) -> Model {
+        let input = Input::new(Shape::Const(4), Shape::Repeat);
+        let trunk = Dense::builder(6, || &input)
+            .with_activation(ReLu::default())
+            .with_bias_init(RandomUniformSupplier::new(1.0, -1.0))
+            .build();
+        let output = Dense::builder(head, || &trunk)
+            .with_activation(SoftMax::default())
+            .build();
+        return ModelBuilder::from_straight(input, output).build();
+    }
+
+    #[test]
+    fn weights_strict_roundtrip() {
+        let source = build_model(3);
+        let mut target = build_model(3);
+
+        let serialized = WeightsSerialized::from_model(&source);
+        let names: Vec<&str> = serialized.weights.iter().map(|w| w.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Dense_1.weight",
+                "Dense_1.bias",
+                "Dense_2.weight",
+                "Dense_2.bias"
+            ]
+        );
+        assert!(serialized.weights.iter().all(|w| w.trainable));
+
+        let path = std::env::temp_dir().join("neurotick_weights_strict_roundtrip.json");
+        source.save_weights(&path).unwrap();
+        let report = target
+            .load_weights(&path, WeightLoadMode::Strict, &ModelReader::default())
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(report.is_complete());
+
+        let input = NDMatrix::from_supply(4, 3, RandomUniformSupplier::new(1.0, -1.0));
+        assert_eq!(
+            source.propagate_single(input.clone()).values,
+            target.propagate_single(input).values
+        );
+    }
+
+    #[test]
+    fn weights_non_trainable() {
+        let input = Input::new(Shape::Const(4), Shape::Repeat);
+        let norm = BatchNorm::new(|| &input);
+        let model = ModelBuilder::from_straight(input, norm).build();
+
+        let serialized = WeightsSerialized::from_model(&model);
+        let trainable: Vec<(&str, bool)> = serialized
+            .weights
+            .iter()
+            .map(|w| (w.name.as_str(), w.trainable))
+            .collect();
+        assert_eq!(
+            trainable,
+            vec![
+                ("BatchNorm_1.gamma", true),
+                ("BatchNorm_1.beta", true),
+                ("BatchNorm_1.running_mean", false),
+                ("BatchNorm_1.running_variance", false)
+            ]
+        );
+    }
+
+    #[test]
+    fn weights_transfer_trunk() {
+        let pretrained = build_model(3);
+        let mut new_head = build_model(2);
+        let head_before = ModelWeights::collect(&new_head);
+
+        let path = std::env::temp_dir().join("neurotick_weights_transfer_trunk.json");
+        pretrained.save_weights(&path).unwrap();
+        let strict = new_head.load_weights(&path, WeightLoadMode::Strict, &ModelReader::default());
+        assert_eq!(strict.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        let report = new_head
+            .load_weights(&path, WeightLoadMode::Lenient, &ModelReader::default())
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(report.loaded, vec!["Dense_1.weight", "Dense_1.bias"]);
+        assert_eq!(report.mismatched.len(), 2);
+
+        let trunk = ModelWeights::collect(&pretrained);
+        let after = ModelWeights::collect(&new_head);
+        assert_eq!(
+            after["Dense_1.weight"].values,
+            trunk["Dense_1.weight"].values
+        );
+        assert_eq!(
+            after["Dense_2.weight"].values,
+            head_before["Dense_2.weight"].values
+        );
+    }
+
+    #[test]
+    fn weights_custom_reader() {
+        let source = build_model(3);
+        let mut target = build_model(3);
+
+        // layers are recreated through the given reader, e.g. one with custom layers
+        let created = Rc::new(Cell::new(0));
+        let counter = created.clone();
+        let default = ModelReader::default();
+        let mut reader = ModelReader::default();
+        reader.register_layer(Dense::NAME, move |json, reader| {
+            counter.set(counter.get() + 1);
+            return default.get_layer_di().create(Dense::NAME, json, reader);
+        });
+
+        let path = std::env::temp_dir().join("neurotick_weights_custom_reader.json");
+        source.save_weights(&path).unwrap();
+        let report = target.load_weights(&path, WeightLoadMode::Strict, &reader);
+        std::fs::remove_file(&path).unwrap();
+        assert!(report.unwrap().is_complete());
+        assert_eq!(created.get(), 2);
+    }
+}
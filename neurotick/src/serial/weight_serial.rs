@@ -1,9 +1,98 @@
-use super::matrix_serial::MatrixPack;
+use std::io::{Error, ErrorKind};
 
-#[allow(dead_code)]
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 
+use crate::{
+    matrix::nmatrix::NDMatrix,
+    model::model::Model,
+    serial::model_weights::{ModelWeights, WeightReport},
+};
+
+use super::{
+    matrix_serial::{MatrixPack, MatrixSerial},
+    model_reader::ModelReader,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
 pub struct WeightSerialised {
-    name: String,
-    trainable: bool,
-    pack: MatrixPack,
+    /**
+     * "{layer}.{field}", see ModelWeights
+     */
+    pub name: String,
+    /**
+     * False for state kept by propagation, ex.: the running statistics of BatchNorm
+     */
+    pub trainable: bool,
+    pub pack: MatrixPack,
+}
+
+/**
+ * Weights-only document, the parameters of a model without its graph
+ */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WeightsSerialized {
+    pub weights: Vec<WeightSerialised>,
+}
+
+/**
+ * Strict needs every parameter of the model with the same shape and nothing else.
+ * Lenient loads what matches by name and shape, e.g. a pre-trained trunk into a model
+ * with new heads, the other layers keep their initial weights.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeightLoadMode {
+    Strict,
+    Lenient,
+}
+
+impl WeightsSerialized {
+    pub fn from_model(model: &Model) -> WeightsSerialized {
+        let non_trainable = ModelWeights::non_trainable(model);
+        let weights = ModelWeights::collect(model)
+            .iter()
+            .map(|(name, matrix)| WeightSerialised {
+                name: name.clone(),
+                trainable: !non_trainable.contains(name),
+                pack: matrix.pack(),
+            })
+            .collect();
+        return WeightsSerialized { weights };
+    }
+
+    pub fn to_json(&self) -> String {
+        return serde_json::to_string(self).unwrap();
+    }
+
+    pub fn to_matrices(&self) -> Result<IndexMap<String, NDMatrix>, Error> {
+        let mut matrices: IndexMap<String, NDMatrix> = IndexMap::new();
+        for weight in self.weights.iter() {
+            let matrix = NDMatrix::unpack(&weight.pack).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Weight {}: {}", weight.name, e),
+                )
+            })?;
+            if matrices.insert(weight.name.clone(), matrix).is_some() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Duplicate weight: {}", weight.name),
+                ));
+            }
+        }
+        return Ok(matrices);
+    }
+
+    pub fn apply(
+        &self,
+        model: &mut Model,
+        mode: WeightLoadMode,
+        reader: &ModelReader,
+    ) -> Result<WeightReport, Error> {
+        let matrices = self.to_matrices()?;
+        return match mode {
+            WeightLoadMode::Strict => ModelWeights::apply(model, &matrices, reader),
+            WeightLoadMode::Lenient => ModelWeights::apply_partial(model, &matrices, reader),
+        };
+    }
 }
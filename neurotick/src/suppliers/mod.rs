@@ -1 +1,2 @@
 pub mod suppliers;
+mod tests;
@@ -9,6 +9,7 @@ pub enum Suppliers {
     RandomUniform(RefCell<RandomUniformSupplier>),
     GlorothNormal(RefCell<GlorothNormalSupplier>),
     GlorothUniform(RefCell<GlorothUniformSupplier>),
+    Orthogonal(RefCell<OrthogonalSupplier>),
 }
 
 pub trait Supplier {
@@ -25,6 +26,7 @@ impl Suppliers {
             Suppliers::RandomUniform(rc) => rc.borrow_mut().supply_single(in_f, out_f),
             Suppliers::GlorothNormal(rc) => rc.borrow_mut().supply_single(in_f, out_f),
             Suppliers::GlorothUniform(rc) => rc.borrow_mut().supply_single(in_f, out_f),
+            Suppliers::Orthogonal(rc) => rc.borrow_mut().supply_single(in_f, out_f),
         }
     }
 
@@ -35,6 +37,7 @@ impl Suppliers {
             Suppliers::RandomUniform(rc) => rc.borrow_mut().supply_matrix(width, height),
             Suppliers::GlorothNormal(rc) => rc.borrow_mut().supply_matrix(width, height),
             Suppliers::GlorothUniform(rc) => rc.borrow_mut().supply_matrix(width, height),
+            Suppliers::Orthogonal(rc) => rc.borrow_mut().supply_matrix(width, height),
         }
     }
 }
@@ -61,6 +64,15 @@ pub struct GlorothUniformSupplier {
     rng: ThreadRng,
 }
 
+/**
+ * Matrices with orthonormal rows or columns, whichever are fewer, scaled by the gain.
+ * Keeps recurrent weights from growing or shrinking the state over many steps.
+ */
+pub struct OrthogonalSupplier {
+    pub gain: f32,
+    rng: ThreadRng,
+}
+
 impl ZeroSupplier {
     #[allow(dead_code)]
     pub fn new() -> ZeroSupplier {
@@ -191,3 +203,61 @@ impl Supplier for GlorothNormalSupplier {
         return Suppliers::GlorothNormal(RefCell::new(self));
     }
 }
+
+impl OrthogonalSupplier {
+    #[allow(dead_code)]
+    pub fn new() -> OrthogonalSupplier {
+        return Self::with_gain(1.0);
+    }
+
+    pub fn with_gain(gain: f32) -> OrthogonalSupplier {
+        return OrthogonalSupplier {
+            gain,
+            rng: thread_rng(),
+        };
+    }
+}
+
+impl Supplier for OrthogonalSupplier {
+    /**
+     * A single value can't be orthogonal to anything, a normal sample
+     */
+    fn supply_single(&mut self, _in_f: usize, _out_f: usize) -> f32 {
+        return self.rng.sample::<f32, _>(StandardNormal) * self.gain;
+    }
+
+    fn supply_matrix(&mut self, width: usize, height: usize) -> NDMatrix {
+        let long = width.max(height);
+        let short = width.min(height);
+        // Gram-Schmidt on the short side of a normal matrix
+        let mut vectors: Vec<Vec<f64>> = Vec::with_capacity(short);
+        for _ in 0..short {
+            let mut vector: Vec<f64> = (0..long)
+                .map(|_| self.rng.sample::<f64, _>(StandardNormal))
+                .collect();
+            for done in vectors.iter() {
+                let projection: f64 = vector.iter().zip(done).map(|(a, b)| a * b).sum();
+                vector
+                    .iter_mut()
+                    .zip(done)
+                    .for_each(|(a, b)| *a -= projection * b);
+            }
+            let norm = vector.iter().map(|a| a * a).sum::<f64>().sqrt();
+            vector.iter_mut().for_each(|a| *a /= norm.max(f64::EPSILON));
+            vectors.push(vector);
+        }
+
+        let mut matrix = NDMatrix::new(width, height);
+        for (i, vector) in vectors.iter().enumerate() {
+            for (j, value) in vector.iter().enumerate() {
+                let (row, column) = if height >= width { (j, i) } else { (i, j) };
+                matrix.values[[row, column]] = *value as f32 * self.gain;
+            }
+        }
+        return matrix;
+    }
+
+    fn into_enum(self) -> Suppliers {
+        return Suppliers::Orthogonal(RefCell::new(self));
+    }
+}
@@ -0,0 +1 @@
+mod supplier_tests;
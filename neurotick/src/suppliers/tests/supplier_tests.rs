@@ -0,0 +1,23 @@
+#[cfg(test)]
+mod test {
+    use crate::suppliers::suppliers::{OrthogonalSupplier, Supplier};
+
+    #[test]
+    fn orthogonal_supplier() {
+        // tall matrices get orthonormal columns, wide ones orthonormal rows
+        for (width, height) in [(3, 7), (8, 2), (5, 5)] {
+            let mut supplier = OrthogonalSupplier::with_gain(2.0);
+            let matrix = supplier.supply_matrix(width, height);
+            assert_eq!((matrix.width, matrix.height), (width, height));
+            let gram = if height >= width {
+                matrix.values.t().dot(&matrix.values)
+            } else {
+                matrix.values.dot(&matrix.values.t())
+            };
+            gram.indexed_iter().for_each(|((i, j), value)| {
+                let expected = if i == j { 4.0 } else { 0.0 };
+                assert!((value - expected).abs() < 1e-4, "{} at {}:{}", value, i, j);
+            });
+        }
+    }
+}
@@ -18,8 +18,15 @@ impl<T: ?Sized, J, C> GenericInjector<T, J, C> {
         self.map.insert(name.to_string(), Box::new(call));
     }
 
+    pub fn contains(&self, name: &str) -> bool {
+        return self.map.contains_key(name);
+    }
+
     pub fn create(&self, name: &str, json: &J, context: &C) -> Box<T> {
-        let call = self.map.get(name).unwrap();
+        let call = match self.map.get(name) {
+            Some(call) => call,
+            None => panic!("No implementation registered for: {}", name),
+        };
         return call(&json, context);
     }
 }
@@ -14,11 +14,19 @@ impl JsonWrap {
         });
     }
 
+    pub fn from_value(value: Value) -> JsonWrap {
+        return JsonWrap { value };
+    }
+
     pub fn to<T>(&self) -> Result<T, Error>
     where
         T: DeserializeOwned,
     {
-        return serde_json::from_value(self.value.clone());
+        return T::deserialize(&self.value);
+    }
+
+    pub fn as_value(&self) -> &Value {
+        return &self.value;
     }
 
     pub fn to_string(&self) -> Result<String, Error> {
@@ -0,0 +1,73 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error, LitStr};
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "Activation can not be derived for generic types",
+        ));
+    }
+
+    let ident = &input.ident;
+    let mut name = ident.to_string();
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("activation"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = meta.value()?.parse::<LitStr>()?.value();
+                return Ok(());
+            }
+            return Err(meta.error("unsupported activation attribute, expected name"));
+        })?;
+    }
+
+    return Ok(quote! {
+        impl #ident {
+            pub const NAME: &'static str = #name;
+        }
+
+        impl ::neurotick::activation::abs::Activation for #ident {
+            fn apply(
+                &self,
+                array: &::neurotick::matrix::nmatrix::NDMatrix,
+            ) -> ::neurotick::matrix::nmatrix::NDMatrix {
+                return ::neurotick::activation::abs::ActivationFn::activate(self, array);
+            }
+
+            fn as_serialized(&self) -> ::neurotick::activation::abs::ActivationSerialised {
+                return ::neurotick::activation::abs::ActivationSerialised {
+                    name: Self::NAME.to_string(),
+                    json: ::neurotick::utils::json_wrap::JsonWrap::from(self).unwrap(),
+                };
+            }
+
+            fn act_clone(&self) -> Box<dyn ::neurotick::activation::abs::Activation> {
+                return Box::new(::std::clone::Clone::clone(self));
+            }
+        }
+
+        impl ::neurotick::activation::abs::ActivationVirtual for #ident {
+            fn from_json(
+                json: &::neurotick::utils::json_wrap::JsonWrap,
+            ) -> Box<dyn ::neurotick::activation::abs::Activation> {
+                return Box::new(json.to::<#ident>().unwrap());
+            }
+
+            fn type_name() -> &'static str {
+                return Self::NAME;
+            }
+        }
+
+        ::neurotick::__private::inventory::submit! {
+            ::neurotick::serial::registry::ActivationRegistration {
+                name: #ident::NAME,
+                create: <#ident as ::neurotick::activation::abs::ActivationVirtual>::from_json,
+            }
+        }
+    });
+}
@@ -0,0 +1,159 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Expr, Fields, LitStr};
+
+enum InputKind {
+    Single,
+    Multi,
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "LayerImpl can not be derived for generic types",
+        ));
+    }
+
+    let ident = &input.ident;
+    let serial_ident = format_ident!("__{}Serialization", ident);
+
+    let mut name: Option<Expr> = None;
+    let mut kind: Option<InputKind> = None;
+    let mut init: Option<Expr> = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("layer")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<Expr>()?);
+                return Ok(());
+            }
+            if meta.path.is_ident("init") {
+                init = Some(meta.value()?.parse::<Expr>()?);
+                return Ok(());
+            }
+            if meta.path.is_ident("input") {
+                let value = meta.value()?.parse::<LitStr>()?;
+                kind = match value.value().as_str() {
+                    "single" => Some(InputKind::Single),
+                    "multi" => Some(InputKind::Multi),
+                    _ => {
+                        return Err(Error::new_spanned(
+                            value,
+                            "expected \"single\" or \"multi\"",
+                        ))
+                    }
+                };
+                return Ok(());
+            }
+            return Err(meta.error("unsupported layer attribute, expected name, input or init"));
+        })?;
+    }
+    let name = name.ok_or_else(|| {
+        Error::new_spanned(
+            ident,
+            "missing #[layer(name = ...)], should match Layer::type_name",
+        )
+    })?;
+    let kind = kind.ok_or_else(|| {
+        Error::new_spanned(ident, "missing #[layer(input = \"single\" | \"multi\")]")
+    })?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => &named.named,
+            _ => return Err(Error::new_spanned(ident, "LayerImpl requires named fields")),
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                ident,
+                "LayerImpl can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut serial_fields: Vec<TokenStream> = vec![];
+    let mut to_serial: Vec<TokenStream> = vec![];
+    let mut from_serial: Vec<TokenStream> = vec![];
+    for field in fields.iter() {
+        let field_ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let is_activation = field.attrs.iter().any(|a| a.path().is_ident("activation"));
+        if is_activation {
+            serial_fields.push(quote! {
+                #field_ident: ::neurotick::activation::abs::ActivationSerialised
+            });
+            to_serial.push(quote! {
+                #field_ident: ::neurotick::activation::abs::Activation::as_serialized(
+                    &*self.#field_ident,
+                )
+            });
+            from_serial.push(quote! {
+                #field_ident: model_reader.get_activation_di().create(
+                    &deserialized.#field_ident.name,
+                    &deserialized.#field_ident.json,
+                    model_reader,
+                )
+            });
+        } else {
+            serial_fields.push(quote! { #field_ident: #ty });
+            to_serial.push(quote! {
+                #field_ident: ::std::clone::Clone::clone(&self.#field_ident)
+            });
+            from_serial.push(quote! { #field_ident: deserialized.#field_ident });
+        }
+    }
+
+    let init_body = match init {
+        Some(init) => quote! { (#init)(self); },
+        None => quote! {},
+    };
+
+    let wrap = match kind {
+        InputKind::Single => quote! { SingleInput },
+        InputKind::Multi => quote! { MultipleInput },
+    };
+
+    return Ok(quote! {
+        #[doc(hidden)]
+        #[derive(
+            ::neurotick::__private::serde::Serialize,
+            ::neurotick::__private::serde::Deserialize
+        )]
+        #[serde(crate = "::neurotick::__private::serde")]
+        #[allow(non_camel_case_types)]
+        struct #serial_ident {
+            #(#serial_fields,)*
+        }
+
+        impl ::neurotick::layer::abs::LayerBase for #ident {
+            fn init(&mut self) {
+                #init_body
+            }
+
+            fn to_json(&self) -> ::neurotick::utils::json_wrap::JsonWrap {
+                let serial = #serial_ident {
+                    #(#to_serial,)*
+                };
+                return ::neurotick::utils::json_wrap::JsonWrap::from(serial).unwrap();
+            }
+
+            fn create_from_ser(
+                json: &::neurotick::utils::json_wrap::JsonWrap,
+                model_reader: &::neurotick::serial::model_reader::ModelReader,
+            ) -> ::neurotick::layer::abs::LayerPropagateEnum {
+                let deserialized: #serial_ident = json.to().unwrap();
+                let impl_ref = #ident {
+                    #(#from_serial,)*
+                };
+                return ::neurotick::layer::abs::LayerPropagateEnum::#wrap(Box::new(impl_ref));
+            }
+        }
+
+        ::neurotick::__private::inventory::submit! {
+            ::neurotick::serial::registry::LayerRegistration {
+                name: #name,
+                create: <#ident as ::neurotick::layer::abs::LayerBase>::create_from_ser,
+            }
+        }
+    });
+}
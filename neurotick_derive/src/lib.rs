@@ -0,0 +1,40 @@
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod activation;
+mod layer;
+
+/**
+ * Implements Activation, ActivationVirtual, a NAME constant and the registration into the
+ * default ModelReader. The struct should be Clone + Debug + serde Serialize/Deserialize
+ * and implement ActivationFn with the actual function.
+ *
+ * The registered name defaults to the struct name, override with #[activation(name = "...")]
+ */
+#[proc_macro_derive(Activation, attributes(activation))]
+pub fn derive_activation(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    return activation::expand(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into();
+}
+
+/**
+ * Implements LayerBase with a generated serialization struct and the registration into
+ * the default ModelReader. Put on the layer instance struct (the *Impl), next to
+ * LayerSingleInput or LayerMultiInput.
+ *
+ * #[layer(name = ..., input = "single" | "multi")] is required, the name should be the same
+ * as the Layer::type_name of the builder struct. Fields of Box<dyn Activation> should be
+ * marked with #[activation], all other fields need to be serde serializable.
+ *
+ * LayerBase::init is empty unless #[layer(init = ...)] names a fn(&mut Self) to call instead,
+ * ex.: #[layer(init = Self::setup)]
+ */
+#[proc_macro_derive(LayerImpl, attributes(layer, activation))]
+pub fn derive_layer_impl(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    return layer::expand(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into();
+}
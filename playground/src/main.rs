@@ -1,4 +1,5 @@
 pub mod tests {
+    pub mod derive_tests;
     pub mod speed_tests;
     pub mod test;
 }
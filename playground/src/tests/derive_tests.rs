@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use neurotick::{
+        activation::abs::{Activation, ActivationFn},
+        builder::builder::ModelBuilder,
+        layer::{
+            abs::{Layer, LayerBase, LayerImpl, LayerPropagateEnum, LayerRef, LayerSingleInput},
+            input::Input,
+        },
+        map,
+        matrix::{
+            meta::{node::LayerType, shape::Shape},
+            nmatrix::NDMatrix,
+        },
+        serial::{model_reader::ModelReader, model_serial::ModelSerialized},
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Serialize, Deserialize, Debug, Activation)]
+    #[activation(name = "TestShift")]
+    struct Shift {
+        delta: f32,
+    }
+
+    impl ActivationFn for Shift {
+        fn activate(&self, array: &NDMatrix) -> NDMatrix {
+            let data = array.values.map(|f| f + self.delta);
+            return NDMatrix::with(array.width, array.height, data);
+        }
+    }
+
+    struct Scale {
+        parent: LayerRef,
+        factor: f32,
+        activation: Box<dyn Activation>,
+    }
+
+    impl Scale {
+        const NAME: &str = "TestScale";
+    }
+
+    impl Layer for Scale {
+        fn type_name(&self) -> &'static str {
+            return Self::NAME;
+        }
+
+        fn get_shape(&self) -> (Shape, Shape) {
+            return self.parent.get_shape();
+        }
+
+        fn get_node(&self) -> LayerType {
+            return LayerType::SingleParent(self.parent.clone());
+        }
+
+        fn create_instance(&self, id: String) -> LayerPropagateEnum {
+            let instance = ScaleImpl {
+                id,
+                factor: self.factor,
+                activation: self.activation.act_clone(),
+            };
+            return LayerPropagateEnum::SingleInput(Box::new(instance));
+        }
+    }
+
+    #[derive(LayerImpl)]
+    #[layer(name = Scale::NAME, input = "single")]
+    struct ScaleImpl {
+        #[allow(dead_code)]
+        id: String,
+        factor: f32,
+        #[activation]
+        activation: Box<dyn Activation>,
+    }
+
+    impl LayerSingleInput for ScaleImpl {
+        fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+            let data = input.values.map(|f| f * self.factor);
+            let scaled = NDMatrix::with(input.width, input.height, data);
+            return self.activation.apply(&scaled);
+        }
+    }
+
+    #[test]
+    fn test_derived_roundtrip() {
+        let input = Input::new(Shape::Const(3), Shape::Repeat);
+        let scale = LayerRef::pin(Scale {
+            parent: input.clone(),
+            factor: 2.0,
+            activation: Box::new(Shift { delta: 0.5 }),
+        });
+        let model = ModelBuilder::from_straight(input, scale).build();
+
+        let input_data: HashMap<String, NDMatrix> = map! {
+            ModelBuilder::SINGLE_IO.to_owned() => NDMatrix::constant(3, 2, 1.0),
+        };
+        let pre = model.propagate_single_input(NDMatrix::constant(3, 2, 1.0));
+        let json = model.to_json();
+
+        let serialized: ModelSerialized = serde_json::from_str(&json).unwrap();
+        let restored = serialized.build_model(&ModelReader::default());
+        let post = restored.propagate(&input_data);
+
+        let output = post.get(ModelBuilder::SINGLE_IO).unwrap();
+        assert_eq!(
+            pre.get(ModelBuilder::SINGLE_IO).unwrap().values,
+            output.values
+        );
+        assert!(output.iter_all().all(|f| f.eq(&2.5)));
+    }
+
+    #[derive(LayerImpl)]
+    #[layer(name = "TestReady", input = "single", init = Self::setup)]
+    struct ReadyImpl {
+        ready: bool,
+    }
+
+    impl ReadyImpl {
+        fn setup(&mut self) {
+            self.ready = true;
+        }
+    }
+
+    impl LayerSingleInput for ReadyImpl {
+        fn propagate(&self, input: &NDMatrix) -> NDMatrix {
+            return input.clone();
+        }
+    }
+
+    #[test]
+    fn test_derived_init() {
+        let mut ready = ReadyImpl { ready: false };
+        ready.init();
+        assert!(ready.ready);
+    }
+}